@@ -0,0 +1,53 @@
+//! Compares `BitSlice::get_block` on block-aligned and unaligned slices.
+//!
+//! Run with `cargo bench --bench bit_slice`.
+
+extern crate succinct;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use succinct::BitVec;
+use succinct::bit_vec::BitSlice;
+
+const BLOCKS: usize = 1 << 20;
+const ROUNDS: usize = 50;
+
+fn random_blocks() -> Vec<u64> {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    (0 .. BLOCKS).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }).collect()
+}
+
+fn time<F: FnMut() -> u64>(name: &str, mut f: F) -> u64 {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0 .. ROUNDS {
+        result = f();
+    }
+    let elapsed = start.elapsed();
+    let per_block = elapsed.as_secs_f64() * 1e9 / (ROUNDS * BLOCKS) as f64;
+    println!("{:>12}: {:?} total, {:.3} ns/block", name, elapsed, per_block);
+    result
+}
+
+fn sum_blocks<Bits: BitVec<Block = u64>>(bits: &Bits) -> u64 {
+    (0 .. bits.block_len())
+        .map(|i| bits.get_block(i).count_ones() as u64)
+        .sum()
+}
+
+fn main() {
+    let blocks = black_box(random_blocks());
+    let len = 64 * (BLOCKS as u64 - 1);
+
+    let aligned = BitSlice::new(&*blocks, 64 .. 64 + len);
+    let unaligned = BitSlice::new(&*blocks, 37 .. 37 + len);
+
+    time("aligned", || sum_blocks(&aligned));
+    time("unaligned", || sum_blocks(&unaligned));
+}
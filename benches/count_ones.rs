@@ -0,0 +1,45 @@
+//! Compares `BlockType::count_ones_slice` against a per-block loop.
+//!
+//! Run with `cargo bench --bench count_ones`.
+
+extern crate succinct;
+
+use std::time::Instant;
+
+use succinct::storage::BlockType;
+
+const BLOCKS: usize = 1 << 20;
+const ROUNDS: usize = 50;
+
+fn random_blocks() -> Vec<u64> {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    (0 .. BLOCKS).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }).collect()
+}
+
+fn time<F: FnMut() -> u64>(name: &str, mut f: F) -> u64 {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0 .. ROUNDS {
+        result = f();
+    }
+    let elapsed = start.elapsed();
+    let per_block = elapsed.as_secs_f64() * 1e9 / (ROUNDS * BLOCKS) as f64;
+    println!("{:>12}: {:?} total, {:.3} ns/block", name, elapsed, per_block);
+    result
+}
+
+fn main() {
+    let blocks = random_blocks();
+
+    let scalar = time("per-block", || {
+        blocks.iter().map(|block| block.count_ones() as u64).sum()
+    });
+    let slice = time("slice", || u64::count_ones_slice(&blocks));
+
+    assert_eq!(scalar, slice);
+}
@@ -0,0 +1,48 @@
+//! Compares rank queries on `JacobsonRank`, `Rank9` and
+//! `InterleavedRank` at random positions.
+//!
+//! Run with `cargo bench --bench rank`.
+
+extern crate succinct;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use succinct::{BitRankSupport, BitVector, JacobsonRank, Rank9};
+use succinct::rank::InterleavedRank;
+
+// 2^28 bits, 32 MiB, so that most queries miss the cache.
+const BLOCKS: usize = 1 << 22;
+const QUERIES: usize = 1 << 22;
+
+fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn time<Rank: BitRankSupport>(name: &str, rank: &Rank, positions: &[u64]) -> u64 {
+    let start = Instant::now();
+    let result = positions.iter().map(|&i| rank.rank1(i)).fold(0, u64::wrapping_add);
+    let elapsed = start.elapsed();
+    let per_query = elapsed.as_secs_f64() * 1e9 / positions.len() as f64;
+    println!("{:>12}: {:?} total, {:.2} ns/query", name, elapsed, per_query);
+    black_box(result)
+}
+
+fn main() {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let blocks: Vec<u64> = (0 .. BLOCKS).map(|_| xorshift(&mut state)).collect();
+    let bits = BitVector::from(blocks);
+    let positions: Vec<u64> = (0 .. QUERIES)
+        .map(|_| xorshift(&mut state) % (64 * BLOCKS as u64))
+        .collect();
+
+    let jacobson = time("jacobson", &JacobsonRank::new(&bits), &positions);
+    let rank9 = time("rank9", &Rank9::new(&bits), &positions);
+    let interleaved = time("interleaved", &InterleavedRank::new(&bits), &positions);
+
+    assert_eq!(jacobson, rank9);
+    assert_eq!(jacobson, interleaved);
+}
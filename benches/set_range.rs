@@ -0,0 +1,42 @@
+//! Compares `BitVecMut::set_range` with setting each bit in turn.
+//!
+//! Run with `cargo bench --bench set_range`.
+
+extern crate succinct;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use succinct::{BitVec, BitVecMut, BitVector};
+
+const BITS: u64 = 1 << 24;
+const ROUNDS: usize = 20;
+
+fn time<F: FnMut() -> bool>(name: &str, mut f: F) -> bool {
+    let start = Instant::now();
+    let mut result = false;
+    for _ in 0 .. ROUNDS {
+        result = f();
+    }
+    let elapsed = start.elapsed();
+    let per_bit = elapsed.as_secs_f64() * 1e9 / (ROUNDS as u64 * BITS) as f64;
+    println!("{:>12}: {:?} total, {:.4} ns/bit", name, elapsed, per_bit);
+    result
+}
+
+fn main() {
+    let mut bits: BitVector<u64> = BitVector::with_fill(BITS + 64, false);
+    let range = black_box(37 .. 37 + BITS);
+
+    time("set_range", || {
+        bits.set_range(range.clone(), true);
+        bits.set_range(range.clone(), false);
+        bits.get_bit(range.start)
+    });
+
+    time("per-bit", || {
+        for i in range.clone() { bits.set_bit(i, true); }
+        for i in range.clone() { bits.set_bit(i, false); }
+        bits.get_bit(range.start)
+    });
+}
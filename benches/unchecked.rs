@@ -0,0 +1,52 @@
+//! Compares `BitVec::get_bit` against `get_bit_unchecked` in a loop
+//! whose bounds are already known.
+//!
+//! Run with `cargo bench --bench unchecked`.
+
+extern crate succinct;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use succinct::{BitVec, BitVector};
+
+const BITS: u64 = 1 << 24;
+const ROUNDS: usize = 20;
+
+fn random_bits() -> BitVector<u64> {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let blocks: Vec<u64> = (0 .. BITS / 64).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    }).collect();
+    BitVector::from(blocks)
+}
+
+fn time<F: FnMut() -> u64>(name: &str, mut f: F) -> u64 {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0 .. ROUNDS {
+        result = f();
+    }
+    let elapsed = start.elapsed();
+    let per_bit = elapsed.as_secs_f64() * 1e9 / (ROUNDS as u64 * BITS) as f64;
+    println!("{:>12}: {:?} total, {:.3} ns/bit", name, elapsed, per_bit);
+    result
+}
+
+fn main() {
+    let bits = black_box(random_bits());
+
+    let checked = time("checked", || {
+        (0 .. bits.bit_len()).filter(|&i| bits.get_bit(i)).count() as u64
+    });
+    let unchecked = time("unchecked", || {
+        (0 .. bits.bit_len())
+            .filter(|&i| unsafe { bits.get_bit_unchecked(i) })
+            .count() as u64
+    });
+
+    assert_eq!(checked, unchecked);
+}
@@ -0,0 +1,173 @@
+use std::mem;
+
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A fixed-length array of `N` bits stored inline in `BLOCKS` blocks.
+///
+/// Useful for small masks and flag sets that shouldn’t allocate. The
+/// number of blocks must be exactly `ceil(N / Block::nbits())`; stable
+/// Rust can’t compute an array length from `N`, so it is a parameter,
+/// and `BitArray::new` fails to compile if it is wrong. As with
+/// `BitVector`, the bits past the end of the last block are always zero.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitVec, BitVecMut};
+/// use succinct::bit_vec::BitArray;
+///
+/// let mut flags: BitArray<100, u32, 4> = BitArray::new();
+/// flags.set_bit(99, true);
+/// assert!(flags.get_bit(99));
+/// assert_eq!(100, flags.bit_len());
+/// ```
+///
+/// A block count that doesn’t match is rejected:
+///
+/// ```compile_fail
+/// use succinct::bit_vec::BitArray;
+///
+/// let flags: BitArray<100, u32, 3> = BitArray::new();
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BitArray<const N: usize, Block: BlockType, const BLOCKS: usize> {
+    blocks: [Block; BLOCKS],
+}
+
+impl<const N: usize, Block: BlockType, const BLOCKS: usize>
+    BitArray<N, Block, BLOCKS>
+{
+    // Evaluated when `new` is instantiated, rejecting a `BLOCKS` that
+    // doesn’t match `N`.
+    const BLOCKS_MATCH: () = {
+        let block_bits = 8 * mem::size_of::<Block>();
+        assert!(BLOCKS == N.div_ceil(block_bits),
+                "BitArray: BLOCKS must be ceil(N / Block::nbits())");
+    };
+
+    /// Creates a new bit array with every bit 0.
+    pub fn new() -> Self {
+        let () = Self::BLOCKS_MATCH;
+        BitArray { blocks: [Block::zero(); BLOCKS] }
+    }
+
+    /// Creates a new bit array with every bit set to `value`.
+    pub fn with_fill(value: bool) -> Self {
+        let mut result = Self::new();
+        if value {
+            for i in 0 .. BLOCKS {
+                result.set_block(i, !Block::zero());
+            }
+        }
+        result
+    }
+
+    /// The underlying blocks.
+    pub fn as_blocks(&self) -> &[Block; BLOCKS] {
+        &self.blocks
+    }
+}
+
+impl<const N: usize, Block: BlockType, const BLOCKS: usize> Default
+    for BitArray<N, Block, BLOCKS>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, Block: BlockType, const BLOCKS: usize> BitVec
+    for BitArray<N, Block, BLOCKS>
+{
+    type Block = Block;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        N as u64
+    }
+
+    #[inline]
+    fn block_len(&self) -> usize {
+        BLOCKS
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> Block {
+        self.blocks[position]
+    }
+}
+
+impl<const N: usize, Block: BlockType, const BLOCKS: usize> BitVecMut
+    for BitArray<N, Block, BLOCKS>
+{
+    #[inline]
+    fn set_block(&mut self, position: usize, value: Block) {
+        self.blocks[position] = if position + 1 == BLOCKS {
+            value & Block::last_block_mask(N as u64)
+        } else {
+            value
+        };
+    }
+}
+
+impl<const N: usize, Block: BlockType, const BLOCKS: usize> SpaceUsage
+    for BitArray<N, Block, BLOCKS>
+{
+    fn is_stack_only() -> bool { true }
+    fn heap_bytes(&self) -> usize { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+    use space_usage::SpaceUsage;
+
+    type Flags = BitArray<100, u32, 4>;
+
+    #[test]
+    fn set_get() {
+        let mut flags = Flags::new();
+        assert_eq!(100, flags.bit_len());
+        assert_eq!(4, flags.block_len());
+        assert!((0 .. 100).all(|i| !flags.get_bit(i)));
+
+        for i in (0 .. 100).filter(|i| i % 7 == 3) {
+            flags.set_bit(i, true);
+        }
+        for i in 0 .. 100 {
+            assert_eq!(i % 7 == 3, flags.get_bit(i));
+        }
+
+        flags.set_bit(3, false);
+        assert!(!flags.get_bit(3));
+        assert!(flags.get_bit(10));
+    }
+
+    #[test]
+    fn padding_stays_zero() {
+        let mut flags = Flags::with_fill(true);
+        assert_eq!(0xF, flags.get_block(3));
+        assert_eq!(100, flags.ones().count());
+
+        flags.set_block(3, !0);
+        assert_eq!(0xF, flags.get_block(3));
+        assert_eq!(Flags::with_fill(true), flags);
+    }
+
+    #[test]
+    fn space() {
+        assert!(Flags::is_stack_only());
+        assert_eq!(0, Flags::new().heap_bytes());
+        assert_eq!(16, Flags::new().total_bytes());
+    }
+
+    #[test]
+    fn exact_blocks() {
+        let mut bits: BitArray<64, u32, 2> = BitArray::default();
+        bits.set_block(1, !0);
+        assert_eq!(!0, bits.get_block(1));
+        assert!(bits.get_bit(63));
+    }
+}
@@ -1,10 +1,32 @@
+use std::cmp;
 use std::ops::{Range, RangeTo, RangeFrom, RangeFull};
 
+use num_traits::PrimInt;
+
 use bit_vec::traits::*;
+use error::{Error, Result};
 use space_usage::SpaceUsage;
-use storage::BlockType;
+use storage::{Address, BlockType};
 
 /// A borrowed slice of a bit vector.
+///
+/// A slice is itself a `BitVec` whose blocks are zero past its end, so
+/// rank and select structures can be built directly over part of a
+/// larger vector.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitRankSupport, BitVector, JacobsonRank};
+/// use succinct::bit_vec::BitSlice;
+///
+/// let bits: BitVector<u64> = (0 .. 1000).map(|i| i % 3 == 0).collect();
+/// let rank = JacobsonRank::new(BitSlice::new(&bits, 100 .. 400));
+///
+/// // Positions 102, 105, ..., 150 of the base are multiples of 3.
+/// assert_eq!(17, rank.rank1(50));
+/// assert_eq!(100, rank.total_ones());
+/// ```
 #[derive(Clone, Copy, Debug)]
 pub struct BitSlice<'a, Base: 'a + BitVec + ?Sized> {
     data: &'a Base,
@@ -13,6 +35,10 @@ pub struct BitSlice<'a, Base: 'a + BitVec + ?Sized> {
 }
 
 /// A borrowed, mutable slice of a bit vector.
+///
+/// Blocks are read as efficiently as through a `BitSlice`, so rank and
+/// select structures can be built over `&slice` to query a window that
+/// is also being modified. Rebuild them after writing through the slice.
 #[derive(Debug)]
 pub struct BitSliceMut<'a, Base: 'a + BitVecMut + ?Sized> {
     data: &'a mut Base,
@@ -24,11 +50,24 @@ impl<'a, Base: 'a + BitVec + ?Sized> BitSlice<'a, Base> {
     /// Slices base to the specified range.
     pub fn new<R: IntoRange<u64>>(base: &'a Base, range: R) -> Self {
         let range = range.into_range(0, base.bit_len());
+        assert!(range.start <= range.end, "BitSlice::new: bad range");
         assert!(range.end <= base.bit_len(), "BitSlice::new: out of bounds");
         BitSlice {
             data: base,
             start: range.start,
-            len: range.end.saturating_sub(range.start),
+            len: range.end - range.start,
+        }
+    }
+
+    /// Slices base to the specified range, or fails with
+    /// `Error::OutOfBounds` if the range is backwards or extends past
+    /// the end of `base`.
+    pub fn try_new<R: IntoRange<u64>>(base: &'a Base, range: R) -> Result<Self> {
+        let range = range.into_range(0, base.bit_len());
+        if range.start <= range.end && range.end <= base.bit_len() {
+            Ok(BitSlice::new(base, range))
+        } else {
+            Err(Error::OutOfBounds)
         }
     }
 
@@ -38,24 +77,65 @@ impl<'a, Base: 'a + BitVec + ?Sized> BitSlice<'a, Base> {
     /// indirection.
     pub fn slice<R: IntoRange<u64>>(&self, range: R) -> Self {
         let range = range.into_range(0, self.len);
+        assert!(range.start <= range.end, "BitSlice::slice: bad range");
         assert!(range.end <= self.len, "BitSlice::slice: out of bounds");
         BitSlice {
             data: self.data,
             start: self.start + range.start,
-            len: range.end.saturating_sub(range.start),
+            len: range.end - range.start,
         }
     }
+
+    /// The number of 1 bits in the slice.
+    ///
+    /// Counts the base’s blocks directly, masking off the bits before
+    /// the start of the slice in the first block and after its end in
+    /// the last.
+    pub fn count_ones(&self) -> u64 {
+        if self.len == 0 { return 0; }
+
+        let first = Address::new::<Base::Block>(self.start);
+        let last = Address::new::<Base::Block>(self.start + self.len - 1);
+
+        let mut result = 0;
+        for i in first.block_index ..= last.block_index {
+            let mut block = self.data.get_block(i);
+            if i == first.block_index {
+                block = block & !Base::Block::low_mask(first.bit_offset);
+            }
+            if i == last.block_index {
+                block = block & Base::Block::low_mask(last.bit_offset + 1);
+            }
+            result += PrimInt::count_ones(block) as u64;
+        }
+
+        result
+    }
 }
 
 impl<'a, Base: 'a + BitVecMut + ?Sized> BitSliceMut<'a, Base> {
     /// Slices base to the specified range.
     pub fn new<R: IntoRange<u64>>(base: &'a mut Base, range: R) -> Self {
         let range = range.into_range(0, base.bit_len());
-        assert!(range.end <= base.bit_len(), "BitSlice::new: out of bounds");
+        assert!(range.start <= range.end, "BitSliceMut::new: bad range");
+        assert!(range.end <= base.bit_len(), "BitSliceMut::new: out of bounds");
         BitSliceMut {
             data: base,
             start: range.start,
-            len: range.end.saturating_sub(range.start),
+            len: range.end - range.start,
+        }
+    }
+
+    /// Slices base to the specified range, or fails with
+    /// `Error::OutOfBounds` if the range is backwards or extends past
+    /// the end of `base`.
+    pub fn try_new<R: IntoRange<u64>>(base: &'a mut Base, range: R)
+                                      -> Result<Self> {
+        let range = range.into_range(0, base.bit_len());
+        if range.start <= range.end && range.end <= base.bit_len() {
+            Ok(BitSliceMut::new(base, range))
+        } else {
+            Err(Error::OutOfBounds)
         }
     }
 
@@ -66,26 +146,49 @@ impl<'a, Base: 'a + BitVecMut + ?Sized> BitSliceMut<'a, Base> {
     pub fn slice_mut<R: IntoRange<u64>>(&mut self, range: R)
                                         -> BitSliceMut<Base> {
         let range = range.into_range(0, self.len);
+        assert!(range.start <= range.end, "BitSliceMut::slice_mut: bad range");
         assert!(range.end <= self.len, "BitSlice::slice: out of bounds");
         BitSliceMut {
             data: self.data,
             start: self.start + range.start,
-            len: range.end.saturating_sub(range.start),
+            len: range.end - range.start,
         }
     }
 
     /// Slices this slice into an immutable subslice.
     pub fn slice<R: IntoRange<u64>>(&self, range: R) -> BitSlice<Base> {
         let range = range.into_range(0, self.len);
+        assert!(range.start <= range.end, "BitSliceMut::slice: bad range");
         assert!(range.end <= self.len, "BitSlice::slice: out of bounds");
         BitSlice {
             data: self.data,
             start: self.start + range.start,
-            len: range.end.saturating_sub(range.start),
+            len: range.end - range.start,
         }
     }
 }
 
+// Fetches the `position`th block of the window `start .. start + len` of
+// `data`. The base's `get_bits` reads the (at most two) underlying blocks
+// that the window block overlaps and stitches them together; for the final
+// block of the window we only ask for the remaining bits, so everything
+// past `len` comes back zero.
+//
+// When `start` is block-aligned, each window block is a base block, so
+// full blocks are copied directly.
+fn get_slice_block<Base: BitVec + ?Sized>(data: &Base, start: u64, len: u64,
+                                          position: usize) -> Base::Block {
+    let offset = Base::Block::mul_nbits(position);
+    assert!(offset < len, "BitSlice::get_block: out of bounds");
+
+    let count = cmp::min(Base::Block::nbits() as u64, len - offset);
+    if count == Base::Block::nbits() as u64 && Base::Block::mod_nbits(start) == 0 {
+        return data.get_block(Base::Block::div_nbits(start) + position);
+    }
+
+    data.get_bits(start + offset, count as usize)
+}
+
 impl<'a, Base: 'a + BitVec + ?Sized> BitVec for BitSlice<'a, Base> {
     type Block = Base::Block;
 
@@ -108,7 +211,7 @@ impl<'a, Base: 'a + BitVec + ?Sized> BitVec for BitSlice<'a, Base> {
     }
 
     fn get_block(&self, position: usize) -> Self::Block {
-        self.get_bits(Self::Block::mul_nbits(position), Self::Block::nbits())
+        get_slice_block(self.data, self.start, self.len, position)
     }
 }
 
@@ -134,7 +237,7 @@ impl<'a, Base: 'a + BitVecMut + ?Sized> BitVec for BitSliceMut<'a, Base> {
     }
 
     fn get_block(&self, position: usize) -> Self::Block {
-        self.get_bits(Self::Block::mul_nbits(position), Self::Block::nbits())
+        get_slice_block(self.data, self.start, self.len, position)
     }
 }
 
@@ -153,8 +256,22 @@ impl<'a, Base: 'a + BitVecMut + ?Sized> BitVecMut for BitSliceMut<'a, Base> {
     }
 
     fn set_block(&mut self, position: usize, value: Self::Block) {
-        self.set_bits(Self::Block::mul_nbits(position),
-                      Self::Block::nbits(), value);
+        let offset = Self::Block::mul_nbits(position);
+        assert!(offset < self.len, "BitSliceMut::set_block: out of bounds");
+
+        // As in `get_slice_block`, full blocks of an aligned slice are
+        // written directly. Otherwise the base's `set_bits` does the
+        // read-modify-write on the (at most two) underlying blocks, and
+        // limiting `count` keeps the final block from writing past `len`.
+        let count = cmp::min(Self::Block::nbits() as u64, self.len - offset);
+        if count == Self::Block::nbits() as u64
+                && Self::Block::mod_nbits(self.start) == 0 {
+            let index = Self::Block::div_nbits(self.start) + position;
+            self.data.set_block(index, value);
+            return;
+        }
+
+        self.data.set_bits(self.start + offset, count as usize, value);
     }
 }
 
@@ -204,3 +321,481 @@ impl<T> IntoRange<T> for RangeFrom<T> {
 impl<T> IntoRange<T> for RangeFull {
     fn into_range(self, start: T, end: T) -> Range<T> { start .. end }
 }
+
+#[cfg(test)]
+mod test {
+    use std::cmp;
+
+    use bit_vec::*;
+    use error::Error;
+    use internal::test_util::Lcg;
+    use storage::BlockType;
+    use quickcheck::quickcheck;
+
+    // Assembles block `position` of `bits` one bit at a time.
+    fn reference_block<Bits: BitVec<Block = u32>>(bits: &Bits, position: usize)
+                                                  -> u32 {
+        let start = position as u64 * 32;
+        let mut result = 0;
+        for i in 0 .. 32 {
+            if start + i < bits.bit_len() && bits.get_bit(start + i) {
+                result |= 1 << i;
+            }
+        }
+        result
+    }
+
+    fn base() -> Vec<u32> {
+        vec![ 0xDEAD_BEEF, 0x0123_4567, 0xFFFF_0000, 0x8000_0001, 0xA5A5_5A5A ]
+    }
+
+    #[test]
+    fn get_block_unaligned() {
+        let base = base();
+
+        for start in 0 .. 40 {
+            for end in start .. base.bit_len() + 1 {
+                let slice = BitSlice::new(&*base, start .. end);
+                for i in 0 .. slice.block_len() {
+                    assert_eq!(reference_block(&slice, i), slice.get_block(i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn get_block_mut_unaligned() {
+        let mut base = base();
+        let len = base.bit_len();
+
+        for start in 0 .. 40 {
+            let slice = BitSliceMut::new(&mut base, start .. len - 3);
+            for i in 0 .. slice.block_len() {
+                assert_eq!(reference_block(&slice, i), slice.get_block(i));
+            }
+        }
+    }
+
+    #[test]
+    fn get_block_straddles() {
+        let base = vec![ 0xFFFF_0000u32, 0x0000_FFFF ];
+        let slice = BitSlice::new(&*base, 16 .. 48);
+        assert_eq!(1, slice.block_len());
+        assert_eq!(!0, slice.get_block(0));
+    }
+
+    #[test]
+    fn get_block_partial_last() {
+        let base = vec![ !0u32; 3 ];
+        let slice = BitSlice::new(&*base, 5 .. 75);
+        assert_eq!(3, slice.block_len());
+        assert_eq!(!0, slice.get_block(0));
+        assert_eq!(!0, slice.get_block(1));
+        assert_eq!(0b111111, slice.get_block(2));
+    }
+
+    #[test]
+    fn set_block_unaligned() {
+        let patterns = [ 0xDEAD_BEEFu32, 0x0F0F_F0F0, !0, 0, 0x8000_0001 ];
+
+        for start in 0 .. 40 {
+            for &end in &[ 40, 64, 97, 128, 157, 160 ] {
+                if end < start { continue; }
+
+                let original = base();
+                let mut data = original.clone();
+
+                {
+                    let mut slice = BitSliceMut::new(&mut data, start .. end);
+                    for i in 0 .. slice.block_len() {
+                        slice.set_block(i, patterns[i % patterns.len()]);
+                    }
+
+                    for i in 0 .. slice.block_len() {
+                        let limit = cmp::min(32, slice.bit_len() - i as u64 * 32);
+                        let mask = u32::low_mask(limit as usize);
+                        assert_eq!(patterns[i % patterns.len()] & mask,
+                                   slice.get_block(i));
+                    }
+                }
+
+                for i in (0 .. start).chain(end .. original.bit_len()) {
+                    assert_eq!(original.get_bit(i), data.get_bit(i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_block_partial_last() {
+        let mut data = vec![ 0u32; 3 ];
+
+        BitSliceMut::new(&mut data, 5 .. 75).set_block(2, !0);
+        assert_eq!(vec![ 0, 0, 0b111111 << 5 ], data);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_block_oob() {
+        let mut data = base();
+        BitSliceMut::new(&mut data, 5 .. 69).set_block(2, 0);
+    }
+
+    #[test]
+    fn new_full_length() {
+        let data = base();
+        let slice = BitSlice::new(&*data, 0 .. data.bit_len());
+        assert_eq!(data.bit_len(), slice.bit_len());
+        assert_eq!(data.get_bit(159), slice.get_bit(159));
+    }
+
+    #[test]
+    fn new_last_bit() {
+        let data = vec![ 0x8000_0000u32 ];
+        let slice = BitSlice::new(&*data, 31 .. 32);
+        assert_eq!(1, slice.bit_len());
+        assert!(slice.get_bit(0));
+    }
+
+    #[test]
+    fn new_empty() {
+        let data = base();
+        for n in 0 .. data.bit_len() + 1 {
+            let slice = BitSlice::new(&*data, n .. n);
+            assert_eq!(0, slice.bit_len());
+            assert_eq!(0, slice.block_len());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_backwards() {
+        let data = base();
+        let (start, end) = (9, 8);
+        BitSlice::new(&*data, start .. end);
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_oob() {
+        let data = base();
+        BitSlice::new(&*data, 0 .. 161);
+    }
+
+    #[test]
+    fn slice_slice() {
+        let data = base();
+        let outer = BitSlice::new(&*data, 7 .. 150);
+        let inner = outer.slice(10 .. 100);
+        let innermost = inner.slice(3 .. 60);
+
+        assert_eq!(57, innermost.bit_len());
+        for i in 0 .. innermost.bit_len() {
+            assert_eq!(data.get_bit(20 + i), innermost.get_bit(i));
+        }
+
+        let tail = inner.slice(50 ..);
+        assert_eq!(40, tail.bit_len());
+        assert_eq!(data.get_bit(67), tail.get_bit(0));
+        assert_eq!(data.get_bit(106), tail.get_bit(39));
+    }
+
+    #[test]
+    fn slice_mut_slice_mut() {
+        let mut data = vec![ 0u32; 5 ];
+
+        {
+            let mut outer = BitSliceMut::new(&mut data, 7 .. 150);
+            let mut inner = outer.slice_mut(10 .. 100);
+            let mut innermost = inner.slice_mut(3 .. 60);
+            innermost.set_bit(0, true);
+            innermost.set_bit(56, true);
+        }
+
+        for i in 0 .. data.bit_len() {
+            assert_eq!(i == 20 || i == 76, data.get_bit(i));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_oob() {
+        let data = base();
+        let outer = BitSlice::new(&*data, 7 .. 50);
+        outer.slice(10 .. 44);
+    }
+
+    #[test]
+    fn try_get_block() {
+        let base = base();
+        let slice = BitSlice::new(&*base, 5 .. 69);
+        assert_eq!(2, slice.block_len());
+        assert_eq!(Some(slice.get_block(1)), slice.try_get_block(1));
+        assert_eq!(None, slice.try_get_block(2));
+        assert_eq!(None, BitSlice::new(&*base, 7 .. 7).try_get_block(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_block_oob() {
+        let base = base();
+        let slice = BitSlice::new(&*base, 5 .. 69);
+        slice.get_block(2);
+    }
+
+    #[test]
+    fn space_usage() {
+        use std::mem::size_of;
+        use space_usage::SpaceUsage;
+
+        let mut v: Vec<u32> = vec![0; 4];
+        {
+            let slice = BitSlice::new(&v[..], 3 .. 70);
+            assert!(BitSlice::<[u32]>::is_stack_only());
+            assert_eq!(0, slice.heap_bytes());
+            assert_eq!(size_of::<BitSlice<[u32]>>(), slice.total_bytes());
+        }
+
+        let slice = BitSliceMut::new(&mut v[..], 3 .. 70);
+        assert_eq!(0, slice.heap_bytes());
+        assert_eq!(size_of::<BitSliceMut<[u32]>>(), slice.total_bytes());
+    }
+
+    #[test]
+    fn rank_over_slice() {
+        use rank::{BitRankSupport, JacobsonRank};
+
+        let base: BitVector<u32> = (0 .. 2000u64)
+            .map(|i| (i * i + 7 * i) % 11 < 4)
+            .collect();
+
+        for &(start, end) in &[(0, 2000), (64, 1024), (5, 1300), (31, 33), (999, 1999)] {
+            let slice = BitSlice::new(&base, start .. end);
+            let copy: BitVector<u32> = slice.bits().collect();
+
+            let over_slice = JacobsonRank::new(slice);
+            let over_copy = JacobsonRank::new(copy);
+            for i in 0 .. end - start {
+                assert_eq!(over_copy.rank1(i), over_slice.rank1(i));
+            }
+            assert_eq!(over_copy.total_ones(), over_slice.total_ones());
+        }
+    }
+
+    #[test]
+    fn aligned_matches_unaligned() {
+        let base = base();
+
+        // The same 100 bits, starting at a block boundary of one copy and
+        // at an offset of 7 in another.
+        let mut shifted = vec![ 0u32; 5 ];
+        shifted.set_bits(0, 7, 0b1010011);
+        for i in 0 .. 100 {
+            shifted.set_bit(7 + i, base.get_bit(32 + i));
+        }
+
+        let aligned = BitSlice::new(&*base, 32 .. 132);
+        let unaligned = BitSlice::new(&*shifted, 7 .. 107);
+        for i in 0 .. aligned.block_len() {
+            assert_eq!(reference_block(&aligned, i), aligned.get_block(i));
+            assert_eq!(unaligned.get_block(i), aligned.get_block(i));
+        }
+
+        let mut aligned_base = base.clone();
+        let mut unaligned_base = shifted.clone();
+        {
+            let mut aligned = BitSliceMut::new(&mut *aligned_base, 32 .. 132);
+            let mut unaligned = BitSliceMut::new(&mut *unaligned_base, 7 .. 107);
+            for i in 0 .. aligned.block_len() {
+                aligned.set_block(i, 0x1234_5678 * (i as u32 + 1));
+                unaligned.set_block(i, 0x1234_5678 * (i as u32 + 1));
+            }
+            for i in 0 .. aligned.block_len() {
+                assert_eq!(unaligned.get_block(i), aligned.get_block(i));
+            }
+        }
+
+        // Bits outside the slices are untouched.
+        assert_eq!(base[0], aligned_base[0]);
+        assert_eq!(base[4] >> 4, aligned_base[4] >> 4);
+        assert_eq!(0b1010011, unaligned_base[0] & 0x7F);
+        assert_eq!(shifted[3] >> 11, unaligned_base[3] >> 11);
+    }
+
+    #[test]
+    fn rank_over_slice_mut() {
+        use rank::{BitRankSupport, JacobsonRank};
+
+        let mut data = BitVector::<u16>::from_fn(3000, |i| i % 7 == 0 || i % 11 < 2);
+        for &(start, end) in &[(0, 3000), (13, 2900), (64, 1024), (5, 6)] {
+            let mut slice = BitSliceMut::new(&mut data, start .. end);
+            for i in (0 .. slice.bit_len()).step_by(13) {
+                slice.set_bit(i, i % 2 == 0);
+            }
+
+            let copy: BitVector<u16> = (0 .. slice.bit_len())
+                .map(|i| slice.get_bit(i)).collect();
+            let expected = JacobsonRank::new(&copy);
+            let rank = JacobsonRank::new(&slice);
+            assert_eq!(expected.total_ones(), rank.total_ones());
+            for i in 0 .. slice.bit_len() {
+                assert_eq!(expected.rank1(i), rank.rank1(i));
+            }
+        }
+    }
+
+    #[test]
+    fn count_ones() {
+        let base = base();
+        let total = base.iter().map(|block| block.count_ones() as u64).sum::<u64>();
+        assert_eq!(total, BitSlice::new(&base[..], ..).count_ones());
+
+        // Within a single block, across one boundary, and empty.
+        assert_eq!(3, BitSlice::new(&base[..], 4 .. 8).count_ones());
+        assert_eq!(8, BitSlice::new(&base[..], 28 .. 40).count_ones());
+        assert_eq!(0, BitSlice::new(&base[..], 17 .. 17).count_ones());
+        assert_eq!(0, BitSlice::new(&base[..], 160 ..).count_ones());
+    }
+
+    fn count_ones_prop(bits: Vec<bool>, start: usize, len: usize) -> bool {
+        let start = if bits.is_empty() { 0 } else { start % bits.len() };
+        let len = if bits.len() == start { 0 } else { len % (bits.len() - start) };
+        let vec: BitVector<u8> = bits.iter().cloned().collect();
+        let slice = BitSlice::new(&vec, start as u64 .. (start + len) as u64);
+
+        let expected = bits[start .. start + len].iter().filter(|&&bit| bit).count();
+        slice.count_ones() == expected as u64
+    }
+
+    #[test]
+    fn count_ones_qc() {
+        quickcheck(count_ones_prop as fn(Vec<bool>, usize, usize) -> bool);
+    }
+
+    // Model-based checks of the block stitching in `get_slice_block` and
+    // `BitSliceMut::set_block`: random bases, slice ranges and block
+    // writes, compared bit for bit against a `Vec<bool>`.
+    mod stitching {
+        use bit_vec::*;
+        use internal::test_util::random_bools;
+        use storage::BlockType;
+        use quickcheck::quickcheck;
+
+        // Block `position` of `model[start .. start + len]`, with 0s past
+        // the end.
+        fn model_block<Block: BlockType>(model: &[bool], start: usize, len: usize,
+                                         position: usize) -> Block {
+            let mut result = Block::zero();
+            for i in 0 .. Block::nbits() {
+                let offset = position * Block::nbits() + i;
+                if offset < len && model[start + offset] {
+                    result = result.with_bit(i, true);
+                }
+            }
+            result
+        }
+
+        fn check<Block: BlockType>(base: &[bool], start: usize, len: usize,
+                                   writes: &[(usize, u64)]) -> bool {
+            let mut model = base.to_vec();
+            let mut bits: BitVector<Block> = base.iter().cloned().collect();
+
+            {
+                let mut slice = BitSliceMut::new(&mut bits, start as u64 .. (start + len) as u64);
+                for &(position, value) in writes {
+                    if slice.block_len() == 0 { break; }
+                    let position = position % slice.block_len();
+                    let value = Block::from(value & Block::max_value().to_u64().unwrap())
+                                    .unwrap();
+                    slice.set_block(position, value);
+
+                    for i in 0 .. Block::nbits() {
+                        let offset = position * Block::nbits() + i;
+                        if offset < len {
+                            model[start + offset] = value.get_bit(i);
+                        }
+                    }
+                }
+
+                if slice.bit_len() != len as u64 { return false; }
+                for i in 0 .. slice.block_len() {
+                    if slice.get_block(i) != model_block(&model, start, len, i) {
+                        return false;
+                    }
+                }
+            }
+
+            let slice = BitSlice::new(&bits, start as u64 .. (start + len) as u64);
+            (0 .. slice.block_len()).all(|i| {
+                slice.get_block(i) == model_block(&model, start, len, i)
+            }) && (0 .. len).all(|i| slice.get_bit(i as u64) == model[start + i])
+               && (0 .. base.len()).all(|i| bits.get_bit(i as u64) == model[i])
+        }
+
+        // Reduces arbitrary inputs to a valid range of `base`.
+        fn range(base: &[bool], start: usize, len: usize) -> (usize, usize) {
+            let start = start % (base.len() + 1);
+            (start, len % (base.len() - start + 1))
+        }
+
+        fn stitching_prop(base: Vec<bool>, start: usize, len: usize,
+                          writes: Vec<(usize, u64)>) -> bool {
+            let (start, len) = range(&base, start, len);
+            check::<u8>(&base, start, len, &writes)
+                && check::<u32>(&base, start, len, &writes)
+                && check::<u64>(&base, start, len, &writes)
+        }
+
+        #[test]
+        fn stitching_qc() {
+            quickcheck(stitching_prop as fn(Vec<bool>, usize, usize, Vec<(usize, u64)>)
+                                           -> bool);
+        }
+
+        // Every start and length over a few blocks, so each alignment,
+        // partial final block and empty slice is covered regardless of
+        // what quickcheck generates.
+        #[test]
+        fn stitching_exhaustive() {
+            let base = random_bools(80, 128, 3);
+            let writes = [(0, !0), (1, 0), (2, 0x5A5A_5A5A_5A5A_5A5A), (0, 0x1234_5678)];
+
+            for start in 0 ..= base.len() {
+                for len in 0 ..= base.len() - start {
+                    assert!(check::<u8>(&base, start, len, &writes));
+                    assert!(check::<u32>(&base, start, len, &writes));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn count_ones_random() {
+        let mut lcg = Lcg::new(17);
+        let blocks: Vec<u64> = (0 .. 20).map(|_| lcg.next_u64()).collect();
+
+        for start in (0 .. 1280).step_by(37) {
+            for len in (0 .. 1280 - start).step_by(29) {
+                let slice = BitSlice::new(&blocks[..], start .. start + len);
+                let expected = (0 .. len).filter(|&i| slice.get_bit(i)).count();
+                assert_eq!(expected as u64, slice.count_ones());
+            }
+        }
+    }
+
+    #[test]
+    fn try_new() {
+        let mut base = base();
+        assert!(BitSlice::try_new(&*base, 3 .. 160).is_ok());
+        assert!(BitSlice::try_new(&*base, ..).is_ok());
+        assert_eq!(Some(Error::OutOfBounds),
+                   BitSlice::try_new(&*base, 3 .. 161).err());
+        let (start, end) = (10, 9);
+        assert_eq!(Some(Error::OutOfBounds),
+                   BitSlice::try_new(&*base, start .. end).err());
+
+        assert_eq!(5, BitSliceMut::try_new(&mut *base, 5 .. 10).unwrap().bit_len());
+        assert_eq!(Some(Error::OutOfBounds),
+                   BitSliceMut::try_new(&mut *base, 161 ..).err());
+    }
+}
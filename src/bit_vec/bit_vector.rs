@@ -1,17 +1,49 @@
+use std::cmp;
 use std::fmt;
+use std::io;
+use std::iter::FromIterator;
 
 #[cfg(target_pointer_width = "32")]
 use num::ToPrimitive;
 
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use serde::de::Error;
+
 use internal::vector_base::{VectorBase, self};
 use space_usage::SpaceUsage;
-use storage::BlockType;
+use storage::{Address, BlockType};
+use super::ext::BitVecExt;
 use super::traits::*;
 
 /// Uncompressed vector of bits.
+///
+/// Equality, ordering and hashing depend only on the bits, not on the
+/// capacity or on how the vector was built, since the bits past the end
+/// of the last block are always zero.
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct BitVector<Block: BlockType = usize>(VectorBase<Block>);
 
+/// The order in which the bits of each byte are assigned to bit
+/// positions by [`BitVector::from_bytes_with_order`] and
+/// [`BitVector::to_bytes_with_order`], or the bits of each block by
+/// [`OrderedBits`].
+///
+/// [`BitVector::from_bytes_with_order`]: struct.BitVector.html#method.from_bytes_with_order
+/// [`BitVector::to_bytes_with_order`]: struct.BitVector.html#method.to_bytes_with_order
+/// [`OrderedBits`]: struct.OrderedBits.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BitOrder {
+    /// The most significant bit of byte (or block) `i` comes first.
+    MsbFirst,
+    /// The least significant bit of byte (or block) `i` comes first,
+    /// matching how bits are laid out within blocks.
+    LsbFirst,
+}
+
 impl<Block: BlockType> BitVector<Block> {
     /// Creates a new, empty bit vector.
     pub fn new() -> Self {
@@ -57,6 +89,89 @@ impl<Block: BlockType> BitVector<Block> {
         BitVector(VectorBase::block_with_fill(1, block_len, value))
     }
 
+    /// Creates a bit vector of `len` bits with exactly the given
+    /// positions set.
+    ///
+    /// Positions may repeat and come in any order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any position is not less than `len`.
+    pub fn with_bits_set<I>(len: u64, positions: I) -> Self
+        where I: IntoIterator<Item = u64> {
+
+        let mut result = Self::with_fill(len, false);
+        for position in positions {
+            assert!(position < len, "BitVector::with_bits_set: out of bounds");
+            result.set_bit(position, true);
+        }
+        result
+    }
+
+    /// Creates a bit vector of `len` bits with positions `0`, `n`, `2n`,
+    /// … set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn every_nth(len: u64, n: u64) -> Self {
+        assert!(n > 0, "BitVector::every_nth: zero step");
+        Self::with_bits_set(len, (0 .. len.ceil_div(n)).map(|i| i * n))
+    }
+
+    /// Creates a bit vector of `len` bits where bit `i` is `f(i)`.
+    ///
+    /// `f` is called once for each position, in order. Each block is
+    /// assembled before it is written, which is faster than setting the
+    /// bits one at a time.
+    pub fn from_fn<F: FnMut(u64) -> bool>(len: u64, mut f: F) -> Self {
+        let mut result = Self::with_fill(len, false);
+        for i in 0 .. result.block_len() {
+            let start = Block::mul_nbits(i);
+            let count = cmp::min(Block::nbits() as u64, len - start) as usize;
+            let mut block = Block::zero();
+            for j in 0 .. count {
+                if f(start + j as u64) {
+                    block = block | Block::one() << j;
+                }
+            }
+            result.set_block(i, block);
+        }
+        result
+    }
+
+    /// Creates a bit vector from a slice of bits.
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut result = Self::with_capacity(bits.len() as u64);
+        for &bit in bits {
+            result.push_bit(bit);
+        }
+        result
+    }
+
+    /// Creates a bit vector of `8 * bytes.len()` bits, reading each byte
+    /// most significant bit first.
+    ///
+    /// That is, bit `8 * i + j` is bit `7 - j` of `bytes[i]`. This is the
+    /// inverse of [`to_bytes`](#method.to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::from_bytes_with_order(bytes, BitOrder::MsbFirst)
+    }
+
+    /// Creates a bit vector of `8 * bytes.len()` bits, reading the bits of
+    /// each byte in the given order.
+    pub fn from_bytes_with_order(bytes: &[u8], order: BitOrder) -> Self {
+        let mut result = Self::with_fill(8 * bytes.len() as u64, false);
+        for (i, &byte) in bytes.iter().enumerate() {
+            let byte = match order {
+                BitOrder::MsbFirst => byte.reverse_bits(),
+                BitOrder::LsbFirst => byte,
+            };
+            result.set_bits(8 * i as u64, 8, Block::from(byte).unwrap());
+        }
+        result
+    }
+
     /// How many bits the bit vector can hold without reallocating.
     pub fn capacity(&self) -> u64 {
         self.0.capacity(1)
@@ -93,6 +208,24 @@ impl<Block: BlockType> BitVector<Block> {
         self.0.truncate(1, new_len);
     }
 
+    /// Sets the bit at `position` to `value`, first extending the vector
+    /// with 0s to `position + 1` bits if it is shorter.
+    ///
+    /// Clearing a bit past the end still extends the vector, so that
+    /// `bit_len` is always past the last position written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position + 1` is too large, as for `resize`.
+    pub fn set_bit_grow(&mut self, position: u64, value: bool) {
+        if position >= self.bit_len() {
+            let new_len = position.checked_add(1)
+                                  .expect("BitVector::set_bit_grow: overflow");
+            self.resize(new_len, false);
+        }
+        self.set_bit(position, value);
+    }
+
     /// Resizes the bit vector to the given number of blocks,
     /// filling if necessary.
     pub fn block_resize(&mut self, new_len: usize, value: Block) {
@@ -175,10 +308,178 @@ impl<Block: BlockType> BitVector<Block> {
         self.0.clear();
     }
 
+    /// Appends the bits of `other` to the end of the vector.
+    ///
+    /// This works a block at a time even when the current length isn’t a
+    /// multiple of the block size, shifting each block of `other` across
+    /// the boundary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the combined length overflows.
+    pub fn append<Other>(&mut self, other: &Other)
+        where Other: BitVec<Block = Block> + ?Sized {
+
+        let start = self.bit_len();
+        let new_len = start.checked_add(other.bit_len())
+                           .expect("BitVector::append: overflow");
+        self.resize(new_len, false);
+
+        let address = Address::new::<Block>(start);
+        let blocks = self.0.blocks_mut();
+
+        for (i, block) in other.blocks().enumerate() {
+            let index = address.block_index + i;
+            blocks[index] = blocks[index] | block << address.bit_offset;
+
+            if address.bit_offset > 0 && index + 1 < blocks.len() {
+                let high = block >> (Block::nbits() - address.bit_offset);
+                blocks[index + 1] = blocks[index + 1] | high;
+            }
+        }
+    }
+
+    /// Reverses the order of the bits in place, so that bit `i` moves to
+    /// `bit_len() - 1 - i`.
+    pub fn reverse(&mut self) {
+        // The unused bits of the last block end up at the bottom of the
+        // first, so after reversing the blocks and the bits within each,
+        // shift everything down over them.
+        let pad = Block::nbits() - Block::last_block_bits(self.bit_len());
+        let blocks = self.0.blocks_mut();
+
+        blocks.reverse();
+        for block in blocks.iter_mut() {
+            *block = block.reverse_block();
+        }
+
+        if pad > 0 {
+            for i in 0 .. blocks.len() {
+                let next = blocks.get(i + 1).map_or(Block::zero(), |&next| {
+                    next << (Block::nbits() - pad)
+                });
+                blocks[i] = blocks[i] >> pad | next;
+            }
+        }
+    }
+
+    /// Borrows the underlying blocks.
+    ///
+    /// Bits of the last block past `bit_len()` are always zero.
+    pub fn as_blocks(&self) -> &[Block] {
+        self.0.blocks()
+    }
+
+    /// Returns the underlying blocks without copying them.
+    ///
+    /// Bits of the last block past `bit_len()` are always zero.
+    pub fn into_blocks(self) -> Vec<Block> {
+        self.0.into_vec()
+    }
+
     /// Returns an iterator over the bits of the bit vector
     pub fn iter(&self) -> Iter<Block> {
         Iter(vector_base::Iter::new(1, &self.0))
     }
+
+    /// Packs the bits into `ceil(bit_len / 8)` bytes, most significant bit
+    /// first, as read by [`from_bytes`](#method.from_bytes).
+    ///
+    /// If `bit_len` isn’t a multiple of 8, the low-order bits of the last
+    /// byte are zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_order(BitOrder::MsbFirst)
+    }
+
+    /// Packs the bits into `ceil(bit_len / 8)` bytes in the given order.
+    ///
+    /// Bits of the last byte that lie past `bit_len` are zero.
+    pub fn to_bytes_with_order(&self, order: BitOrder) -> Vec<u8> {
+        let len = self.bit_len();
+        let byte_len = self.byte_len();
+        let mut result = Vec::with_capacity(byte_len);
+
+        for i in 0 .. byte_len {
+            let start = 8 * i as u64;
+            let count = cmp::min(8, len - start) as usize;
+            let byte = self.get_bits(start, count).to_u8().unwrap();
+            result.push(match order {
+                BitOrder::MsbFirst => byte.reverse_bits(),
+                BitOrder::LsbFirst => byte,
+            });
+        }
+
+        result
+    }
+
+    /// Writes the bit vector to `sink`.
+    ///
+    /// The format is the bit length as a little-endian `u64`, followed by
+    /// the bits packed little-endian into `ceil(bit_len / 8)` bytes. This
+    /// doesn’t depend on `Block`, so a vector written with one block type
+    /// can be read back with another.
+    pub fn write_to<W: io::Write>(&self, sink: &mut W) -> io::Result<()> {
+        sink.write_u64::<LittleEndian>(self.bit_len())?;
+
+        let block_len = self.block_len();
+        for i in 0 .. block_len {
+            let block = self.get_block(i);
+
+            if i + 1 < block_len
+                    || Block::last_block_bits(self.bit_len()) == Block::nbits() {
+                block.write_block::<W, LittleEndian>(sink)?;
+            } else {
+                let bits = Block::last_block_bits(self.bit_len());
+                for k in 0 .. bits.ceil_div(8) {
+                    let byte = block.get_bits(8 * k, 8).to_u8().unwrap();
+                    sink.write_u8(byte)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a bit vector in the format written by
+    /// [`write_to`](#method.write_to).
+    ///
+    /// Fails with `ErrorKind::UnexpectedEof` if `source` ends before all
+    /// the bits promised by the header, and with `ErrorKind::InvalidData`
+    /// if the final byte has bits set past the end of the vector.
+    pub fn read_from<R: io::Read>(source: &mut R) -> io::Result<Self> {
+        let len = source.read_u64::<LittleEndian>()?;
+        let block_bytes = Block::nbits() as u64 / 8;
+        let mut remaining = len / 8 + (len % 8 != 0) as u64;
+        let mut result = BitVector::new();
+
+        while remaining > 0 {
+            let block = if remaining >= block_bytes {
+                Block::read_block::<R, LittleEndian>(source)?
+            } else {
+                let mut block = Block::zero();
+                for k in 0 .. remaining as usize {
+                    let byte = Block::from(source.read_u8()?).unwrap();
+                    block = block | byte << (8 * k);
+                }
+                block
+            };
+
+            result.push_block(block);
+            remaining = remaining.saturating_sub(block_bytes);
+        }
+
+        if result.bit_len() > len {
+            let last = result.get_block(result.block_len() - 1);
+            let mask = Block::last_block_mask(len);
+            if last & !mask != Block::zero() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          "BitVector::read_from: bits past end"));
+            }
+            result.truncate(len);
+        }
+
+        Ok(result)
+    }
 }
 
 impl<Block: BlockType> BitVec for BitVector<Block> {
@@ -197,6 +498,11 @@ impl<Block: BlockType> BitVec for BitVector<Block> {
     fn get_block(&self, index: usize) -> Block {
         self.0.get_block(index)
     }
+
+    #[inline]
+    unsafe fn get_block_unchecked(&self, index: usize) -> Block {
+        self.0.blocks().get_block_unchecked(index)
+    }
 }
 
 impl<Block: BlockType> BitVecMut for BitVector<Block> {
@@ -243,6 +549,58 @@ impl<Block: BlockType> SpaceUsage for BitVector<Block> {
     }
 }
 
+/// Serializes as the pair `(bit_len, blocks)`.
+#[cfg(feature = "serde")]
+impl<Block: BlockType + Serialize> Serialize for BitVector<Block> {
+    fn serialize<S: Serializer>(&self, serializer: S)
+                                -> Result<S::Ok, S::Error> {
+        (self.bit_len(), self.0.blocks()).serialize(serializer)
+    }
+}
+
+/// Rejects data whose block count doesn’t match its bit length, or whose
+/// last block has bits set past the end.
+#[cfg(feature = "serde")]
+impl<'de, Block> Deserialize<'de> for BitVector<Block>
+        where Block: BlockType + Deserialize<'de> {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+                                         -> Result<Self, D::Error> {
+        let (len, blocks) = <(u64, Vec<Block>)>::deserialize(deserializer)?;
+        VectorBase::from_parts(1, len, blocks)
+            .map(BitVector)
+            .ok_or_else(|| D::Error::custom("BitVector: inconsistent length"))
+    }
+}
+
+/// Takes ownership of the blocks without copying them; the bit length is
+/// `blocks.len() * Block::nbits()`.
+impl<Block: BlockType> From<Vec<Block>> for BitVector<Block> {
+    fn from(blocks: Vec<Block>) -> Self {
+        let len = Block::mul_nbits(blocks.len());
+        BitVector(VectorBase::from_parts(1, len, blocks)
+                      .expect("BitVector::from: too many blocks"))
+    }
+}
+
+impl<Block: BlockType> Extend<bool> for BitVector<Block> {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0 as u64);
+        for bit in iter {
+            self.push_bit(bit);
+        }
+    }
+}
+
+impl<Block: BlockType> FromIterator<bool> for BitVector<Block> {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut result = BitVector::new();
+        result.extend(iter);
+        result
+    }
+}
+
 impl<Block: BlockType> Default for BitVector<Block> {
     fn default() -> Self {
         BitVector::new()
@@ -303,6 +661,9 @@ impl<'a, Block: BlockType + 'a> IntoIterator for &'a BitVector<Block> {
 #[cfg(test)]
 mod test {
     use bit_vec::*;
+    use storage::BlockType;
+    use internal::test_util::random_bits;
+    use quickcheck::quickcheck;
 
     macro_rules! assert_bv {
         ($expected:expr, $actual:expr) => {
@@ -326,6 +687,34 @@ mod test {
         assert_eq!(96, bit_vector.capacity());
     }
 
+    #[test]
+    fn shrink_to_fit() {
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+        for i in 0 .. 10_000 {
+            bit_vector.push_bit(i % 3 == 0);
+        }
+        bit_vector.truncate(100);
+        assert!(bit_vector.capacity() >= 10_000);
+
+        let before = bit_vector.clone();
+        bit_vector.shrink_to_fit();
+        assert_eq!(4, bit_vector.block_capacity());
+        assert_eq!(128, bit_vector.capacity());
+        assert_eq!(100, bit_vector.bit_len());
+        assert_eq!(before, bit_vector);
+        for i in 0 .. 100 {
+            assert_eq!(i % 3 == 0, bit_vector.get_bit(i));
+        }
+
+        // Still growable, and the bits past the end were cleared.
+        bit_vector.resize(128, false);
+        assert_eq!(34, bit_vector.ones().count());
+
+        let mut empty: BitVector<u64> = BitVector::with_capacity(1000);
+        empty.shrink_to_fit();
+        assert_eq!(0, empty.capacity());
+    }
+
     #[test]
     fn push_binary() {
         let mut bit_vector: BitVector = BitVector::new();
@@ -379,6 +768,30 @@ mod test {
         assert_eq!(false, bit_vector.get_bit(2));
     }
 
+    #[test]
+    fn push_get_200() {
+        let pattern = |i: u64| i % 3 == 1 || i % 7 == 2;
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+
+        for i in 0 .. 200 {
+            bit_vector.push_bit(pattern(i));
+            assert_eq!(i + 1, bit_vector.bit_len());
+            assert_eq!(i as usize / 32 + 1, bit_vector.block_len());
+        }
+
+        for i in 0 .. 200 {
+            assert_eq!(pattern(i), bit_vector.get_bit(i));
+        }
+
+        for i in (0 .. 200).rev() {
+            assert_eq!(Some(pattern(i)), bit_vector.pop_bit());
+            assert_eq!(i, bit_vector.bit_len());
+            assert_eq!(i.ceil_div(32) as usize, bit_vector.block_len());
+        }
+
+        assert_eq!(None, bit_vector.pop_bit());
+    }
+
     #[test]
     #[should_panic]
     fn get_oob() {
@@ -423,6 +836,312 @@ mod test {
         bit_vector.get_block(3);
     }
 
+    #[test]
+    fn set_bit_grow() {
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+        let positions = [0, 3, 31, 32, 100, 101, 1000];
+
+        for (k, &position) in positions.iter().enumerate() {
+            bit_vector.set_bit_grow(position, true);
+            assert_eq!(position + 1, bit_vector.bit_len());
+            for i in 0 .. bit_vector.bit_len() {
+                assert_eq!(positions[.. k + 1].contains(&i), bit_vector.get_bit(i));
+            }
+        }
+
+        // Setting inside the vector doesn't change its length.
+        bit_vector.set_bit_grow(500, true);
+        bit_vector.set_bit_grow(3, false);
+        assert_eq!(1001, bit_vector.bit_len());
+        assert!(bit_vector.get_bit(500));
+        assert!(!bit_vector.get_bit(3));
+
+        bit_vector.set_bit_grow(2000, false);
+        assert_eq!(2001, bit_vector.bit_len());
+        assert!(!bit_vector.get_bit(2000));
+        assert_eq!(7, bit_vector.ones().count());
+    }
+
+    #[test]
+    fn set_range() {
+        let mut bit_vector: BitVector<u32> = BitVector::with_fill(200, false);
+        bit_vector.set_range(20 .. 150, true);
+        for i in 0 .. 200 {
+            assert_eq!((20 .. 150).contains(&i), bit_vector.get_bit(i));
+        }
+
+        bit_vector.set_range(40 .. 41, false);
+        bit_vector.set_range(64 .. 96, false);
+        bit_vector.set_range(200 .. 200, true);
+        for i in 0 .. 200 {
+            let expected = (20 .. 150).contains(&i) && i != 40
+                        && !(64 .. 96).contains(&i);
+            assert_eq!(expected, bit_vector.get_bit(i));
+        }
+
+        bit_vector.set_range(0 .. 200, true);
+        assert_eq!(0xFF, bit_vector.get_block(6));
+        assert!((0 .. 200).all(|i| bit_vector.get_bit(i)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_range_oob() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+        bit_vector.set_range(15 .. 21, true);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_range_backwards() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+        let (start, end) = (7, 3);
+        bit_vector.set_range(start .. end, true);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_range_empty_oob() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_fill(10, false);
+        bit_vector.set_range(1000 .. 1000, true);
+    }
+
+    fn set_range_prop<Block: BlockType>(bits: Vec<bool>, a: u16, b: u16,
+                                        value: bool) -> bool {
+        let len = bits.len() as u64;
+        let (start, end) = if len == 0 {
+            (0, 0)
+        } else {
+            let (a, b) = (a as u64 % (len + 1), b as u64 % (len + 1));
+            (a.min(b), a.max(b))
+        };
+
+        let mut fast: BitVector<Block> = bits.iter().cloned().collect();
+        fast.set_range(start .. end, value);
+        let mut naive: BitVector<Block> = bits.iter().cloned().collect();
+        for i in start .. end { naive.set_bit(i, value); }
+
+        fast == naive && fast.block_len() == naive.block_len()
+            && (0 .. fast.block_len()).all(|i| fast.get_block(i) == naive.get_block(i))
+    }
+
+    #[test]
+    fn set_range_qc() {
+        quickcheck(set_range_prop::<u8> as fn(Vec<bool>, u16, u16, bool) -> bool);
+        quickcheck(set_range_prop::<u64> as fn(Vec<bool>, u16, u16, bool) -> bool);
+    }
+
+    fn check_copy<Block: BlockType>(dst: &BitVector<Block>, dst_start: u64,
+                                    src: &BitVector<Block>, start: u64, end: u64) {
+        let mut fast = dst.clone();
+        fast.copy_bits_from(dst_start, src, start .. end);
+        let mut naive = dst.clone();
+        for i in start .. end {
+            naive.set_bit(dst_start + i - start, src.get_bit(i));
+        }
+        assert_eq!(naive, fast, "dst_start {} range {} .. {}", dst_start, start, end);
+    }
+
+    #[test]
+    fn copy_bits_from() {
+        let src = random_bits::<u32>(300, 128, 1);
+        let dst = random_bits::<u32>(300, 128, 2);
+
+        // Aligned and unaligned starts on both sides, with lengths that
+        // end mid-block, on a boundary, and within the first block.
+        for &src_start in &[0, 32, 5, 37] {
+            for &dst_start in &[0, 64, 3, 70] {
+                for &len in &[0, 1, 20, 27, 32, 64, 100, 190] {
+                    check_copy(&dst, dst_start, &src, src_start, src_start + len);
+                }
+            }
+        }
+
+        // Ranges that overlap in position, as when shifting a copy of
+        // `dst` within itself.
+        for &(dst_start, start, end) in &[(10, 0, 200), (0, 10, 210), (33, 31, 250),
+                                          (31, 33, 250), (50, 50, 299)] {
+            check_copy(&dst, dst_start, &dst.clone(), start, end);
+        }
+
+        // Up to the end of the destination.
+        check_copy(&dst, 250, &src, 7, 57);
+        check_copy(&src, 0, &random_bits::<u32>(20, 128, 3), 0, 20);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_bits_from_dst_oob() {
+        let src: BitVector<u8> = BitVector::with_fill(64, true);
+        let mut dst: BitVector<u8> = BitVector::with_fill(64, false);
+        dst.copy_bits_from(60, &src, 0 .. 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_bits_from_src_oob() {
+        let src: BitVector<u8> = BitVector::with_fill(10, true);
+        let mut dst: BitVector<u8> = BitVector::with_fill(64, false);
+        dst.copy_bits_from(0, &src, 5 .. 11);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_bits_from_backwards() {
+        let src: BitVector<u8> = BitVector::with_fill(10, true);
+        let mut dst: BitVector<u8> = BitVector::with_fill(64, false);
+        let (start, end) = (7, 3);
+        dst.copy_bits_from(0, &src, start .. end);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_bits_from_empty_dst_oob() {
+        let src: BitVector<u8> = BitVector::with_fill(10, true);
+        let mut dst: BitVector<u8> = BitVector::with_fill(64, false);
+        dst.copy_bits_from(u64::MAX, &src, 5 .. 5);
+    }
+
+    fn copy_bits_prop(src: Vec<bool>, dst: Vec<bool>, a: u16, b: u16, c: u16)
+                      -> bool {
+        let src: BitVector<u8> = src.into_iter().collect();
+        let dst: BitVector<u8> = dst.into_iter().collect();
+        let (a, b) = (a as u64 % (src.bit_len() + 1), b as u64 % (src.bit_len() + 1));
+        let (start, end) = (a.min(b), a.max(b));
+        if end - start > dst.bit_len() { return true; }
+        let dst_start = c as u64 % (dst.bit_len() - (end - start) + 1);
+
+        check_copy(&dst, dst_start, &src, start, end);
+        true
+    }
+
+    #[test]
+    fn copy_bits_qc() {
+        quickcheck(copy_bits_prop as fn(Vec<bool>, Vec<bool>, u16, u16, u16) -> bool);
+    }
+
+    fn check_swap<Block: BlockType>(bits: &BitVector<Block>, a: u64, b: u64, len: u64) {
+        let mut fast = bits.clone();
+        fast.swap_ranges(a .. a + len, b .. b + len);
+        let mut naive = bits.clone();
+        for i in 0 .. len {
+            naive.set_bit(a + i, bits.get_bit(b + i));
+            naive.set_bit(b + i, bits.get_bit(a + i));
+        }
+        assert_eq!(naive, fast, "swap {} and {}, length {}", a, b, len);
+    }
+
+    #[test]
+    fn swap_ranges() {
+        let bits = random_bits::<u32>(300, 128, 4);
+
+        // Aligned and unaligned starts, in either order, with lengths
+        // that end mid-block, on a boundary, and within the first block.
+        for &(a, b) in &[(0, 128), (5, 150), (37, 3), (64, 0), (31, 33)] {
+            for &len in &[0, 1, 2, 20, 32, 33, 64, 100] {
+                if a.max(b) + len <= 300 && (a + len <= b || b + len <= a) {
+                    check_swap(&bits, a, b, len);
+                }
+            }
+        }
+
+        // Adjacent ranges, up to the end of the vector.
+        check_swap(&bits, 0, 150, 150);
+        check_swap(&bits, 299, 0, 1);
+        check_swap(&random_bits::<u8>(100, 128, 5), 2, 51, 49);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_overlap() {
+        let mut bits: BitVector<u8> = BitVector::with_fill(64, false);
+        bits.swap_ranges(0 .. 10, 9 .. 19);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_lengths_differ() {
+        let mut bits: BitVector<u8> = BitVector::with_fill(64, false);
+        bits.swap_ranges(0 .. 10, 20 .. 31);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_oob() {
+        let mut bits: BitVector<u8> = BitVector::with_fill(64, false);
+        bits.swap_ranges(0 .. 10, 60 .. 70);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_backwards() {
+        let mut bits: BitVector<u8> = BitVector::with_fill(64, false);
+        let (a, b) = ((5, 2), (9, 1));
+        bits.swap_ranges(a.0 .. a.1, b.0 .. b.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_ranges_empty_oob() {
+        let mut bits: BitVector<u8> = BitVector::with_fill(64, false);
+        bits.swap_ranges(0 .. 0, 100 .. 100);
+    }
+
+    fn swap_ranges_prop(bits: Vec<bool>, a: u16, b: u16, c: u16) -> bool {
+        let bits: BitVector<u8> = bits.into_iter().collect();
+        let len = bits.bit_len();
+        let len_ab = c as u64 % (len / 2 + 1);
+        // Two disjoint starts: one in the first part, one after it.
+        let a = a as u64 % (len - 2 * len_ab + 1);
+        let b = a + len_ab + b as u64 % (len - a - 2 * len_ab + 1);
+        check_swap(&bits, a, b, len_ab);
+        check_swap(&bits, b, a, len_ab);
+        true
+    }
+
+    #[test]
+    fn swap_ranges_qc() {
+        quickcheck(swap_ranges_prop as fn(Vec<bool>, u16, u16, u16) -> bool);
+    }
+
+    #[test]
+    fn byte_len() {
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+        assert_eq!(0, bit_vector.byte_len());
+        assert_eq!(0, bit_vector.block_len());
+
+        for &(len, bytes, blocks) in &[(1, 1, 1), (8, 1, 1), (9, 2, 1),
+                                       (32, 4, 1), (33, 5, 2)] {
+            bit_vector.resize(len, true);
+            assert_eq!(bytes, bit_vector.byte_len());
+            assert_eq!(blocks, bit_vector.block_len());
+            assert_eq!(bytes, bit_vector.to_bytes().len());
+        }
+
+        let blocks: &[u16] = &[1, 2];
+        assert_eq!(4, blocks.byte_len());
+        assert_eq!(8, 5u64.byte_len());
+    }
+
+    #[test]
+    fn try_get_block() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_capacity(64);
+        assert_eq!(None, bit_vector.try_get_block(0));
+
+        bit_vector.resize(12, true);
+        assert_eq!(Some(0xFF), bit_vector.try_get_block(0));
+        assert_eq!(Some(0x0F), bit_vector.try_get_block(1));
+        assert_eq!(None, bit_vector.try_get_block(2));
+        assert_eq!(None, bit_vector.try_get_block(7));
+        assert_eq!(None, bit_vector.try_get_block(usize::MAX));
+
+        assert_eq!(Some(5), 5u32.try_get_block(0));
+        assert_eq!(None, 5u32.try_get_block(1));
+        let blocks: &[u16] = &[1, 2];
+        assert_eq!(Some(2), blocks.try_get_block(1));
+        assert_eq!(None, blocks.try_get_block(2));
+    }
+
     #[test]
     fn push_block_get_bit() {
         let mut bit_vector: BitVector = BitVector::new();
@@ -492,6 +1211,174 @@ mod test {
         assert_bv!("1010000011111111", bit_vector);
     }
 
+    // Counts the 1s in the raw blocks, including any bits past the end.
+    fn raw_ones<Block: BlockType>(bit_vector: &BitVector<Block>) -> u64 {
+        (0 .. bit_vector.block_len())
+            .map(|i| bit_vector.get_block(i).count_ones() as u64)
+            .sum()
+    }
+
+    fn logical_ones<Block: BlockType>(bit_vector: &BitVector<Block>) -> u64 {
+        bit_vector.iter().filter(|&bit| bit).count() as u64
+    }
+
+    #[test]
+    fn grow_then_shrink() {
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+
+        bit_vector.resize(100, true);
+        assert_eq!(100, raw_ones(&bit_vector));
+        assert_eq!(4, bit_vector.block_len());
+
+        bit_vector.resize(37, true);
+        assert_eq!(37, bit_vector.bit_len());
+        assert_eq!(2, bit_vector.block_len());
+        assert_eq!(37, raw_ones(&bit_vector));
+
+        bit_vector.resize(70, false);
+        assert_eq!(37, raw_ones(&bit_vector));
+        assert_eq!(37, logical_ones(&bit_vector));
+
+        bit_vector.truncate(5);
+        assert_eq!(5, raw_ones(&bit_vector));
+
+        // Truncating to a longer length does nothing.
+        bit_vector.truncate(50);
+        assert_eq!(5, bit_vector.bit_len());
+
+        bit_vector.resize(64, true);
+        assert_eq!(64, raw_ones(&bit_vector));
+        bit_vector.truncate(0);
+        assert_eq!(0, bit_vector.block_len());
+    }
+
+    fn resize_prop(ops: Vec<(u8, bool)>) -> bool {
+        let mut bit_vector: BitVector<u8> = BitVector::new();
+        let mut model: Vec<bool> = Vec::new();
+
+        ops.into_iter().all(|(len, value)| {
+            bit_vector.resize(len as u64, value);
+            model.resize(len as usize, value);
+
+            bit_vector.iter().eq(model.iter().cloned())
+                && raw_ones(&bit_vector) == logical_ones(&bit_vector)
+        })
+    }
+
+    #[test]
+    fn resize_qc() {
+        quickcheck(resize_prop as fn(Vec<(u8, bool)>) -> bool);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use bincode;
+        use serde_json;
+
+        let mut bit_vector: BitVector<u32> = BitVector::new();
+        for i in 0 .. 100 {
+            bit_vector.push_bit(i % 3 == 1);
+        }
+
+        let json = serde_json::to_string(&bit_vector).unwrap();
+        assert_eq!(bit_vector, serde_json::from_str(&json).unwrap());
+
+        let binary = bincode::serialize(&bit_vector).unwrap();
+        assert_eq!(bit_vector, bincode::deserialize(&binary).unwrap());
+
+        let empty: BitVector<u8> = BitVector::new();
+        let json = serde_json::to_string(&empty).unwrap();
+        assert_eq!(empty, serde_json::from_str(&json).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_bad_data() {
+        use bincode;
+        use serde_json;
+
+        type BV = BitVector<u8>;
+
+        assert!(serde_json::from_str::<BV>("[9,[255]]").is_err());
+        assert!(serde_json::from_str::<BV>("[9,[255,1,0]]").is_err());
+        assert!(serde_json::from_str::<BV>("[9,[255,2]]").is_err());
+        assert!(serde_json::from_str::<BV>("[9,[255,1]]").is_ok());
+
+        let mut bit_vector: BV = BitVector::with_fill(20, true);
+        bit_vector.push_bit(false);
+        let binary = bincode::serialize(&bit_vector).unwrap();
+        assert!(bincode::deserialize::<BV>(&binary[.. binary.len() - 1])
+                    .is_err());
+    }
+
+    fn pattern<Block: BlockType>(len: u64) -> BitVector<Block> {
+        let mut bit_vector = BitVector::new();
+        for i in 0 .. len {
+            bit_vector.push_bit(i % 5 == 1 || i % 7 == 3);
+        }
+        bit_vector
+    }
+
+    #[test]
+    fn write_read() {
+        for len in 0 .. 150 {
+            let bit_vector = pattern::<u32>(len);
+            let mut buffer = Vec::new();
+            bit_vector.write_to(&mut buffer).unwrap();
+            assert_eq!(8 + len.ceil_div(8) as usize, buffer.len());
+
+            let read_back = BitVector::read_from(&mut &*buffer).unwrap();
+            assert_eq!(bit_vector, read_back);
+        }
+    }
+
+    #[test]
+    fn write_read_across_block_types() {
+        let bit_vector = pattern::<u8>(77);
+        let mut buffer = Vec::new();
+        bit_vector.write_to(&mut buffer).unwrap();
+
+        let wide: BitVector<u64> = BitVector::read_from(&mut &*buffer).unwrap();
+        assert_eq!(77, wide.bit_len());
+        for i in 0 .. 77 {
+            assert_eq!(bit_vector.get_bit(i), wide.get_bit(i));
+        }
+
+        let mut wide_buffer = Vec::new();
+        wide.write_to(&mut wide_buffer).unwrap();
+        assert_eq!(buffer, wide_buffer);
+
+        let widest: BitVector<u128> = BitVector::read_from(&mut &*buffer).unwrap();
+        assert_eq!(1, widest.block_len());
+        let mut widest_buffer = Vec::new();
+        widest.write_to(&mut widest_buffer).unwrap();
+        assert_eq!(buffer, widest_buffer);
+    }
+
+    #[test]
+    fn read_truncated() {
+        use std::io::ErrorKind;
+
+        let mut buffer = Vec::new();
+        pattern::<u16>(100).write_to(&mut buffer).unwrap();
+
+        for cut in 0 .. buffer.len() {
+            let error = BitVector::<u16>::read_from(&mut &buffer[.. cut])
+                            .unwrap_err();
+            assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        }
+    }
+
+    #[test]
+    fn read_bits_past_end() {
+        use std::io::ErrorKind;
+
+        let buffer = [ 3, 0, 0, 0, 0, 0, 0, 0, 0b00001101 ];
+        let error = BitVector::<u32>::read_from(&mut &buffer[..]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+    }
+
     #[test]
     fn block_resize() {
         let mut bit_vector: BitVector<u8> = BitVector::new();
@@ -510,4 +1397,334 @@ mod test {
         bit_vector.block_resize(2, 0);
         assert_bv!("1010000010100010", bit_vector);
     }
+
+    #[test]
+    fn from_bits() {
+        let bv = BitVector::<u8>::from_bits(&[true, false, false, true, true]);
+        assert_bv!("10011", bv);
+        assert_eq!(0, BitVector::<u8>::from_bits(&[]).bit_len());
+    }
+
+    #[test]
+    fn from_bytes_order() {
+        let bytes = [0b10000010u8, 0b00000001];
+
+        let msb = BitVector::<u32>::from_bytes(&bytes);
+        assert_eq!(16, msb.bit_len());
+        assert!(msb.get_bit(0));
+        assert!(msb.get_bit(6));
+        assert!(msb.get_bit(15));
+        assert_eq!(3, msb.iter().filter(|&bit| bit).count());
+
+        let lsb = BitVector::<u32>::from_bytes_with_order(&bytes,
+                                                          BitOrder::LsbFirst);
+        assert!(lsb.get_bit(1));
+        assert!(lsb.get_bit(7));
+        assert!(lsb.get_bit(8));
+        assert_eq!(3, lsb.iter().filter(|&bit| bit).count());
+        assert_eq!(bytes.to_vec(), lsb.to_bytes_with_order(BitOrder::LsbFirst));
+    }
+
+    #[test]
+    fn to_bytes_partial() {
+        let bv = BitVector::<u64>::from_bits(&[true; 11]);
+        assert_eq!(vec![0xFF, 0b11100000], bv.to_bytes());
+        assert_eq!(vec![0xFF, 0b00000111],
+                   bv.to_bytes_with_order(BitOrder::LsbFirst));
+        assert!(BitVector::<u64>::new().to_bytes().is_empty());
+    }
+
+    #[test]
+    fn bytes_round_trip_lengths() {
+        for &len in &[0, 1, 3, 4, 7, 8, 9, 17] {
+            let bytes: Vec<u8> = (0 .. len).map(|i| (i * 37 + 11) as u8).collect();
+            assert_eq!(bytes, BitVector::<u32>::from_bytes(&bytes).to_bytes());
+            assert_eq!(bytes, BitVector::<u64>::from_bytes(&bytes).to_bytes());
+        }
+    }
+
+    fn bytes_round_trip_prop<Block: BlockType>(bytes: Vec<u8>) -> bool {
+        let bv = BitVector::<Block>::from_bytes(&bytes);
+        bv.bit_len() == 8 * bytes.len() as u64
+            && bv.to_bytes() == bytes
+            && BitVector::<Block>::from_bytes_with_order(&bytes, BitOrder::LsbFirst)
+                   .to_bytes_with_order(BitOrder::LsbFirst) == bytes
+    }
+
+    #[test]
+    fn bytes_round_trip_qc() {
+        quickcheck(bytes_round_trip_prop::<u8> as fn(Vec<u8>) -> bool);
+        quickcheck(bytes_round_trip_prop::<u32> as fn(Vec<u8>) -> bool);
+        quickcheck(bytes_round_trip_prop::<u64> as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut bv = BitVector::<u8>::from_bits(&[true, true, false, true]);
+        bv.reverse();
+        assert_bv!("1011", bv);
+
+        // 21 bits across three u8 blocks, with only 5 in the last.
+        let bits: Vec<bool> = (0 .. 21).map(|i| i % 3 < 1 || i == 20).collect();
+        let mut bv = BitVector::<u8>::from_bits(&bits);
+        bv.reverse();
+        assert_eq!(21, bv.bit_len());
+        for (i, &bit) in bits.iter().rev().enumerate() {
+            assert_eq!(bit, bv.get_bit(i as u64));
+        }
+        assert_eq!(0, bv.get_block(2) >> 5);
+
+        let mut empty = BitVector::<u64>::new();
+        empty.reverse();
+        assert_eq!(0, empty.bit_len());
+    }
+
+    fn reverse_prop<Block: BlockType>(bits: Vec<bool>) -> bool {
+        let mut bv = BitVector::<Block>::from_bits(&bits);
+        bv.reverse();
+        let reversed: Vec<bool> = bits.iter().rev().cloned().collect();
+        bv == BitVector::from_bits(&reversed)
+    }
+
+    #[test]
+    fn reverse_qc() {
+        quickcheck(reverse_prop::<u8> as fn(Vec<bool>) -> bool);
+        quickcheck(reverse_prop::<u64> as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn blocks_round_trip() {
+        let blocks = vec![0b1011u8, 0, 0xFF];
+        let bv = BitVector::from(blocks.clone());
+        assert_eq!(24, bv.bit_len());
+        assert_bv!("110100000000000011111111", bv);
+        assert_eq!(&blocks[..], bv.as_blocks());
+        assert_eq!(blocks, bv.into_blocks());
+
+        let empty = BitVector::<u64>::from(Vec::new());
+        assert_eq!(0, empty.bit_len());
+        assert!(empty.into_blocks().is_empty());
+    }
+
+    #[test]
+    fn blocks_without_copying() {
+        let blocks = vec![0x0123_4567_89AB_CDEFu64; 10];
+        let pointer = blocks.as_ptr();
+
+        let bv = BitVector::from(blocks);
+        assert_eq!(pointer, bv.as_blocks().as_ptr());
+        let blocks = bv.into_blocks();
+        assert_eq!(pointer, blocks.as_ptr());
+    }
+
+    #[test]
+    fn into_blocks_partial() {
+        let mut bv = BitVector::<u32>::with_fill(40, true);
+        bv.truncate(36);
+        assert_eq!(vec![!0, 0b1111], bv.into_blocks());
+    }
+
+    #[test]
+    fn unchecked_matches_checked() {
+        let bits: Vec<bool> = (0 .. 77).map(|i| i % 5 < 2).collect();
+        let bv = BitVector::<u16>::from_bits(&bits);
+
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(bit, unsafe { bv.get_bit_unchecked(i as u64) });
+        }
+        for i in 0 .. bv.block_len() {
+            assert_eq!(bv.get_block(i), unsafe { bv.get_block_unchecked(i) });
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn get_bit_unchecked_oob_debug() {
+        let bv = BitVector::<u64>::with_fill(10, true);
+        unsafe { bv.get_bit_unchecked(10); }
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn get_block_unchecked_oob_debug() {
+        let bv = BitVector::<u64>::with_fill(64, true);
+        unsafe { bv.get_block_unchecked(1); }
+    }
+
+    #[test]
+    fn append_aligned() {
+        let mut bv = BitVector::<u8>::from_bits(&[true; 8]);
+        bv.append(&BitVector::<u8>::from_bits(&[false, true, true]));
+        assert_bv!("11111111011", bv);
+
+        bv.append(&BitVector::<u8>::new());
+        assert_eq!(11, bv.bit_len());
+    }
+
+    #[test]
+    fn append_unaligned() {
+        // Every combination of lengths around the u8 block boundaries.
+        for left_len in 0 .. 20 {
+            for right_len in 0 .. 20 {
+                let left: Vec<bool> = (0 .. left_len).map(|i| i % 3 < 1).collect();
+                let right: Vec<bool> = (0 .. right_len).map(|i| i % 2 < 1).collect();
+
+                let mut bv = BitVector::<u8>::from_bits(&left);
+                bv.append(&BitVector::<u8>::from_bits(&right));
+
+                let expected: Vec<bool> = left.iter().chain(&right).cloned().collect();
+                assert_eq!(BitVector::from_bits(&expected), bv);
+            }
+        }
+    }
+
+    #[test]
+    fn append_slice() {
+        let mut bv = BitVector::<u32>::from_bits(&[true, false, true]);
+        bv.append(&[0xFFFF_0000u32, 1][..]);
+        assert_eq!(67, bv.bit_len());
+        assert_eq!(0b101 | 0xFFFF_0000 << 3, bv.get_block(0));
+        assert_eq!(0xFFFF_0000 >> 29 | 1 << 3, bv.get_block(1));
+        assert_eq!(0, bv.get_block(2));
+    }
+
+    fn append_prop(left: Vec<bool>, right: Vec<bool>) -> bool {
+        let mut bv = BitVector::<u64>::from_bits(&left);
+        bv.append(&BitVector::<u64>::from_bits(&right));
+        let expected: Vec<bool> = left.iter().chain(&right).cloned().collect();
+        bv == BitVector::from_bits(&expected)
+    }
+
+    #[test]
+    fn append_qc() {
+        quickcheck(append_prop as fn(Vec<bool>, Vec<bool>) -> bool);
+    }
+
+    fn hash_of<T: ::std::hash::Hash>(value: &T) -> u64 {
+        use std::hash::Hasher;
+        let mut hasher = ::std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn eq_and_hash_by_bits() {
+        let bytes = [0b10110000u8, 0b01000000];
+
+        let mut pushed = BitVector::<u32>::with_capacity(1000);
+        for &bit in &[true, false, true, true, false, false, false, false,
+                      false, true, false, false, false, false, false, false] {
+            pushed.push_bit(bit);
+        }
+        let from_bytes = BitVector::<u32>::from_bytes(&bytes);
+
+        assert!(pushed.capacity() != from_bytes.capacity());
+        assert_eq!(pushed, from_bytes);
+        assert_eq!(hash_of(&pushed), hash_of(&from_bytes));
+
+        // Bits that were set and then truncated away don’t linger.
+        let mut truncated = BitVector::<u32>::with_fill(30, true);
+        truncated.truncate(10);
+        truncated.resize(16, false);
+        for i in 0 .. 16 {
+            truncated.set_bit(i, from_bytes.get_bit(i));
+        }
+        assert_eq!(from_bytes, truncated);
+        assert_eq!(hash_of(&from_bytes), hash_of(&truncated));
+
+        let mut longer = from_bytes.clone();
+        longer.push_bit(false);
+        assert!(longer != from_bytes);
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let bv: BitVector<u64> = (0 .. 100).map(|i| i % 3 == 0).collect();
+        assert_eq!(100, bv.bit_len());
+        for i in 0 .. 100 {
+            assert_eq!(i % 3 == 0, bv.get_bit(i));
+        }
+
+        let mut bv: BitVector<u8> = bv.iter().take(10).collect();
+        bv.extend((0 .. 13).map(|i| i < 2));
+        assert_eq!(23, bv.bit_len());
+        assert_bv!("10010010011100000000000", bv);
+
+        let empty: BitVector<u32> = ::std::iter::empty().collect();
+        assert_eq!(BitVector::new(), empty);
+    }
+
+    #[test]
+    fn with_bits_set() {
+        let positions = [0, 63, 64, 5, 129, 5];
+        let bv = BitVector::<u64>::with_bits_set(130, positions.iter().cloned());
+        assert_eq!(130, bv.bit_len());
+        assert_eq!(vec![0, 5, 63, 64, 129], bv.ones().collect::<Vec<_>>());
+        assert_eq!(5, u64::count_ones_slice(bv.as_blocks()));
+
+        let none = BitVector::<u8>::with_bits_set(10, None);
+        assert_eq!(BitVector::with_fill(10, false), none);
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_bits_set_oob() {
+        BitVector::<u8>::with_bits_set(10, vec![3, 10]);
+    }
+
+    #[test]
+    fn every_nth() {
+        for &len in &[0, 1, 7, 64, 100, 301] {
+            for &n in &[1, 2, 3, 64, 1000] {
+                let bv = BitVector::<u32>::every_nth(len, n);
+                assert_eq!(len, bv.bit_len());
+                for i in 0 .. len {
+                    assert_eq!(i % n < 1, bv.get_bit(i));
+                }
+                assert_eq!(len.ceil_div(n), u32::count_ones_slice(bv.as_blocks()));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn every_nth_zero() {
+        BitVector::<u8>::every_nth(10, 0);
+    }
+
+    #[test]
+    fn from_fn() {
+        let powers = BitVector::<u32>::from_fn(1000, |i| i.is_power_of_two());
+        assert_eq!(1000, powers.bit_len());
+        assert_eq!((0 .. 10).map(|k| 1 << k).collect::<Vec<u64>>(),
+                   powers.ones().collect::<Vec<_>>());
+
+        use select::{BinSearchSelect, Select1Support};
+        let select = BinSearchSelect::new(::rank::JacobsonRank::new(&powers));
+        for k in 0 .. 10 {
+            assert_eq!(Some(1 << k), select.select1(k));
+        }
+        assert_eq!(None, select.select1(10));
+
+        let mut calls = vec![];
+        let bv = BitVector::<u8>::from_fn(20, |i| { calls.push(i); i % 3 == 0 });
+        assert_eq!((0 .. 20).collect::<Vec<_>>(), calls);
+        assert_eq!(BitVector::every_nth(20, 3), bv);
+
+        assert_eq!(0, BitVector::<u64>::from_fn(0, |_| true).bit_len());
+        assert_eq!(BitVector::<u64>::with_fill(128, true),
+                   BitVector::from_fn(128, |_| true));
+    }
+
+    fn from_fn_prop(bits: Vec<bool>) -> bool {
+        BitVector::<u16>::from_fn(bits.len() as u64, |i| bits[i as usize])
+            == BitVector::from_bits(&bits)
+    }
+
+    #[test]
+    fn from_fn_qc() {
+        quickcheck(from_fn_prop as fn(Vec<bool>) -> bool);
+    }
 }
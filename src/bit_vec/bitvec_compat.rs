@@ -0,0 +1,192 @@
+use bitvec::order::BitOrder as BitvecOrder;
+use bitvec::slice::BitSlice as BitvecSlice;
+use bitvec::store::BitStore;
+
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A `bitvec` crate `BitSlice` viewed as a bit vector, without copying.
+///
+/// Bit `i` of the view is bit `i` of the slice, as `bitvec` indexes it,
+/// whatever its store type `T` and bit order `O`: an `Msb0` slice is not
+/// reversed, and a slice that starts partway into an element starts at
+/// position 0. Blocks are `u64`s assembled from the slice, so rank and
+/// select structures built over the view see the same bits that
+/// iterating the slice would.
+///
+/// Assembling a block visits the set bits in it, so for repeated
+/// block-heavy use, such as scanning select, copying into a `BitVector`
+/// with `copy_bits_from` may be faster.
+///
+/// Available with the `bitvec-compat` feature.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bitvec;
+/// extern crate succinct;
+///
+/// use bitvec::order::Msb0;
+/// use bitvec::vec::BitVec as BitvecVec;
+/// use succinct::{BitRankSupport, BitVec, JacobsonRank};
+/// use succinct::bit_vec::BitvecBits;
+///
+/// # fn main() {
+/// let mut source: BitvecVec<u8, Msb0> = BitvecVec::repeat(false, 20);
+/// source.set(0, true);
+/// source.set(19, true);
+///
+/// let bits = BitvecBits::new(&source[..]);
+/// assert!(bits.get_bit(0) && bits.get_bit(19));
+/// assert_eq!(2, JacobsonRank::new(bits).total_ones());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BitvecBits<'a, T: 'a + BitStore, O: 'a + BitvecOrder> {
+    slice: &'a BitvecSlice<T, O>,
+}
+
+// Derived `Clone` and `Copy` would require them of `T` and `O`.
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> Clone for BitvecBits<'a, T, O> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> Copy for BitvecBits<'a, T, O> {}
+
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> BitvecBits<'a, T, O> {
+    /// Views `slice` as a bit vector.
+    pub fn new(slice: &'a BitvecSlice<T, O>) -> Self {
+        BitvecBits { slice }
+    }
+
+    /// The underlying slice.
+    pub fn slice(&self) -> &'a BitvecSlice<T, O> {
+        self.slice
+    }
+}
+
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> From<&'a BitvecSlice<T, O>>
+    for BitvecBits<'a, T, O> {
+
+    fn from(slice: &'a BitvecSlice<T, O>) -> Self {
+        Self::new(slice)
+    }
+}
+
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> BitVec for BitvecBits<'a, T, O> {
+    type Block = u64;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.slice.len() as u64
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.bit_len(), "BitvecBits::get_bit: out of bounds");
+        self.slice[position as usize]
+    }
+
+    fn get_block(&self, position: usize) -> u64 {
+        let start = u64::mul_nbits(position) as usize;
+        assert!(start < self.slice.len(), "BitvecBits::get_block: out of bounds");
+        let end = self.slice.len().min(start + u64::nbits());
+
+        self.slice[start .. end].iter_ones()
+            .fold(0, |block, offset| block | 1 << offset)
+    }
+}
+
+impl<'a, T: 'a + BitStore, O: 'a + BitvecOrder> SpaceUsage for BitvecBits<'a, T, O> {
+    fn is_stack_only() -> bool { true }
+    fn heap_bytes(&self) -> usize { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use bitvec::order::{BitOrder as BitvecOrder, Lsb0, Msb0};
+    use bitvec::store::BitStore;
+    use bitvec::vec::BitVec as BitvecVec;
+
+    use bit_vec::*;
+    use internal::test_util::random_bools;
+    use rank::{BitRankSupport, JacobsonRank};
+    use quickcheck::quickcheck;
+
+    fn to_bitvec<T: BitStore, O: BitvecOrder>(bools: &[bool]) -> BitvecVec<T, O> {
+        bools.iter().cloned().collect()
+    }
+
+    // Bits, blocks and rank over the view match a native `BitVector` of
+    // the same bits.
+    fn check<T: BitStore, O: BitvecOrder>(source: &BitvecVec<T, O>, bools: &[bool]) {
+        let native = BitVector::<u64>::from_bits(bools);
+        let bits = BitvecBits::new(&source[..]);
+
+        assert_eq!(native.bit_len(), bits.bit_len());
+        assert_eq!(native.block_len(), bits.block_len());
+        for i in 0 .. native.bit_len() {
+            assert_eq!(native.get_bit(i), bits.get_bit(i));
+        }
+        for i in 0 .. native.block_len() {
+            assert_eq!(native.get_block(i), bits.get_block(i));
+        }
+
+        let expected = JacobsonRank::new(&native);
+        let rank = JacobsonRank::new(bits);
+        assert_eq!(expected.total_ones(), rank.total_ones());
+        for i in 0 .. native.bit_len() {
+            assert_eq!(expected.rank1(i), rank.rank1(i));
+        }
+    }
+
+    #[test]
+    fn orders_and_stores() {
+        let bools = random_bools(1000, 64, 1);
+        check(&to_bitvec::<u8, Lsb0>(&bools), &bools);
+        check(&to_bitvec::<u8, Msb0>(&bools), &bools);
+        check(&to_bitvec::<u16, Msb0>(&bools), &bools);
+        check(&to_bitvec::<u64, Lsb0>(&bools), &bools);
+
+        // The two orders store different elements for the same bits.
+        let lsb = to_bitvec::<u8, Lsb0>(&bools);
+        let msb = to_bitvec::<u8, Msb0>(&bools);
+        assert_ne!(lsb.as_raw_slice(), msb.as_raw_slice());
+    }
+
+    #[test]
+    fn unaligned_subslice() {
+        let bools = random_bools(300, 64, 2);
+        let source = to_bitvec::<u32, Msb0>(&bools);
+        let native = BitVector::<u64>::from_bits(&bools[5 .. 250]);
+        let bits = BitvecBits::from(&source[5 .. 250]);
+
+        assert_eq!(245, bits.bit_len());
+        for i in 0 .. native.block_len() {
+            assert_eq!(native.get_block(i), bits.get_block(i));
+        }
+        assert_eq!(JacobsonRank::new(&native).rank1(200),
+                   JacobsonRank::new(bits).rank1(200));
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_bit_oob() {
+        let source = to_bitvec::<u8, Lsb0>(&[true; 10]);
+        BitvecBits::new(&source[..]).get_bit(10);
+    }
+
+    fn rank_prop(bools: Vec<bool>, start: u8) -> bool {
+        let start = start as usize % (bools.len() + 1);
+        let source = to_bitvec::<u16, Msb0>(&bools);
+        let native = BitVector::<u64>::from_bits(&bools[start ..]);
+        let expected = JacobsonRank::new(&native);
+        let rank = JacobsonRank::new(BitvecBits::new(&source[start ..]));
+        (0 .. native.bit_len()).all(|i| expected.rank1(i) == rank.rank1(i))
+    }
+
+    #[test]
+    fn rank_qc() {
+        quickcheck(rank_prop as fn(Vec<bool>, u8) -> bool);
+    }
+}
@@ -0,0 +1,141 @@
+use bit_vec::traits::*;
+use bit_vec::bit_vector::BitVector;
+use storage::BlockType;
+
+/// Builds a `BitVector` by setting bits in any order.
+///
+/// The vector grows to cover the highest position set, so its final
+/// length is one more than that position. Reserve capacity up front with
+/// `reserve` when the range of positions is known, to avoid reallocating
+/// as it grows.
+#[derive(Clone, Debug)]
+pub struct BitVecBuilder<Block: BlockType = usize> {
+    bits: BitVector<Block>,
+}
+
+impl<Block: BlockType> BitVecBuilder<Block> {
+    /// Creates a new builder with no bits set.
+    pub fn new() -> Self {
+        BitVecBuilder {
+            bits: BitVector::new(),
+        }
+    }
+
+    /// Reserves capacity for a vector of at least `bits` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of blocks overflows a `usize`.
+    pub fn reserve(&mut self, bits: u64) {
+        let additional = bits.saturating_sub(self.bits.bit_len());
+        self.bits.reserve(additional);
+    }
+
+    /// Sets the bit at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of blocks needed overflows a `usize`.
+    pub fn set(&mut self, position: u64) {
+        if position >= self.bits.bit_len() {
+            let new_len = position.checked_add(1)
+                                  .expect("BitVecBuilder::set: overflow");
+            self.bits.resize(new_len, false);
+        }
+        self.bits.set_bit(position, true);
+    }
+
+    /// The length the built vector will have: one more than the highest
+    /// position set, or 0 if none has been.
+    pub fn len(&self) -> u64 {
+        self.bits.bit_len()
+    }
+
+    /// Have no bits been set?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Finishes building.
+    pub fn build(self) -> BitVector<Block> {
+        self.bits
+    }
+}
+
+impl<Block: BlockType> Default for BitVecBuilder<Block> {
+    fn default() -> Self {
+        BitVecBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVecPush;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn scattered() {
+        let positions = [70, 3, 129, 3, 0, 64, 63, 200, 5];
+
+        let mut builder = BitVecBuilder::<u32>::new();
+        builder.reserve(201);
+        for &position in &positions {
+            builder.set(position);
+        }
+        assert_eq!(201, builder.len());
+
+        let mut expected = BitVector::<u32>::with_fill(201, false);
+        for &position in &positions {
+            expected.set_bit(position, true);
+        }
+        assert_eq!(expected, builder.build());
+    }
+
+    #[test]
+    fn reserve_doesnt_reallocate() {
+        let mut builder = BitVecBuilder::<u64>::new();
+        builder.reserve(10_000);
+        builder.set(0);
+        let capacity = builder.bits.capacity();
+
+        for position in (0 .. 10_000).rev().step_by(7) {
+            builder.set(position);
+        }
+        assert_eq!(capacity, builder.bits.capacity());
+        assert_eq!(10_000, builder.build().bit_len());
+    }
+
+    #[test]
+    fn empty() {
+        let builder = BitVecBuilder::<u8>::default();
+        assert!(builder.is_empty());
+        assert_eq!(BitVector::new(), builder.build());
+    }
+
+    fn build_prop(positions: Vec<u16>) -> bool {
+        let mut builder = BitVecBuilder::<u8>::new();
+        for &position in &positions {
+            builder.set(position as u64);
+        }
+
+        let mut sorted = positions.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        let mut expected = BitVector::<u8>::new();
+        for &position in &sorted {
+            while expected.bit_len() < position as u64 {
+                expected.push_bit(false);
+            }
+            expected.push_bit(true);
+        }
+
+        builder.build() == expected
+    }
+
+    #[test]
+    fn build_qc() {
+        quickcheck(build_prop as fn(Vec<u16>) -> bool);
+    }
+}
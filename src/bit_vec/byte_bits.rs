@@ -0,0 +1,150 @@
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+use super::bit_vector::BitOrder;
+
+/// A borrowed byte buffer viewed as a bit vector, without copying.
+///
+/// Each byte is one `u8` block, so rank and select structures can be
+/// built directly over memory owned elsewhere, such as a memory-mapped
+/// file. By default the bits of each byte are read most significant bit
+/// first, giving the same bits as `BitVector::from_bytes`.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitRankSupport, BitVec, JacobsonRank};
+/// use succinct::bit_vec::ByteBits;
+///
+/// let bytes = [0b1000_0001, 0xFF];
+/// let bits = ByteBits::new(&bytes);
+/// assert!(bits.get_bit(0));
+/// assert!(!bits.get_bit(1));
+///
+/// let rank = JacobsonRank::new(bits);
+/// assert_eq!(10, rank.total_ones());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ByteBits<'a> {
+    bytes: &'a [u8],
+    order: BitOrder,
+}
+
+impl<'a> ByteBits<'a> {
+    /// Views `bytes` as `8 * bytes.len()` bits, most significant bit of
+    /// each byte first.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_order(bytes, BitOrder::MsbFirst)
+    }
+
+    /// Views `bytes` as `8 * bytes.len()` bits, reading the bits of each
+    /// byte in the given order.
+    pub fn with_order(bytes: &'a [u8], order: BitOrder) -> Self {
+        ByteBits {
+            bytes,
+            order,
+        }
+    }
+
+    /// The bit order used to read each byte.
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// The underlying bytes.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> BitVec for ByteBits<'a> {
+    type Block = u8;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        8 * self.bytes.len() as u64
+    }
+
+    #[inline]
+    fn block_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> u8 {
+        let byte = self.bytes[position];
+        match self.order {
+            BitOrder::MsbFirst => byte.reverse_block(),
+            BitOrder::LsbFirst => byte,
+        }
+    }
+}
+
+impl<'a> SpaceUsage for ByteBits<'a> {
+    fn is_stack_only() -> bool { true }
+    fn heap_bytes(&self) -> usize { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+    use rank::{BitRankSupport, JacobsonRank};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn known_bytes() {
+        let bytes = [0b1010_0000, 0b0000_0011];
+        let bits = ByteBits::new(&bytes);
+        assert_eq!(16, bits.bit_len());
+        assert_eq!(2, bits.block_len());
+
+        let read: Vec<bool> = (0 .. 16).map(|i| bits.get_bit(i)).collect();
+        let mut expected = vec![false; 16];
+        expected[0] = true;
+        expected[2] = true;
+        expected[14] = true;
+        expected[15] = true;
+        assert_eq!(expected, read);
+
+        let lsb = ByteBits::with_order(&bytes, BitOrder::LsbFirst);
+        assert!(lsb.get_bit(5));
+        assert!(lsb.get_bit(7));
+        assert!(lsb.get_bit(8));
+        assert!(!lsb.get_bit(0));
+        assert_eq!(0b0000_0011, lsb.get_block(1));
+    }
+
+    #[test]
+    fn empty() {
+        let bits = ByteBits::new(&[]);
+        assert_eq!(0, bits.bit_len());
+        assert_eq!(0, bits.block_len());
+    }
+
+    #[test]
+    fn rank_over_bytes() {
+        let bytes: Vec<u8> = (0 .. 200).map(|i| (i * 37) as u8).collect();
+        let rank = JacobsonRank::new(ByteBits::new(&bytes));
+        let vector = JacobsonRank::new(BitVector::<u8>::from_bytes(&bytes));
+
+        for i in 0 .. vector.bit_len() {
+            assert_eq!(vector.rank1(i), rank.rank1(i));
+        }
+    }
+
+    fn matches_from_bytes_prop(bytes: Vec<u8>) -> bool {
+        [BitOrder::MsbFirst, BitOrder::LsbFirst].iter().all(|&order| {
+            let view = ByteBits::with_order(&bytes, order);
+            let vector = BitVector::<u8>::from_bytes_with_order(&bytes, order);
+            view.bit_len() == vector.bit_len()
+                && (0 .. view.bit_len()).all(|i| view.get_bit(i) == vector.get_bit(i))
+                && (0 .. view.block_len())
+                       .all(|i| view.get_block(i) == vector.get_block(i))
+        })
+    }
+
+    #[test]
+    fn matches_from_bytes_qc() {
+        quickcheck(matches_from_bytes_prop as fn(Vec<u8>) -> bool);
+    }
+}
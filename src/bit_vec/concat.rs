@@ -0,0 +1,276 @@
+use std::cmp;
+use std::mem;
+
+use num_traits::Zero;
+
+use bit_vec::traits::*;
+use rank::{BitRankSupport, RankSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A borrowed view of several bit vectors, one after another, as a single
+/// vector.
+///
+/// Positions are mapped to segments by binary search over their starting
+/// positions, so segments needn’t be a whole number of blocks long;
+/// `get_block` stitches blocks that span segments. Segments may be of
+/// different types behind `&dyn BitVec`, such as chunks of an index
+/// loaded separately.
+///
+/// When the segments support rank, so does the view: the rank of a
+/// position is the rank within its segment plus the number of 1s in the
+/// segments before it, which takes time linear in the number of segments.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitRankSupport, BitVec, BitVector, JacobsonRank};
+/// use succinct::bit_vec::ConcatBits;
+///
+/// let first = BitVector::<u64>::with_fill(10, true);
+/// let second: Vec<u64> = vec![0b101];
+/// let bits = ConcatBits::new(vec![&first as &dyn BitVec<Block = u64>, &second]);
+/// assert_eq!(74, bits.bit_len());
+/// assert!(bits.get_bit(9) && bits.get_bit(10) && !bits.get_bit(11));
+///
+/// let ranks = [JacobsonRank::new(&first), JacobsonRank::new(&first)];
+/// let ranked = ConcatBits::new(ranks.iter().collect());
+/// assert_eq!(15, ranked.rank1(14));
+/// ```
+#[derive(Debug)]
+pub struct ConcatBits<'a, Segment: 'a + BitVec + ?Sized> {
+    segments: Vec<&'a Segment>,
+    // The position at which each segment starts, and then the total.
+    starts: Vec<u64>,
+}
+
+// Derived `Clone` would require `Segment: Clone`, which `dyn BitVec`
+// isn't.
+impl<'a, Segment: 'a + BitVec + ?Sized> Clone for ConcatBits<'a, Segment> {
+    fn clone(&self) -> Self {
+        ConcatBits {
+            segments: self.segments.clone(),
+            starts: self.starts.clone(),
+        }
+    }
+}
+
+impl<'a, Segment: 'a + BitVec + ?Sized> ConcatBits<'a, Segment> {
+    /// Views `segments` as one vector, in order.
+    pub fn new(segments: Vec<&'a Segment>) -> Self {
+        let mut starts = Vec::with_capacity(segments.len() + 1);
+        let mut total: u64 = 0;
+        starts.push(0);
+        for segment in &segments {
+            total = total.checked_add(segment.bit_len())
+                         .expect("ConcatBits::new: overflow");
+            starts.push(total);
+        }
+
+        ConcatBits { segments, starts }
+    }
+
+    /// The segments, in order.
+    pub fn segments(&self) -> &[&'a Segment] {
+        &self.segments
+    }
+
+    /// The segment holding `position`, and the position within it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.bit_len()`.
+    pub fn locate(&self, position: u64) -> (usize, u64) {
+        assert!(position < self.bit_len(), "ConcatBits::locate: out of bounds");
+        // Empty segments share their start with the next segment, and
+        // this finds the last of those.
+        let index = self.starts.partition_point(|&start| start <= position) - 1;
+        (index, position - self.starts[index])
+    }
+}
+
+impl<'a, Segment: 'a + BitVec + ?Sized> BitVec for ConcatBits<'a, Segment> {
+    type Block = Segment::Block;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.starts[self.segments.len()]
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        let (index, offset) = self.locate(position);
+        self.segments[index].get_bit(offset)
+    }
+
+    fn get_block(&self, position: usize) -> Self::Block {
+        let start = Self::Block::mul_nbits(position);
+        assert!(start < self.bit_len(), "ConcatBits::get_block: out of bounds");
+
+        let count = cmp::min(Self::Block::nbits() as u64, self.bit_len() - start);
+        let mut result = Self::Block::zero();
+        let mut filled = 0;
+        while filled < count {
+            let (index, offset) = self.locate(start + filled);
+            let segment = self.segments[index];
+            let take = cmp::min(count - filled, segment.bit_len() - offset);
+            let part = segment.get_bits(offset, take as usize);
+            result = result | part << filled as usize;
+            filled += take;
+        }
+
+        result
+    }
+}
+
+impl<'a, Segment> RankSupport for ConcatBits<'a, Segment>
+    where Segment: 'a + BitVec + BitRankSupport + ?Sized {
+
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.bit_len()
+    }
+}
+
+impl<'a, Segment> BitRankSupport for ConcatBits<'a, Segment>
+    where Segment: 'a + BitVec + BitRankSupport + ?Sized {
+
+    fn rank1(&self, position: u64) -> u64 {
+        let (index, offset) = self.locate(position);
+        let before: u64 = self.segments[.. index].iter()
+            .map(|segment| segment.total_ones())
+            .sum();
+        before + self.segments[index].rank1(offset)
+    }
+
+    fn total_ones(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.total_ones()).sum()
+    }
+}
+
+impl<'a, Segment: 'a + BitVec + ?Sized> SpaceUsage for ConcatBits<'a, Segment> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.segments.capacity() * mem::size_of::<&Segment>()
+            + self.starts.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp;
+
+    use bit_vec::*;
+    use rank::{BitRankSupport, JacobsonRank, Rank9};
+    use quickcheck::quickcheck;
+
+    fn segments() -> (BitVector<u64>, Vec<u64>, BitVector<u64>) {
+        let first = BitVector::from_fn(37, |i| i % 3 == 0);
+        let second = vec![0xF0F0_F0F0_0000_FFFFu64, 1];
+        let third = BitVector::from_fn(100, |i| i % 7 < 2);
+        (first, second, third)
+    }
+
+    fn merged<Bits: BitVec + ?Sized>(parts: &[&Bits]) -> BitVector<u64> {
+        let mut result = BitVector::new();
+        for part in parts {
+            for i in 0 .. part.bit_len() {
+                result.push_bit(part.get_bit(i));
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn three_segments() {
+        let (first, second, third) = segments();
+        let parts: Vec<&dyn BitVec<Block = u64>> = vec![&first, &second, &third];
+        let reference = merged(&parts);
+        let concat = ConcatBits::new(parts);
+
+        assert_eq!(37 + 128 + 100, concat.bit_len());
+        assert_eq!(reference.block_len(), concat.block_len());
+        assert_eq!((1, 3), concat.locate(40));
+        for i in 0 .. reference.bit_len() {
+            assert_eq!(reference.get_bit(i), concat.get_bit(i));
+        }
+        for i in 0 .. reference.block_len() {
+            assert_eq!(reference.get_block(i), concat.get_block(i));
+        }
+
+        // Rank over the view, with the view as a plain `BitVec`.
+        let expected = JacobsonRank::new(&reference);
+        let rank = JacobsonRank::new(&concat);
+        for i in 0 .. reference.bit_len() {
+            assert_eq!(expected.rank1(i), rank.rank1(i));
+        }
+    }
+
+    #[test]
+    fn rank_sums_segments() {
+        let (first, second, third) = segments();
+        let reference = merged::<dyn BitVec<Block = u64>>(&[&first, &second, &third]);
+        let expected = JacobsonRank::new(&reference);
+
+        let ranks = [Rank9::new(first), Rank9::new(BitVector::from(second)),
+                     Rank9::new(third)];
+        let concat = ConcatBits::new(ranks.iter().collect());
+        assert_eq!(expected.total_ones(), concat.total_ones());
+        for i in 0 .. reference.bit_len() {
+            assert_eq!(expected.rank1(i), concat.rank1(i));
+            assert_eq!(expected.rank0(i), concat.rank0(i));
+        }
+    }
+
+    #[test]
+    fn empty_segments() {
+        let empty = BitVector::<u8>::new();
+        let ones = BitVector::<u8>::with_fill(5, true);
+        let concat = ConcatBits::new(vec![&empty, &ones, &empty, &empty, &ones, &empty]);
+        assert_eq!(10, concat.bit_len());
+        assert_eq!((1, 0), concat.locate(0));
+        assert_eq!((4, 0), concat.locate(5));
+        assert_eq!(0xFF, concat.get_block(0));
+        assert_eq!(0b11, concat.get_block(1));
+
+        let none: ConcatBits<BitVector<u8>> = ConcatBits::new(vec![]);
+        assert_eq!(0, none.bit_len());
+        assert_eq!(0, none.block_len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_bit_oob() {
+        let ones = BitVector::<u8>::with_fill(5, true);
+        ConcatBits::new(vec![&ones, &ones]).get_bit(10);
+    }
+
+    fn concat_prop(parts: Vec<Vec<bool>>) -> bool {
+        let parts: Vec<JacobsonRank<BitVector<u8>>> = parts.iter()
+            .map(|bits| JacobsonRank::new(BitVector::from_bits(bits)))
+            .collect();
+        let concat = ConcatBits::new(parts.iter().collect());
+        let reference = merged(&parts.iter().collect::<Vec<_>>());
+        let expected = JacobsonRank::new(&reference);
+
+        (0 .. reference.bit_len()).all(|i| {
+            concat.get_bit(i) == reference.get_bit(i)
+                && concat.rank1(i) == expected.rank1(i)
+        }) && (0 .. concat.block_len()).all(|i| {
+            let start = 8 * i as u64;
+            let count = cmp::min(8, reference.bit_len() - start) as usize;
+            concat.get_block(i) as u64 == reference.get_bits(start, count)
+        })
+    }
+
+    #[test]
+    fn concat_qc() {
+        quickcheck(concat_prop as fn(Vec<Vec<bool>>) -> bool);
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+use bit_vec::traits::BitVec;
+
+/// Displays a bit vector as `0`s and `1`s, in groups of eight.
+///
+/// Bits are printed in position order, so bit 0 comes first; this is the
+/// reverse of the `{:b}` format for integers. Groups are separated by a
+/// single space, with no separator after the last group.
+///
+/// Construct with [`BitVecExt::display`](trait.BitVecExt.html#method.display).
+pub struct BitDisplay<'a, Bits: 'a + ?Sized> {
+    data: &'a Bits,
+}
+
+impl<'a, Bits: BitVec + ?Sized> BitDisplay<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        BitDisplay { data }
+    }
+}
+
+impl<'a, Bits: BitVec + ?Sized> fmt::Display for BitDisplay<'a, Bits> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0 .. self.data.bit_len() {
+            if i > 0 && i & 7 == 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(if self.data.get_bit(i) { "1" } else { "0" })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, Bits: BitVec + ?Sized> fmt::Debug for BitDisplay<'a, Bits> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitDisplay(\"{}\")", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+
+    #[test]
+    fn known_pattern() {
+        let blocks = vec![ 0b00010010u8, 0b10000001 ];
+        assert_eq!("01001000 10000001", blocks.display().to_string());
+    }
+
+    #[test]
+    fn partial_group() {
+        let mut bv: BitVector<u32> = BitVector::new();
+        for i in 0 .. 11 {
+            bv.push_bit(i % 3 < 1);
+        }
+        assert_eq!("10010010 010", bv.display().to_string());
+
+        bv.truncate(8);
+        assert_eq!("10010010", bv.display().to_string());
+
+        bv.clear();
+        assert_eq!("", bv.display().to_string());
+    }
+
+    #[test]
+    fn debug() {
+        let blocks = vec![ 0b101u8 ];
+        assert_eq!("BitDisplay(\"10100000\")", format!("{:?}", blocks.display()));
+    }
+}
@@ -0,0 +1,750 @@
+use std::cmp;
+
+use num_traits::{PrimInt, ToPrimitive, Zero};
+
+use bit_vec::traits::*;
+use bit_vec::bit_vector::BitVector;
+use bit_vec::iter::*;
+use bit_vec::display::BitDisplay;
+use storage::BlockType;
+
+/// Derived read-only operations, implemented for every `BitVec`.
+///
+/// These are defined in terms of `bit_len` and `get_block`, so they are
+/// as fast as the underlying `get_block` allows.
+pub trait BitVecExt: BitVec {
+    /// Returns an iterator over the bits of the vector, from position 0
+    /// to `bit_len() - 1`.
+    fn bits(&self) -> BitIter<'_, Self> {
+        BitIter::new(self)
+    }
+
+    /// Returns an iterator over the blocks of the vector, in order, with
+    /// the bits past `bit_len` in the final block cleared.
+    fn blocks(&self) -> BlockIter<'_, Self> {
+        BlockIter::new(self)
+    }
+
+    /// Returns an iterator over the positions of the 1 bits, in
+    /// ascending order.
+    fn ones(&self) -> Ones<'_, Self> {
+        Ones::new(self)
+    }
+
+    /// Returns an iterator over the positions of the 0 bits, in
+    /// ascending order.
+    fn zeros(&self) -> Zeros<'_, Self> {
+        Zeros::new(self)
+    }
+
+    /// Returns an iterator over the maximal runs of equal bits, as
+    /// `(bit, length)` pairs in order.
+    ///
+    /// Each run is found a block at a time, so long runs are cheap.
+    fn runs(&self) -> Runs<'_, Self> {
+        Runs::new(self)
+    }
+
+    /// Returns an iterator over the number of 1s in each consecutive,
+    /// non-overlapping window of `window` bits, starting at position 0.
+    ///
+    /// If `window` doesn’t divide `bit_len()`, the last window is short.
+    /// Each window is counted a block at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is 0.
+    fn popcount_windows(&self, window: u64) -> PopcountWindows<'_, Self> {
+        PopcountWindows::new(self, window)
+    }
+
+    /// Returns the number of positions at which `self` and `other`
+    /// differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn hamming_distance<Other>(&self, other: &Other) -> u64
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        assert!(self.bit_len() == other.bit_len(),
+                "BitVecExt::hamming_distance: lengths differ");
+
+        self.blocks().zip(other.blocks())
+            .map(|(a, b)| (a ^ b).count_ones() as u64)
+            .sum()
+    }
+
+    /// Returns the bitwise XOR of `self` and `other`, which has a 1 at
+    /// each position where they differ.
+    ///
+    /// Applying the result to `self` with
+    /// [`BitVecOps::apply_patch`](trait.BitVecOps.html#method.apply_patch)
+    /// gives `other`, and applying it to `other` gives `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn diff<Other>(&self, other: &Other) -> BitVector<Self::Block>
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        assert!(self.bit_len() == other.bit_len(),
+                "BitVecExt::diff: lengths differ");
+
+        let mut result = BitVector::block_with_capacity(self.block_len());
+        for (a, b) in self.blocks().zip(other.blocks()) {
+            result.push_block(a ^ b);
+        }
+        result.truncate(self.bit_len());
+        result
+    }
+
+    /// Do `self` and `other` hold the same bits?
+    ///
+    /// This compares lengths and then blocks, ignoring the bits past the
+    /// end of the final block, so it works across different `BitVec`
+    /// types with the same block type.
+    fn bits_eq<Other>(&self, other: &Other) -> bool
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        self.bit_len() == other.bit_len() && self.blocks().eq(other.blocks())
+    }
+
+    /// Is every 1 bit of `self` also set in `other`?
+    ///
+    /// Stops at the first block with a 1 that `other` lacks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn is_subset<Other>(&self, other: &Other) -> bool
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        assert!(self.bit_len() == other.bit_len(),
+                "BitVecExt::is_subset: lengths differ");
+
+        self.blocks().zip(other.blocks())
+            .all(|(a, b)| a & !b == Self::Block::zero())
+    }
+
+    /// Do `self` and `other` have a 1 bit in common?
+    ///
+    /// Stops at the first block where they do.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn intersects<Other>(&self, other: &Other) -> bool
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        assert!(self.bit_len() == other.bit_len(),
+                "BitVecExt::intersects: lengths differ");
+
+        self.blocks().zip(other.blocks())
+            .any(|(a, b)| a & b != Self::Block::zero())
+    }
+
+    /// Returns the number of 0s below the lowest 1, or `bit_len()` if
+    /// there are no 1s.
+    fn count_trailing_zeros(&self) -> u64 {
+        for (i, block) in self.blocks().enumerate() {
+            if block != Self::Block::zero() {
+                return Self::Block::mul_nbits(i) + block.trailing_zeros() as u64;
+            }
+        }
+
+        self.bit_len()
+    }
+
+    /// Returns the number of 0s above the highest 1, counting down from
+    /// position `bit_len() - 1`, or `bit_len()` if there are no 1s.
+    fn count_leading_zeros(&self) -> u64 {
+        for (i, block) in self.blocks().enumerate().rev() {
+            if block != Self::Block::zero() {
+                let offset = Self::Block::nbits() - 1 - block.leading_zeros() as usize;
+                return self.bit_len() - 1 - Self::Block::mul_nbits(i) - offset as u64;
+            }
+        }
+
+        self.bit_len()
+    }
+
+    /// Returns the length of the run of 1s starting at position 0, or
+    /// `bit_len()` if there are no 0s.
+    fn count_trailing_ones(&self) -> u64 {
+        for (i, block) in self.blocks().enumerate() {
+            if block != !Self::Block::zero() {
+                // `blocks` clears the bits past the end, so the run
+                // stops there at the latest.
+                return Self::Block::mul_nbits(i) + (!block).trailing_zeros() as u64;
+            }
+        }
+
+        self.bit_len()
+    }
+
+    /// Returns the length of the run of 1s ending at position
+    /// `bit_len() - 1`, or `bit_len()` if there are no 0s.
+    fn count_leading_ones(&self) -> u64 {
+        let mut run = 0;
+        for (i, block) in self.blocks().enumerate().rev() {
+            let bits = if i + 1 == self.block_len() {
+                Self::Block::last_block_bits(self.bit_len())
+            } else {
+                Self::Block::nbits()
+            };
+
+            let zeros = !block & Self::Block::low_mask(bits);
+            if zeros != Self::Block::zero() {
+                let highest = Self::Block::nbits() - 1 - zeros.leading_zeros() as usize;
+                return run + (bits - 1 - highest) as u64;
+            }
+            run += bits as u64;
+        }
+
+        run
+    }
+
+    /// Returns a CRC-64 of the bits and the length, for detecting
+    /// corruption.
+    ///
+    /// The checksum depends only on `bit_len()` and the bits themselves,
+    /// not on the block type or any capacity, so it is stable across
+    /// representations: the bits are read eight at a time, bit 0 lowest,
+    /// the last byte padded with 0s, and followed by the length as eight
+    /// little-endian bytes. The CRC is CRC-64/XZ.
+    fn checksum(&self) -> u64 {
+        let len = self.bit_len();
+        let mut crc = Crc64::new();
+        let mut position = 0;
+        while position < len {
+            let count = cmp::min(8, len - position) as usize;
+            crc.update(self.get_bits(position, count).to_u8().unwrap());
+            position += 8;
+        }
+        for &byte in &len.to_le_bytes() {
+            crc.update(byte);
+        }
+        crc.finish()
+    }
+
+    /// Returns a wrapper that displays the bits as `0`s and `1`s, bit 0
+    /// first, in space-separated groups of eight.
+    fn display(&self) -> BitDisplay<'_, Self> {
+        BitDisplay::new(self)
+    }
+}
+
+impl<T: BitVec + ?Sized> BitVecExt for T {}
+
+// CRC-64/XZ, the reflected ECMA-182 polynomial, a byte at a time.
+struct Crc64(u64);
+
+const CRC64_POLY: u64 = 0xC96C_5795_D787_0F42;
+
+const CRC64_TABLE: [u64; 256] = crc64_table();
+
+const fn crc64_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ CRC64_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Crc64 {
+    fn new() -> Self {
+        Crc64(!0)
+    }
+
+    #[inline]
+    fn update(&mut self, byte: u8) {
+        self.0 = CRC64_TABLE[(self.0 as u8 ^ byte) as usize] ^ (self.0 >> 8);
+    }
+
+    fn finish(&self) -> u64 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use quickcheck::quickcheck;
+
+    use bit_vec::*;
+
+    fn from_bools(bits: &[bool]) -> BitVector<u8> {
+        let mut result = BitVector::new();
+        for &bit in bits {
+            result.push_bit(bit);
+        }
+        result
+    }
+
+    fn ones_zeros_prop(bits: Vec<bool>) -> bool {
+        let bv = from_bools(&bits);
+
+        bv.ones().eq((0 .. bv.bit_len()).filter(|&i| bv.get_bit(i)))
+            && bv.zeros().eq((0 .. bv.bit_len()).filter(|&i| !bv.get_bit(i)))
+    }
+
+    #[test]
+    fn ones_zeros_qc() {
+        quickcheck(ones_zeros_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn bits() {
+        let vec = vec![ 0b00010010u8, 0, 0b10000001 ];
+        let forward: Vec<bool> = vec.bits().collect();
+
+        assert_eq!(24, vec.bits().count());
+        assert_eq!(24, vec.bits().len());
+        assert_eq!(vec![ false, true, false, false, true, false, false, false ],
+                   &forward[.. 8]);
+        assert!(forward[16] && forward[23]);
+
+        let mut backward: Vec<bool> = vec.bits().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn bits_both_ends() {
+        let bv = from_bools(&[ true, false, false, true, true ]);
+        let mut iter = bv.bits();
+
+        assert_eq!(5, iter.len());
+        assert_eq!(Some(true), iter.next());
+        assert_eq!(Some(true), iter.next_back());
+        assert_eq!(3, iter.len());
+        assert_eq!(Some(true), iter.next_back());
+        assert_eq!(Some(false), iter.nth(1));
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn bits_qc() {
+        fn prop(bits: Vec<bool>) -> bool {
+            let bv = from_bools(&bits);
+            bv.bits().len() == bits.len()
+                && bv.bits().eq(bits.iter().cloned())
+                && bv.bits().rev().eq(bits.iter().rev().cloned())
+        }
+
+        quickcheck(prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn ones() {
+        let vec = vec![ 0b00010010u8, 0, 0b10000001 ];
+        assert_eq!(vec![ 1, 4, 16, 23 ], vec.ones().collect::<Vec<_>>());
+
+        let empty: Vec<u32> = vec![];
+        assert_eq!(None, empty.ones().next());
+        assert_eq!(None, vec![ 0u64; 4 ].ones().next());
+        assert_eq!(256, vec![ !0u64; 4 ].ones().count());
+    }
+
+    #[test]
+    fn zeros_partial_block() {
+        let bv = from_bools(&[ true, false, true, true, false ]);
+        assert_eq!(vec![ 1, 4 ], bv.zeros().collect::<Vec<_>>());
+
+        let slice = BitSlice::new(&bv, 2 .. 4);
+        assert_eq!(None, slice.zeros().next());
+        assert_eq!(vec![ 0, 1 ], slice.ones().collect::<Vec<_>>());
+    }
+
+    fn hamming_prop(pairs: Vec<(bool, bool)>) -> bool {
+        let (xs, ys): (Vec<bool>, Vec<bool>) = pairs.into_iter().unzip();
+        let x = from_bools(&xs);
+        let y = from_bools(&ys);
+        let naive = xs.iter().zip(&ys).filter(|&(a, b)| a != b).count() as u64;
+
+        x.hamming_distance(&y) == naive && y.hamming_distance(&x) == naive
+    }
+
+    #[test]
+    fn hamming_distance_qc() {
+        quickcheck(hamming_prop as fn(Vec<(bool, bool)>) -> bool);
+    }
+
+    #[test]
+    fn hamming_distance_extremes() {
+        for &len in &[0, 1, 7, 8, 9, 100] {
+            let bits: Vec<bool> = (0 .. len).map(|i| i % 3 < 1).collect();
+            let flipped: Vec<bool> = bits.iter().map(|&b| !b).collect();
+            let x = from_bools(&bits);
+
+            assert_eq!(0, x.hamming_distance(&x));
+            assert_eq!(len, x.hamming_distance(&from_bools(&flipped)));
+        }
+    }
+
+    // A one-block vector whose `get_block` doesn’t clear the bits past
+    // `bit_len`.
+    struct Dirty(u8, u64);
+
+    impl BitVec for Dirty {
+        type Block = u8;
+        fn bit_len(&self) -> u64 { self.1 }
+        fn get_block(&self, _position: usize) -> u8 { self.0 }
+    }
+
+    #[test]
+    fn blocks_masks_padding() {
+        let dirty = Dirty(0xFF, 5);
+        assert_eq!(vec![0b11111], dirty.blocks().collect::<Vec<_>>());
+        assert_eq!(1, dirty.blocks().len());
+    }
+
+    fn blocks_prop(bits: Vec<bool>) -> bool {
+        let bv = from_bools(&bits);
+        let blocks: Vec<u8> = bv.blocks().collect();
+        let backward: Vec<u8> = bv.blocks().rev().collect();
+
+        blocks.len() == bv.block_len()
+            && bv.blocks().len() == bv.block_len()
+            && (0 .. bv.bit_len()).all(|i| {
+                   blocks[i as usize / 8] >> (i % 8) & 1 == bv.get_bit(i) as u8
+               })
+            && blocks.last().is_none_or(|&last| {
+                   let used = bits.len() - 8 * (blocks.len() - 1);
+                   used == 8 || last >> used == 0
+               })
+            && backward.into_iter().rev().eq(blocks)
+    }
+
+    #[test]
+    fn blocks_qc() {
+        quickcheck(blocks_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn hamming_distance_ignores_padding() {
+        let x = Dirty(0b11100000, 5);
+        let y = Dirty(0b00000001, 5);
+        assert_eq!(1, x.hamming_distance(&y));
+    }
+
+    #[test]
+    #[should_panic]
+    fn hamming_distance_mismatch() {
+        from_bools(&[true]).hamming_distance(&from_bools(&[true, false]));
+    }
+
+    #[test]
+    fn subset_and_intersects() {
+        let evens = from_bools(&(0 .. 50).map(|i| i % 2 == 0).collect::<Vec<_>>());
+        let fours = from_bools(&(0 .. 50).map(|i| i % 4 == 0).collect::<Vec<_>>());
+        let odds = from_bools(&(0 .. 50).map(|i| i % 2 == 1).collect::<Vec<_>>());
+        let threes = from_bools(&(0 .. 50).map(|i| i % 3 == 0).collect::<Vec<_>>());
+        let empty = from_bools(&[false; 50]);
+
+        assert!(fours.is_subset(&evens));
+        assert!(!evens.is_subset(&fours));
+        assert!(evens.is_subset(&evens));
+        assert!(empty.is_subset(&odds));
+        assert!(!threes.is_subset(&evens));
+
+        assert!(fours.intersects(&evens));
+        assert!(!evens.intersects(&odds));
+        assert!(threes.intersects(&evens));
+        assert!(threes.intersects(&odds));
+        assert!(!empty.intersects(&empty));
+    }
+
+    #[test]
+    fn subset_ignores_padding() {
+        assert!(Dirty(0b11100001, 5).is_subset(&Dirty(0b00000001, 5)));
+        assert!(!Dirty(0b11100000, 5).intersects(&Dirty(0b11100000, 5)));
+    }
+
+    // Counts the calls to `get_block`.
+    struct Counting<'a>(&'a BitVector<u64>, Cell<usize>);
+
+    impl<'a> BitVec for Counting<'a> {
+        type Block = u64;
+        fn bit_len(&self) -> u64 { self.0.bit_len() }
+        fn get_block(&self, position: usize) -> u64 {
+            self.1.set(self.1.get() + 1);
+            self.0.get_block(position)
+        }
+    }
+
+    #[test]
+    fn subset_and_intersects_exit_early() {
+        let mut base = BitVector::<u64>::with_fill(1 << 20, false);
+        base.set_bit(100, true);
+        let mut other = BitVector::<u64>::with_fill(1 << 20, false);
+        other.set_bit(100, true);
+        other.set_bit(200, true);
+
+        let counting = Counting(&other, Cell::new(0));
+        assert!(!counting.is_subset(&base));
+        assert_eq!(4, counting.1.get());
+
+        let counting = Counting(&other, Cell::new(0));
+        assert!(counting.intersects(&base));
+        assert_eq!(2, counting.1.get());
+
+        // Without an early exit every block is read.
+        let counting = Counting(&base, Cell::new(0));
+        assert!(counting.is_subset(&other));
+        assert_eq!(base.block_len(), counting.1.get());
+    }
+
+    #[test]
+    #[should_panic]
+    fn is_subset_mismatch() {
+        from_bools(&[true]).is_subset(&from_bools(&[true, false]));
+    }
+
+    fn subset_intersects_prop(pairs: Vec<(bool, bool)>) -> bool {
+        let x = from_bools(&pairs.iter().map(|p| p.0).collect::<Vec<_>>());
+        let y = from_bools(&pairs.iter().map(|p| p.1).collect::<Vec<_>>());
+        x.is_subset(&y) == pairs.iter().all(|&(a, b)| !a || b)
+            && x.intersects(&y) == pairs.iter().any(|&(a, b)| a && b)
+    }
+
+    #[test]
+    fn subset_intersects_qc() {
+        quickcheck(subset_intersects_prop as fn(Vec<(bool, bool)>) -> bool);
+    }
+
+    #[test]
+    fn count_zeros_at_ends() {
+        let empty = BitVector::<u8>::new();
+        assert_eq!(0, empty.count_trailing_zeros());
+        assert_eq!(0, empty.count_leading_zeros());
+
+        let zeros = BitVector::<u8>::with_fill(21, false);
+        assert_eq!(21, zeros.count_trailing_zeros());
+        assert_eq!(21, zeros.count_leading_zeros());
+
+        let ones = BitVector::<u8>::with_fill(21, true);
+        assert_eq!(0, ones.count_trailing_zeros());
+        assert_eq!(0, ones.count_leading_zeros());
+
+        // A single 1 at each position, across the u8 block boundaries.
+        for position in 0 .. 21 {
+            let mut bv = BitVector::<u8>::with_fill(21, false);
+            bv.set_bit(position, true);
+            assert_eq!(position, bv.count_trailing_zeros());
+            assert_eq!(20 - position, bv.count_leading_zeros());
+        }
+
+        let vec = vec![ 0u32, 0b100, 0 ];
+        assert_eq!(34, vec.count_trailing_zeros());
+        assert_eq!(61, vec.count_leading_zeros());
+    }
+
+    fn count_zeros_prop(bits: Vec<bool>) -> bool {
+        let bv = from_bools(&bits);
+        let trailing = bits.iter().take_while(|&&bit| !bit).count() as u64;
+        let leading = bits.iter().rev().take_while(|&&bit| !bit).count() as u64;
+        bv.count_trailing_zeros() == trailing && bv.count_leading_zeros() == leading
+    }
+
+    #[test]
+    fn count_zeros_qc() {
+        quickcheck(count_zeros_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn crc64_check_value() {
+        let mut crc = super::Crc64::new();
+        for &byte in b"123456789" {
+            crc.update(byte);
+        }
+        assert_eq!(0x995D_C9BB_DF19_39FA, crc.finish());
+    }
+
+    #[test]
+    fn checksum() {
+        let bits: Vec<bool> = (0 .. 1000).map(|i| i % 7 == 0 || i % 11 == 3).collect();
+        let expected = from_bools(&bits).checksum();
+
+        // The same bits in other block types and capacities.
+        let mut wide: BitVector<u64> = BitVector::with_capacity(100_000);
+        for &bit in &bits { wide.push_bit(bit); }
+        assert_eq!(expected, wide.checksum());
+        wide.shrink_to_fit();
+        assert_eq!(expected, wide.checksum());
+        assert_eq!(expected, BitVector::<u16>::from_bits(&bits).checksum());
+        assert_eq!(expected, BitVector::<u128>::from_bits(&bits).checksum());
+
+        // Junk past the end of the last block is ignored.
+        let mut grown = BitVector::<u32>::from_bits(&bits);
+        grown.resize(1020, true);
+        grown.truncate(1000);
+        assert_eq!(expected, grown.checksum());
+
+        for &position in &[0, 1, 500, 999] {
+            let mut flipped = BitVector::<u32>::from_bits(&bits);
+            flipped.set_bit(position, !bits[position as usize]);
+            assert_ne!(expected, flipped.checksum());
+        }
+
+        // Trailing 0s count, through the length.
+        let mut longer = bits.clone();
+        longer.push(false);
+        assert_ne!(expected, from_bools(&longer).checksum());
+        assert_ne!(BitVector::<u8>::new().checksum(),
+                   BitVector::<u8>::with_fill(1, false).checksum());
+    }
+
+    fn checksum_prop(bits: Vec<bool>) -> bool {
+        let expected = from_bools(&bits).checksum();
+        BitVector::<u32>::from_bits(&bits).checksum() == expected
+            && BitVector::<u64>::from_bits(&bits).checksum() == expected
+    }
+
+    #[test]
+    fn checksum_qc() {
+        quickcheck(checksum_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn count_ones_at_ends() {
+        let empty = BitVector::<u8>::new();
+        assert_eq!(0, empty.count_trailing_ones());
+        assert_eq!(0, empty.count_leading_ones());
+
+        for &len in &[1, 8, 21, 64] {
+            let ones = BitVector::<u8>::with_fill(len, true);
+            assert_eq!(len, ones.count_trailing_ones());
+            assert_eq!(len, ones.count_leading_ones());
+
+            let zeros = BitVector::<u8>::with_fill(len, false);
+            assert_eq!(0, zeros.count_trailing_ones());
+            assert_eq!(0, zeros.count_leading_ones());
+        }
+
+        // Runs of every length from the start, across the u8 block
+        // boundaries.
+        for run in 0 .. 21 {
+            let bv = BitVector::<u8>::from_fn(21, |i| i < run);
+            assert_eq!(run, bv.count_trailing_ones());
+            assert_eq!(21 - run, bv.count_leading_zeros());
+
+            let bv = BitVector::<u8>::from_fn(21, |i| i >= 21 - run);
+            assert_eq!(run, bv.count_leading_ones());
+            assert_eq!(21 - run, bv.count_trailing_zeros());
+        }
+
+        let vec = vec![ !0u32, 0b0111, 0xFFFF_0000 ];
+        assert_eq!(35, vec.count_trailing_ones());
+        assert_eq!(16, vec.count_leading_ones());
+    }
+
+    fn count_ones_prop(bits: Vec<bool>) -> bool {
+        let bv = from_bools(&bits);
+        let trailing = bits.iter().take_while(|&&bit| bit).count() as u64;
+        let leading = bits.iter().rev().take_while(|&&bit| bit).count() as u64;
+        bv.count_trailing_ones() == trailing && bv.count_leading_ones() == leading
+    }
+
+    #[test]
+    fn count_ones_at_ends_qc() {
+        quickcheck(count_ones_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn bits_eq() {
+        let bv = from_bools(&[ true, false, true, true ]);
+        assert!(bv.bits_eq(&bv));
+        assert!(bv.bits_eq(&Dirty(0xFD, 4)));
+        assert!(!bv.bits_eq(&Dirty(0b1001, 4)));
+        assert!(!bv.bits_eq(&Dirty(0b1101, 5)));
+        assert!(!bv.bits_eq(&[ 0b1101u8 ][..]));
+        assert!(from_bools(&[ true; 8 ]).bits_eq(&[ 0xFFu8 ][..]));
+    }
+
+    #[test]
+    fn popcount_windows() {
+        // Ones at multiples of 3, in 100 bits.
+        let bits: BitVector<u32> = (0 .. 100).map(|i| i % 3 == 0).collect();
+
+        let counts: Vec<u64> = bits.popcount_windows(10).collect();
+        assert_eq!(vec![4, 3, 3, 4, 3, 3, 4, 3, 3, 4], counts);
+
+        let counts: Vec<u64> = bits.popcount_windows(33).collect();
+        assert_eq!(vec![11, 11, 11, 1], counts);
+        assert_eq!(4, bits.popcount_windows(33).len());
+
+        assert_eq!(vec![34], bits.popcount_windows(100).collect::<Vec<_>>());
+        assert_eq!(vec![34], bits.popcount_windows(u64::MAX).collect::<Vec<_>>());
+        assert_eq!(100, bits.popcount_windows(1).len());
+        assert_eq!(0, BitVector::<u8>::new().popcount_windows(8).count());
+    }
+
+    #[test]
+    #[should_panic]
+    fn popcount_windows_zero() {
+        BitVector::<u8>::with_fill(8, true).popcount_windows(0);
+    }
+
+    fn popcount_windows_prop(bits: Vec<bool>, window: u8) -> bool {
+        let window = window as u64 % 70 + 1;
+        let vec = from_bools(&bits);
+        let counts: Vec<u64> = vec.popcount_windows(window).collect();
+        let expected: Vec<u64> = bits.chunks(window as usize)
+            .map(|chunk| chunk.iter().filter(|&&bit| bit).count() as u64)
+            .collect();
+
+        counts == expected
+            && counts.iter().sum::<u64>() == bits.iter().filter(|&&bit| bit).count() as u64
+            && vec.popcount_windows(window).len() == counts.len()
+    }
+
+    #[test]
+    fn popcount_windows_qc() {
+        quickcheck(popcount_windows_prop as fn(Vec<bool>, u8) -> bool);
+    }
+
+    #[test]
+    fn runs_long() {
+        let mut bv = BitVector::<u64>::with_fill(1000, false);
+        bv.resize(1500, true);
+        bv.push_bit(false);
+
+        assert_eq!(vec![ (false, 1000), (true, 500), (false, 1) ],
+                   bv.runs().collect::<Vec<_>>());
+        assert_eq!(bv.bit_len(), bv.runs().map(|(_, length)| length).sum::<u64>());
+
+        assert_eq!(0, BitVector::<u64>::new().runs().count());
+        assert_eq!(vec![ (true, 64) ], vec![ !0u64 ].runs().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn runs_ignore_padding() {
+        assert_eq!(vec![ (true, 5) ], Dirty(0xFF, 5).runs().collect::<Vec<_>>());
+        assert_eq!(vec![ (false, 3), (true, 2) ],
+                   Dirty(0b11111000, 5).runs().collect::<Vec<_>>());
+    }
+
+    fn runs_prop(bits: Vec<bool>) -> bool {
+        let bv = from_bools(&bits);
+        let mut expected: Vec<(bool, u64)> = Vec::new();
+        for &bit in &bits {
+            match expected.last_mut() {
+                Some(&mut (value, ref mut length)) if value == bit => *length += 1,
+                _ => expected.push((bit, 1)),
+            }
+        }
+        bv.runs().collect::<Vec<_>>() == expected
+    }
+
+    #[test]
+    fn runs_qc() {
+        quickcheck(runs_prop as fn(Vec<bool>) -> bool);
+    }
+}
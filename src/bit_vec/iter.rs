@@ -0,0 +1,323 @@
+use num_traits::{One, PrimInt, ToPrimitive, Zero};
+
+use bit_vec::traits::*;
+use bit_vec::bit_slice::BitSlice;
+use bit_vec::not_bits::NotBits;
+use storage::{Address, BlockType};
+
+/// Iterator over the bits of a bit vector.
+///
+/// Construct with [`BitVecExt::bits`](trait.BitVecExt.html#method.bits).
+#[derive(Clone, Debug)]
+pub struct BitIter<'a, Bits: 'a + BitVec + ?Sized> {
+    data: &'a Bits,
+    start: u64,
+    limit: u64,
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> BitIter<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        BitIter {
+            data,
+            start: 0,
+            limit: data.bit_len(),
+        }
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for BitIter<'a, Bits> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.start < self.limit {
+            let result = self.data.get_bit(self.start);
+            self.start += 1;
+            Some(result)
+        } else { None }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if let Some(len) = (self.limit - self.start).to_usize() {
+            (len, Some(len))
+        } else {
+            (!0, None)
+        }
+    }
+
+    fn nth(&mut self, n: usize) -> Option<bool> {
+        self.start = self.start.saturating_add(n as u64).min(self.limit);
+        self.next()
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl<'a, Bits: 'a + BitVec + ?Sized> ExactSizeIterator for BitIter<'a, Bits> {
+    fn len(&self) -> usize {
+        (self.limit - self.start) as usize
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> DoubleEndedIterator for BitIter<'a, Bits> {
+    fn next_back(&mut self) -> Option<bool> {
+        if self.start < self.limit {
+            self.limit -= 1;
+            Some(self.data.get_bit(self.limit))
+        } else { None }
+    }
+}
+
+/// Iterator over the blocks of a bit vector.
+///
+/// The final block has the bits past `bit_len` cleared, whatever the
+/// underlying `get_block` returns there.
+///
+/// Construct with [`BitVecExt::blocks`](trait.BitVecExt.html#method.blocks).
+#[derive(Clone, Debug)]
+pub struct BlockIter<'a, Bits: 'a + BitVec + ?Sized> {
+    data: &'a Bits,
+    start: usize,
+    limit: usize,
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> BlockIter<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        BlockIter {
+            data,
+            start: 0,
+            limit: data.block_len(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Bits::Block {
+        self.data.get_block_masked(index)
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for BlockIter<'a, Bits> {
+    type Item = Bits::Block;
+
+    fn next(&mut self) -> Option<Bits::Block> {
+        if self.start < self.limit {
+            let result = self.get(self.start);
+            self.start += 1;
+            Some(result)
+        } else { None }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.limit - self.start;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Bits::Block> {
+        self.start = self.start.saturating_add(n).min(self.limit);
+        self.next()
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> ExactSizeIterator for BlockIter<'a, Bits> {}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> DoubleEndedIterator for BlockIter<'a, Bits> {
+    fn next_back(&mut self) -> Option<Bits::Block> {
+        if self.start < self.limit {
+            self.limit -= 1;
+            Some(self.get(self.limit))
+        } else { None }
+    }
+}
+
+// Walks the positions of the 1 bits of `data`, or of the 0 bits if
+// `invert` is set, one block at a time.
+#[derive(Clone, Debug)]
+struct Positions<'a, Bits: 'a + BitVec + ?Sized> {
+    data: &'a Bits,
+    invert: bool,
+    // The index of the next block to load.
+    block_index: usize,
+    // The bits of block `block_index - 1` that we haven’t visited yet.
+    current: Bits::Block,
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Positions<'a, Bits> {
+    fn new(data: &'a Bits, invert: bool) -> Self {
+        Positions {
+            data,
+            invert,
+            block_index: 0,
+            current: Bits::Block::zero(),
+        }
+    }
+
+    // Loads block `index`, inverted if need be, with the bits past the
+    // end of the vector cleared.
+    fn load(&self, index: usize) -> Bits::Block {
+        if self.invert {
+            NotBits::new(self.data).get_block(index)
+        } else {
+            self.data.get_block_masked(index)
+        }
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for Positions<'a, Bits> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if self.current != Bits::Block::zero() {
+                let offset = self.current.trailing_zeros() as u64;
+                self.current = self.current & (self.current - Bits::Block::one());
+                return Some(Bits::Block::mul_nbits(self.block_index - 1) + offset);
+            }
+
+            if self.block_index >= self.data.block_len() {
+                return None;
+            }
+
+            self.current = self.load(self.block_index);
+            self.block_index += 1;
+        }
+    }
+}
+
+/// Iterator over the positions of the 1 bits of a bit vector.
+///
+/// Construct with [`BitVecExt::ones`](trait.BitVecExt.html#method.ones).
+#[derive(Clone, Debug)]
+pub struct Ones<'a, Bits: 'a + BitVec + ?Sized>(Positions<'a, Bits>);
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Ones<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        Ones(Positions::new(data, false))
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for Ones<'a, Bits> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.0.next()
+    }
+}
+
+/// Iterator over the positions of the 0 bits of a bit vector.
+///
+/// Construct with [`BitVecExt::zeros`](trait.BitVecExt.html#method.zeros).
+#[derive(Clone, Debug)]
+pub struct Zeros<'a, Bits: 'a + BitVec + ?Sized>(Positions<'a, Bits>);
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Zeros<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        Zeros(Positions::new(data, true))
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for Zeros<'a, Bits> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.0.next()
+    }
+}
+
+/// Iterator over the maximal runs of equal bits of a bit vector, as
+/// `(bit, length)` pairs.
+///
+/// Construct with [`BitVecExt::runs`](trait.BitVecExt.html#method.runs).
+#[derive(Clone, Debug)]
+pub struct Runs<'a, Bits: 'a + BitVec + ?Sized> {
+    data: &'a Bits,
+    start: u64,
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Runs<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits) -> Self {
+        Runs {
+            data,
+            start: 0,
+        }
+    }
+
+    // The first position at or after `start` whose bit isn’t `value`,
+    // or `bit_len` if there is none.
+    fn run_end(&self, start: u64, value: bool) -> u64 {
+        let limit = self.data.bit_len();
+        let address = Address::new::<Bits::Block>(start);
+        let mut block_index = address.block_index;
+        let mut skip = Bits::Block::low_mask(address.bit_offset);
+
+        while block_index < self.data.block_len() {
+            let block = self.data.get_block(block_index);
+            let block = if value { !block } else { block } & !skip;
+
+            if block != Bits::Block::zero() {
+                let end = Bits::Block::mul_nbits(block_index)
+                        + block.trailing_zeros() as u64;
+                return end.min(limit);
+            }
+
+            block_index += 1;
+            skip = Bits::Block::zero();
+        }
+
+        limit
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for Runs<'a, Bits> {
+    type Item = (bool, u64);
+
+    fn next(&mut self) -> Option<(bool, u64)> {
+        if self.start >= self.data.bit_len() { return None; }
+
+        let value = self.data.get_bit(self.start);
+        let end = self.run_end(self.start, value);
+        let length = end - self.start;
+        self.start = end;
+        Some((value, length))
+    }
+}
+
+/// Iterator over the number of 1s in each consecutive window of a bit
+/// vector.
+///
+/// Construct with
+/// [`BitVecExt::popcount_windows`](trait.BitVecExt.html#method.popcount_windows).
+#[derive(Clone, Debug)]
+pub struct PopcountWindows<'a, Bits: 'a + BitVec + ?Sized> {
+    data: &'a Bits,
+    window: u64,
+    start: u64,
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> PopcountWindows<'a, Bits> {
+    pub(crate) fn new(data: &'a Bits, window: u64) -> Self {
+        assert!(window > 0, "BitVecExt::popcount_windows: zero window");
+        PopcountWindows {
+            data,
+            window,
+            start: 0,
+        }
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> Iterator for PopcountWindows<'a, Bits> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let len = self.data.bit_len();
+        if self.start >= len { return None; }
+
+        let end = len.min(self.start.saturating_add(self.window));
+        let count = BitSlice::new(self.data, self.start .. end).count_ones();
+        self.start = end;
+        Some(count)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.data.bit_len() - self.start).div_ceil(self.window);
+        (remaining as usize, Some(remaining as usize))
+    }
+}
+
+impl<'a, Bits: 'a + BitVec + ?Sized> ExactSizeIterator for PopcountWindows<'a, Bits> {}
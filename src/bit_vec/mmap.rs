@@ -0,0 +1,218 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+// The length header written by `BitVector::write_to`.
+const HEADER_BYTES: usize = 8;
+
+/// A read-only bit vector memory-mapped from a file.
+///
+/// The file must be in the format written by `BitVector::write_to`: the
+/// bit length as a little-endian `u64`, followed by the bits packed into
+/// bytes. Pages are read on demand, so rank and select structures can be
+/// built over vectors larger than memory. Each byte of the file is one
+/// `u8` block.
+///
+/// Available with the `memmap` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use succinct::{BitRankSupport, JacobsonRank};
+/// use succinct::bit_vec::MmapBitVector;
+///
+/// let bits = unsafe { MmapBitVector::open("bits.bin") }.unwrap();
+/// let rank = JacobsonRank::new(bits);
+/// println!("{} ones", rank.total_ones());
+/// ```
+#[derive(Debug)]
+pub struct MmapBitVector {
+    map: Mmap,
+    len: u64,
+}
+
+impl MmapBitVector {
+    /// Maps the file at `path`.
+    ///
+    /// Fails with `ErrorKind::InvalidData` if the file’s length doesn’t
+    /// match the bit length in its header, or if the final byte has bits
+    /// set past the end of the vector.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// Maps an open file, validating it as `open` does.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    pub unsafe fn from_file(file: &File) -> io::Result<Self> {
+        let file_len = file.metadata()?.len();
+        if file_len < HEADER_BYTES as u64 {
+            return Err(invalid("MmapBitVector: missing header"));
+        }
+
+        let map = Mmap::map(file)?;
+        let mut header = [0; HEADER_BYTES];
+        header.copy_from_slice(&map[.. HEADER_BYTES]);
+        let len = u64::from_le_bytes(header);
+
+        if file_len - HEADER_BYTES as u64 != len.ceil_div(8) {
+            return Err(invalid("MmapBitVector: file length doesn’t match header"));
+        }
+
+        let result = MmapBitVector { map, len };
+        if let Some(&last) = result.bytes().last() {
+            let mask = u8::last_block_mask(len);
+            if last & !mask != 0 {
+                return Err(invalid("MmapBitVector: bits past end"));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The mapped bits, packed little-endian.
+    pub fn bytes(&self) -> &[u8] {
+        &self.map[HEADER_BYTES ..]
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl BitVec for MmapBitVector {
+    type Block = u8;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    fn block_len(&self) -> usize {
+        self.map.len() - HEADER_BYTES
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> u8 {
+        self.bytes()[position]
+    }
+}
+
+impl SpaceUsage for MmapBitVector {
+    fn is_stack_only() -> bool { true }
+    fn heap_bytes(&self) -> usize { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{ErrorKind, Write};
+    use std::path::PathBuf;
+    use std::process;
+
+    use bit_vec::*;
+    use rank::{BitRankSupport, JacobsonRank};
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("succinct-mmap-{}-{}", process::id(), name))
+    }
+
+    fn write_file(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = temp_path(name);
+        File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn matches_in_memory() {
+        let bits: BitVector<u64> = (0 .. 10_000u64)
+            .map(|i| i.wrapping_mul(2654435761) % 7 < 3)
+            .collect();
+        let mut bytes = Vec::new();
+        bits.write_to(&mut bytes).unwrap();
+        let path = write_file("matches", &bytes);
+
+        let mapped = unsafe { MmapBitVector::open(&path) }.unwrap();
+        assert_eq!(bits.bit_len(), mapped.bit_len());
+        for i in 0 .. bits.bit_len() {
+            assert_eq!(bits.get_bit(i), mapped.get_bit(i));
+        }
+
+        let expected = JacobsonRank::new(bits);
+        let actual = JacobsonRank::new(mapped);
+        for i in 0 .. expected.bit_len() {
+            assert_eq!(expected.rank1(i), actual.rank1(i));
+        }
+
+        drop(actual);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn partial_last_byte() {
+        let bits: BitVector<u8> = (0 .. 13).map(|i| i % 2 == 0).collect();
+        let mut bytes = Vec::new();
+        bits.write_to(&mut bytes).unwrap();
+        let path = write_file("partial", &bytes);
+
+        let mapped = unsafe { MmapBitVector::open(&path) }.unwrap();
+        assert_eq!(13, mapped.bit_len());
+        assert_eq!(2, mapped.block_len());
+        assert!(mapped.get_bit(12));
+        assert!(!mapped.get_bit(11));
+
+        drop(mapped);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        let mut bytes = Vec::new();
+        BitVector::<u8>::new().write_to(&mut bytes).unwrap();
+        let path = write_file("empty", &bytes);
+
+        let mapped = unsafe { MmapBitVector::open(&path) }.unwrap();
+        assert_eq!(0, mapped.bit_len());
+        assert_eq!(0, mapped.block_len());
+
+        drop(mapped);
+        fs::remove_file(path).unwrap();
+    }
+
+    fn open_error(name: &str, bytes: &[u8]) -> ErrorKind {
+        let path = write_file(name, bytes);
+        let result = unsafe { MmapBitVector::open(&path) };
+        fs::remove_file(path).unwrap();
+        result.unwrap_err().kind()
+    }
+
+    #[test]
+    fn bad_length() {
+        // Header claims 20 bits, which need 3 bytes.
+        assert_eq!(ErrorKind::InvalidData,
+                   open_error("short", &[20, 0, 0, 0, 0, 0, 0, 0, 1, 2]));
+        assert_eq!(ErrorKind::InvalidData,
+                   open_error("long", &[20, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 4]));
+        assert_eq!(ErrorKind::InvalidData, open_error("headless", &[1, 0]));
+    }
+
+    #[test]
+    fn bits_past_end() {
+        assert_eq!(ErrorKind::InvalidData,
+                   open_error("past_end", &[4, 0, 0, 0, 0, 0, 0, 0, 0x10]));
+    }
+}
@@ -3,11 +3,51 @@
 mod traits;
 pub use self::traits::*;
 
+mod ext;
+pub use self::ext::*;
+
+mod ops;
+pub use self::ops::*;
+
+mod display;
+pub use self::display::*;
+
+mod iter;
+pub use self::iter::*;
+
 mod bit_vector;
 pub use self::bit_vector::*;
 
+mod bit_array;
+pub use self::bit_array::*;
+
+mod builder;
+pub use self::builder::*;
+
 mod bit_slice;
 pub use self::bit_slice::*;
 
+mod not_bits;
+pub use self::not_bits::*;
+
+mod byte_bits;
+pub use self::byte_bits::*;
+
+mod ordered_bits;
+pub use self::ordered_bits::*;
+
+mod concat;
+pub use self::concat::*;
+
+#[cfg(feature = "memmap")]
+mod mmap;
+#[cfg(feature = "memmap")]
+pub use self::mmap::*;
+
+#[cfg(feature = "bitvec-compat")]
+mod bitvec_compat;
+#[cfg(feature = "bitvec-compat")]
+pub use self::bitvec_compat::*;
+
 mod prim;
 pub use self::prim::*;
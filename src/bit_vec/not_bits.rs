@@ -0,0 +1,133 @@
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A borrowed view of the complement of a bit vector.
+///
+/// Each bit and block is negated as it is read, and the bits past the end
+/// of the last block are zero, so rank and select structures built over
+/// the view count the 0s of the base without copying it.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitRankSupport, BitVec, BitVector, JacobsonRank};
+/// use succinct::bit_vec::NotBits;
+///
+/// let bits: BitVector<u8> = (0 .. 10).map(|i| i < 3).collect();
+/// let zeros = NotBits::new(&bits);
+/// assert!(!zeros.get_bit(0));
+/// assert!(zeros.get_bit(3));
+/// assert_eq!(7, JacobsonRank::new(zeros).total_ones());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct NotBits<'a, Base: 'a + BitVec + ?Sized> {
+    data: &'a Base,
+}
+
+impl<'a, Base: 'a + BitVec + ?Sized> NotBits<'a, Base> {
+    /// Views the complement of `base`.
+    pub fn new(base: &'a Base) -> Self {
+        NotBits { data: base }
+    }
+
+    /// The underlying bit vector.
+    pub fn inner(&self) -> &'a Base {
+        self.data
+    }
+}
+
+impl<'a, Base: 'a + BitVec + ?Sized> BitVec for NotBits<'a, Base> {
+    type Block = Base::Block;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.data.bit_len()
+    }
+
+    #[inline]
+    fn block_len(&self) -> usize {
+        self.data.block_len()
+    }
+
+    #[inline]
+    fn get_bit(&self, position: u64) -> bool {
+        !self.data.get_bit(position)
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> Self::Block {
+        let block = !self.data.get_block(position);
+        if position + 1 == self.block_len() {
+            block & Self::Block::last_block_mask(self.bit_len())
+        } else {
+            block
+        }
+    }
+}
+
+impl<'a, Base: 'a + BitVec + ?Sized> SpaceUsage for NotBits<'a, Base> {
+    fn is_stack_only() -> bool { true }
+    fn heap_bytes(&self) -> usize { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+    use rank::{BitRankSupport, JacobsonRank};
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn complement() {
+        let bits: BitVector<u8> = (0 .. 21).map(|i| i % 3 == 0).collect();
+        let not = NotBits::new(&bits);
+        assert_eq!(21, not.bit_len());
+        assert_eq!(3, not.block_len());
+
+        for i in 0 .. bits.bit_len() {
+            assert_eq!(!bits.get_bit(i), not.get_bit(i));
+        }
+        assert_eq!(!bits.get_block(0), not.get_block(0));
+    }
+
+    #[test]
+    fn last_block_masked() {
+        let bits = BitVector::<u32>::with_fill(40, false);
+        let not = NotBits::new(&bits);
+        assert_eq!(!0, not.get_block(0));
+        assert_eq!(0xFF, not.get_block(1));
+
+        let full = BitVector::<u32>::with_fill(64, false);
+        assert_eq!(!0, NotBits::new(&full).get_block(1));
+    }
+
+    #[test]
+    fn rank_over_complement() {
+        let bits: BitVector<u64> = (0 .. 1000).map(|i| i % 7 < 2).collect();
+        let ones = JacobsonRank::new(&bits);
+        let zeros = JacobsonRank::new(NotBits::new(&bits));
+
+        for i in 0 .. bits.bit_len() {
+            assert_eq!(ones.rank0(i), zeros.rank1(i));
+        }
+        assert_eq!(ones.total_zeros(), zeros.total_ones());
+    }
+
+    fn get_bit_prop(bits: Vec<bool>) -> bool {
+        let vec: BitVector<u8> = bits.iter().cloned().collect();
+        let not = NotBits::new(&vec);
+        let masked = (0 .. not.block_len()).all(|i| {
+            let start = 8 * i as u64;
+            (0 .. 8).all(|j| {
+                not.get_block(i).get_bit(j)
+                    == (start + j < vec.bit_len() && !vec.get_bit(start + j))
+            })
+        });
+        masked && bits.iter().enumerate().all(|(i, &bit)| not.get_bit(i as u64) != bit)
+    }
+
+    #[test]
+    fn get_bit_qc() {
+        quickcheck(get_bit_prop as fn(Vec<bool>) -> bool);
+    }
+}
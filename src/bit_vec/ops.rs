@@ -0,0 +1,438 @@
+use num_traits::Zero;
+
+use bit_vec::traits::*;
+use storage::BlockType;
+
+/// In-place bitwise operations, implemented for every `BitVecMut`.
+///
+/// The binary operations work a block at a time, so both operands must
+/// have the same block type and the same `bit_len`. Bits past the end of
+/// the receiver are left unchanged, as by `set_block_masked`: they stay
+/// zero in a `BitVector`, but are neither cleared nor modified in a
+/// store whose padding is already set, such as the bits past the end of
+/// a `BitSliceMut`.
+pub trait BitVecOps: BitVecMut {
+    /// Sets `self` to the bitwise AND of `self` and `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn and_assign<Other>(&mut self, other: &Other)
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        zip_blocks(self, other, "BitVecOps::and_assign", |a, b| a & b);
+    }
+
+    /// Sets `self` to the bitwise OR of `self` and `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn or_assign<Other>(&mut self, other: &Other)
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        zip_blocks(self, other, "BitVecOps::or_assign", |a, b| a | b);
+    }
+
+    /// Sets `self` to the bitwise XOR of `self` and `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn xor_assign<Other>(&mut self, other: &Other)
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        zip_blocks(self, other, "BitVecOps::xor_assign", |a, b| a ^ b);
+    }
+
+    /// Flips the bits of `self` wherever `patch` has a 1, undoing or
+    /// redoing a [`BitVecExt::diff`](trait.BitVecExt.html#method.diff).
+    ///
+    /// This is `xor_assign`; applying the same patch twice restores
+    /// `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit lengths differ.
+    fn apply_patch<Other>(&mut self, patch: &Other)
+        where Other: BitVec<Block = Self::Block> + ?Sized {
+        zip_blocks(self, patch, "BitVecOps::apply_patch", |a, b| a ^ b);
+    }
+
+    /// Flips every bit of `self`.
+    fn not_assign(&mut self) {
+        let block_len = self.block_len();
+        for i in 0 .. block_len {
+            let block = !self.get_block(i);
+            self.set_block_masked(i, block);
+        }
+    }
+
+    /// Moves every bit `amount` positions up, as `<<` does within a
+    /// block: bit `i` moves to position `i + amount`.
+    ///
+    /// The lowest `amount` positions become 0, and bits shifted past the
+    /// end are lost.
+    fn shift_left(&mut self, amount: u64) {
+        let (block_shift, bit_shift) = match split_shift(self, amount) {
+            Some(shifts) => shifts,
+            None         => return clear(self),
+        };
+
+        for i in (0 .. self.block_len()).rev() {
+            let high = source_block(self, i.checked_sub(block_shift));
+            let block = if bit_shift == 0 {
+                high
+            } else {
+                let low = source_block(self, i.checked_sub(block_shift + 1));
+                high << bit_shift | low >> (Self::Block::nbits() - bit_shift)
+            };
+            self.set_block_masked(i, block);
+        }
+    }
+
+    /// Moves every bit `amount` positions down, as `>>` does within a
+    /// block: bit `i` moves to position `i - amount`.
+    ///
+    /// The highest `amount` positions become 0, and bits shifted below
+    /// position 0 are lost.
+    fn shift_right(&mut self, amount: u64) {
+        let (block_shift, bit_shift) = match split_shift(self, amount) {
+            Some(shifts) => shifts,
+            None         => return clear(self),
+        };
+
+        for i in 0 .. self.block_len() {
+            let low = source_block(self, Some(i + block_shift));
+            let block = if bit_shift == 0 {
+                low
+            } else {
+                let high = source_block(self, Some(i + block_shift + 1));
+                low >> bit_shift | high << (Self::Block::nbits() - bit_shift)
+            };
+            self.set_block_masked(i, block);
+        }
+    }
+}
+
+impl<T: BitVecMut + ?Sized> BitVecOps for T {}
+
+fn clear<Bits: BitVecMut + ?Sized>(bits: &mut Bits) {
+    for i in 0 .. bits.block_len() {
+        bits.set_block_masked(i, Bits::Block::zero());
+    }
+}
+
+// Splits a shift into whole blocks and remaining bits, or returns `None`
+// if it clears every bit.
+fn split_shift<Bits: BitVec + ?Sized>(bits: &Bits, amount: u64)
+                                      -> Option<(usize, usize)> {
+    if amount >= bits.bit_len() { return None; }
+    Some((Bits::Block::div_nbits(amount), Bits::Block::mod_nbits(amount)))
+}
+
+// Block `index` with its padding cleared, or zero if `index` is out of
+// range.
+fn source_block<Bits: BitVec + ?Sized>(bits: &Bits, index: Option<usize>)
+                                       -> Bits::Block {
+    match index {
+        Some(index) if index < bits.block_len() => bits.get_block_masked(index),
+        _ => Bits::Block::zero(),
+    }
+}
+
+fn zip_blocks<Bits, Other, F>(bits: &mut Bits, other: &Other,
+                              who: &str, op: F)
+    where Bits: BitVecMut + ?Sized,
+          Other: BitVec<Block = Bits::Block> + ?Sized,
+          F: Fn(Bits::Block, Bits::Block) -> Bits::Block
+{
+    assert!(bits.bit_len() == other.bit_len(), "{}: lengths differ", who);
+
+    let block_len = bits.block_len();
+    for i in 0 .. block_len {
+        let block = op(bits.get_block(i), other.get_block(i));
+        bits.set_block_masked(i, block);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+    use storage::BlockType;
+    use quickcheck::quickcheck;
+
+    fn pattern(len: u64, f: fn(u64) -> bool) -> BitVector<u8> {
+        let mut result = BitVector::new();
+        for i in 0 .. len {
+            result.push_bit(f(i));
+        }
+        result
+    }
+
+    fn a(i: u64) -> bool { i % 3 == 1 }
+    fn b(i: u64) -> bool { i % 5 < 2 }
+
+    #[test]
+    fn diff_patch() {
+        for &len in &[0, 1, 8, 13, 64, 70] {
+            let x = pattern(len, a);
+            let y = pattern(len, b);
+
+            let patch = x.diff(&y);
+            assert_eq!(len, patch.bit_len());
+            assert_eq!(x.hamming_distance(&y), patch.ones().count() as u64);
+            assert_eq!(patch, y.diff(&x));
+
+            let mut z = x.clone();
+            z.apply_patch(&patch);
+            assert_eq!(y, z);
+            z.apply_patch(&patch);
+            assert_eq!(x, z);
+
+            assert_eq!(x.diff(&x), BitVector::with_fill(len, false));
+        }
+    }
+
+    #[test]
+    fn diff_across_types() {
+        let x = pattern(20, a);
+        let words: &[u8] = &[0xFF, 0x00, 0x0F];
+        let slice = BitSlice::new(words, 0 .. 20);
+        let patch = x.diff(&slice);
+        assert_eq!(0, patch.get_block(2) & !0x0F);
+
+        let mut z = x.clone();
+        z.apply_patch(&patch);
+        assert!(z.bits_eq(&slice));
+    }
+
+    #[test]
+    #[should_panic]
+    fn diff_mismatch() {
+        pattern(10, a).diff(&pattern(11, a));
+    }
+
+    #[test]
+    #[should_panic]
+    fn apply_patch_mismatch() {
+        pattern(10, a).apply_patch(&pattern(9, a));
+    }
+
+    fn diff_patch_prop(pairs: Vec<(bool, bool)>) -> bool {
+        let x: BitVector<u32> = pairs.iter().map(|&(bit, _)| bit).collect();
+        let y: BitVector<u32> = pairs.iter().map(|&(_, bit)| bit).collect();
+        let patch = x.diff(&y);
+
+        let mut z = x.clone();
+        z.apply_patch(&patch);
+        let forward = z == y;
+        z.apply_patch(&patch);
+        forward && z == x
+            && pairs.iter().enumerate()
+                    .all(|(i, &(p, q))| patch.get_bit(i as u64) == (p != q))
+    }
+
+    #[test]
+    fn diff_patch_qc() {
+        quickcheck(diff_patch_prop as fn(Vec<(bool, bool)>) -> bool);
+    }
+
+    #[test]
+    fn and_or_xor() {
+        for &len in &[0, 1, 8, 13, 64, 70] {
+            let x = pattern(len, a);
+            let y = pattern(len, b);
+
+            let mut and = x.clone();
+            and.and_assign(&y);
+            let mut or = x.clone();
+            or.or_assign(&y);
+            let mut xor = x.clone();
+            xor.xor_assign(&y);
+
+            for i in 0 .. len {
+                assert_eq!(a(i) && b(i), and.get_bit(i));
+                assert_eq!(a(i) || b(i), or.get_bit(i));
+                assert_eq!(a(i) != b(i), xor.get_bit(i));
+            }
+        }
+    }
+
+    #[test]
+    fn slice_operand() {
+        let mut x = pattern(16, a);
+        let y: &[u8] = &[ 0b11110000, 0b00001111 ];
+        x.and_assign(y);
+        for i in 0 .. 16 {
+            assert_eq!(a(i) && (4 .. 12).contains(&i), x.get_bit(i));
+        }
+    }
+
+    #[test]
+    fn not_assign() {
+        let mut x = pattern(13, a);
+        x.not_assign();
+        for i in 0 .. 13 {
+            assert_eq!(!a(i), x.get_bit(i));
+        }
+        // Padding past bit 13 stays clear.
+        assert_eq!(0b00011011, x.get_block(1));
+
+        x.not_assign();
+        assert_eq!(pattern(13, a), x);
+    }
+
+    #[test]
+    fn not_assign_slice() {
+        let mut v = vec![ 0b10101010u8, 0b00000000 ];
+        {
+            let mut slice = BitSliceMut::new(&mut v[..], 4 .. 12);
+            slice.not_assign();
+        }
+        assert_eq!(vec![ 0b01011010u8, 0b00001111 ], v);
+    }
+
+    #[test]
+    #[should_panic]
+    fn length_mismatch() {
+        let mut x = pattern(10, a);
+        let y = pattern(11, b);
+        x.or_assign(&y);
+    }
+
+    // Shifts by moving one bit at a time.
+    fn naive_shift(bits: &BitVector<u8>, amount: u64, left: bool) -> BitVector<u8> {
+        let len = bits.bit_len();
+        let mut result = BitVector::with_fill(len, false);
+        for i in 0 .. len {
+            let target = if left { i.checked_add(amount) } else { i.checked_sub(amount) };
+            if let Some(target) = target.filter(|&target| target < len) {
+                result.set_bit(target, bits.get_bit(i));
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn shifts() {
+        for &len in &[0, 1, 7, 8, 13, 64, 100] {
+            let original = pattern(len, a);
+            for amount in (0 .. 30).chain(vec![63, 64, 65, 99, 100, 1000]) {
+                let mut x = original.clone();
+                x.shift_left(amount);
+                assert_eq!(naive_shift(&original, amount, true), x);
+
+                let mut x = original.clone();
+                x.shift_right(amount);
+                assert_eq!(naive_shift(&original, amount, false), x);
+            }
+        }
+    }
+
+    #[test]
+    fn shift_wide_blocks() {
+        let mut x = BitVector::<u64>::with_fill(200, true);
+        x.shift_left(70);
+        assert_eq!(0, x.get_block(0));
+        assert_eq!(!0 << 6, x.get_block(1));
+        assert_eq!(130, x.ones().count());
+
+        x.shift_right(140);
+        assert_eq!(60, x.ones().count());
+        assert!(x.get_bit(59));
+        assert!(!x.get_bit(60));
+    }
+
+    #[test]
+    fn shift_ignores_dirty_padding() {
+        let mut v = Verbatim(vec![ 0b00000001, 0b11100001 ], 13);
+        v.shift_right(4);
+        assert_eq!(vec![ 0b00010000, 0b11100000 ], v.0);
+
+        let mut v = Verbatim(vec![ 0b10000000, 0b11100001 ], 13);
+        v.shift_left(8);
+        assert_eq!(vec![ 0, 0b11100000 ], v.0);
+    }
+
+    fn shift_prop(bits: Vec<bool>, amount: u8, left: bool) -> bool {
+        let original: BitVector<u8> = bits.into_iter().collect();
+        let mut x = original.clone();
+        if left { x.shift_left(amount as u64) } else { x.shift_right(amount as u64) }
+        x == naive_shift(&original, amount as u64, left)
+    }
+
+    #[test]
+    fn shift_qc() {
+        quickcheck(shift_prop as fn(Vec<bool>, u8, bool) -> bool);
+    }
+
+    // Stores `set_block`’s argument as is, padding and all.
+    struct Verbatim(Vec<u8>, u64);
+
+    impl BitVec for Verbatim {
+        type Block = u8;
+        fn bit_len(&self) -> u64 { self.1 }
+        fn get_block(&self, position: usize) -> u8 { self.0[position] }
+    }
+
+    impl BitVecMut for Verbatim {
+        fn set_block(&mut self, position: usize, value: u8) {
+            self.0[position] = value;
+        }
+    }
+
+    #[test]
+    fn set_block_masked_keeps_padding() {
+        let mut v = Verbatim(vec![ 0, 0 ], 13);
+        v.set_block_masked(1, 0xFF);
+        assert_eq!(0b00011111, v.0[1]);
+        v.set_block_masked(0, 0xFF);
+        assert_eq!(0xFF, v.0[0]);
+
+        let mut dirty = Verbatim(vec![ 0, 0b11100000 ], 13);
+        dirty.set_block_masked(1, 0b00000101);
+        assert_eq!(0b11100101, dirty.0[1]);
+
+        let mut x = Verbatim(vec![ 0, 0 ], 13);
+        x.not_assign();
+        assert_eq!(vec![ 0xFF, 0b00011111 ], x.0);
+    }
+
+    #[test]
+    fn ops_keep_padding() {
+        // Set padding is neither cleared nor combined.
+        let mut dirty = Verbatim(vec![ 0b00001111, 0b10100011 ], 13);
+        dirty.not_assign();
+        assert_eq!(vec![ 0b11110000, 0b10111100 ], dirty.0);
+        dirty.and_assign(&Verbatim(vec![ 0xFF, 0 ], 13));
+        assert_eq!(vec![ 0b11110000, 0b10100000 ], dirty.0);
+        dirty.or_assign(&Verbatim(vec![ 0, 0xFF ], 13));
+        assert_eq!(vec![ 0b11110000, 0b10111111 ], dirty.0);
+
+        // Clear padding stays clear.
+        let mut x = pattern(13, a);
+        x.or_assign(&BitVector::with_fill(13, true));
+        x.xor_assign(&pattern(13, b));
+        assert_eq!(0b00011111 & !pattern(13, b).get_block(1), x.get_block(1));
+
+        // The bits past the end of a slice belong to the vector beneath it.
+        let mut base = BitVector::<u8>::with_fill(16, true);
+        BitSliceMut::new(&mut base, 0 .. 13).not_assign();
+        assert_eq!(0, base.get_block(0));
+        assert_eq!(0b11100000, base.get_block(1));
+    }
+
+    #[test]
+    fn set_block_masked_count_ones() {
+        let mut bv = BitVector::<u32>::with_fill(45, false);
+        bv.set_block_masked(1, !0);
+        bv.set_block_masked(0, !0);
+        assert_eq!(45, u32::count_ones_slice(bv.as_blocks()));
+
+        // `BitVector`’s own `set_block` masks too.
+        bv.set_block(1, !0);
+        assert_eq!(45, u32::count_ones_slice(bv.as_blocks()));
+
+        let mut raw = vec![ 0u32; 2 ];
+        raw.set_block_masked(1, !0);
+        assert_eq!(32, u32::count_ones_slice(&raw));
+    }
+}
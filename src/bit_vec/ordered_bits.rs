@@ -0,0 +1,226 @@
+use bit_vec::traits::*;
+use space_usage::SpaceUsage;
+use storage::{Address, BlockType};
+use super::bit_vector::BitOrder;
+
+/// Blocks viewed with a chosen order of bits within each block.
+///
+/// With `BitOrder::LsbFirst` this is the store itself: bit `i` of a block
+/// is its `i`th least significant bit. With `BitOrder::MsbFirst` bit `0`
+/// of each block is its most significant bit instead, as in formats that
+/// pack bits into big-endian words. `get_block` and `set_block` reverse
+/// the bits of each block to match, so rank and select structures built
+/// over the view see the bits in order.
+///
+/// For `MsbFirst` the store must hold a whole number of blocks; view a
+/// shorter length with a `BitSlice` over the result.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitOrder, BitVec, BitVecMut};
+/// use succinct::bit_vec::OrderedBits;
+///
+/// let mut bits = OrderedBits::new(vec![0u16; 2], BitOrder::MsbFirst);
+/// bits.set_bit(0, true);
+/// bits.set_bit(17, true);
+/// assert_eq!(&[0x8000, 0x4000], &*bits.into_inner());
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrderedBits<Store> {
+    store: Store,
+    order: BitOrder,
+}
+
+impl<Store: BitVec> OrderedBits<Store> {
+    /// Views the blocks of `store` with bits in the given order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is `MsbFirst` and `store.bit_len()` is not a
+    /// multiple of `Block::nbits()`.
+    pub fn new(store: Store, order: BitOrder) -> Self {
+        assert!(order == BitOrder::LsbFirst
+                    || Store::Block::mod_nbits(store.bit_len()) == 0,
+                "OrderedBits::new: partial last block");
+        OrderedBits { store, order }
+    }
+
+    /// The bit order within each block.
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Borrows a reference to the underlying store.
+    pub fn inner(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns the underlying store.
+    pub fn into_inner(self) -> Store {
+        self.store
+    }
+
+    // The offset within its block of bit `position` of the view.
+    #[inline]
+    fn stored_address(&self, position: u64) -> Address {
+        let mut address = Address::new::<Store::Block>(position);
+        if self.order == BitOrder::MsbFirst {
+            address.bit_offset = Store::Block::nbits() - 1 - address.bit_offset;
+        }
+        address
+    }
+
+    #[inline]
+    fn convert(&self, block: Store::Block) -> Store::Block {
+        match self.order {
+            BitOrder::MsbFirst => block.reverse_block(),
+            BitOrder::LsbFirst => block,
+        }
+    }
+}
+
+impl<Store: BitVec> BitVec for OrderedBits<Store> {
+    type Block = Store::Block;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.store.bit_len()
+    }
+
+    #[inline]
+    fn block_len(&self) -> usize {
+        self.store.block_len()
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.bit_len(), "OrderedBits::get_bit: out of bounds");
+        let address = self.stored_address(position);
+        self.store.get_block(address.block_index).get_bit(address.bit_offset)
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> Self::Block {
+        self.convert(self.store.get_block(position))
+    }
+}
+
+impl<Store: BitVecMut> BitVecMut for OrderedBits<Store> {
+    fn set_bit(&mut self, position: u64, value: bool) {
+        assert!(position < self.bit_len(), "OrderedBits::set_bit: out of bounds");
+        let address = self.stored_address(position);
+        let block = self.store.get_block(address.block_index)
+                        .with_bit(address.bit_offset, value);
+        self.store.set_block(address.block_index, block);
+    }
+
+    #[inline]
+    fn set_block(&mut self, position: usize, value: Self::Block) {
+        let value = self.convert(value);
+        self.store.set_block(position, value);
+    }
+}
+
+impl<Store: SpaceUsage> SpaceUsage for OrderedBits<Store> {
+    #[inline]
+    fn is_stack_only() -> bool { Store::is_stack_only() }
+
+    fn heap_bytes(&self) -> usize {
+        self.store.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::*;
+    use rank::{BitRankSupport, JacobsonRank};
+    use quickcheck::quickcheck;
+
+    // The same logical bits under both orders.
+    fn both(pattern: &[bool]) -> (OrderedBits<Vec<u32>>, OrderedBits<Vec<u32>>) {
+        let blocks = pattern.len().div_ceil(32);
+        let mut lsb = OrderedBits::new(vec![0; blocks], BitOrder::LsbFirst);
+        let mut msb = OrderedBits::new(vec![0; blocks], BitOrder::MsbFirst);
+        for (i, &bit) in pattern.iter().enumerate() {
+            lsb.set_bit(i as u64, bit);
+            msb.set_bit(i as u64, bit);
+        }
+        (lsb, msb)
+    }
+
+    #[test]
+    fn orders() {
+        let pattern: Vec<bool> = (0 .. 64).map(|i| i % 5 == 0 || i == 33).collect();
+        let (lsb, msb) = both(&pattern);
+
+        for (i, &bit) in pattern.iter().enumerate() {
+            assert_eq!(bit, lsb.get_bit(i as u64));
+            assert_eq!(bit, msb.get_bit(i as u64));
+        }
+        for i in 0 .. 2 {
+            assert_eq!(lsb.get_block(i), msb.get_block(i));
+            assert_eq!(lsb.inner()[i], msb.inner()[i].reverse_bits());
+        }
+
+        // Bit 0 is the low bit of the first stored byte, or the high bit
+        // of the first word's highest byte.
+        let lsb_bytes = BitVector::from(lsb.into_inner()).to_bytes_with_order(BitOrder::LsbFirst);
+        let msb_bytes = BitVector::from(msb.into_inner()).to_bytes_with_order(BitOrder::LsbFirst);
+        assert_ne!(lsb_bytes, msb_bytes);
+        assert_eq!(0b0010_0001, lsb_bytes[0]);
+        assert_eq!(0b1000_0100, msb_bytes[3]);
+        assert_eq!(lsb_bytes[0].reverse_bits(), msb_bytes[3]);
+    }
+
+    #[test]
+    fn lsb_is_identity() {
+        let blocks = vec![0x1234_5678u32, 0xDEAD_BEEF];
+        let bits = OrderedBits::new(&*blocks, BitOrder::LsbFirst);
+        for i in 0 .. 64 {
+            assert_eq!(blocks.get_bit(i), bits.get_bit(i));
+        }
+        assert_eq!(BitOrder::LsbFirst, bits.order());
+
+        let partial = BitVector::<u32>::with_fill(40, true);
+        assert_eq!(40, OrderedBits::new(&partial, BitOrder::LsbFirst).bit_len());
+    }
+
+    #[test]
+    fn rank_over_msb() {
+        let mut bits = OrderedBits::new(vec![0u64; 4], BitOrder::MsbFirst);
+        bits.set_block(1, 0b111);
+        assert!(bits.get_bit(64) && bits.get_bit(66) && !bits.get_bit(67));
+        assert_eq!(0b111 << 61, bits.inner()[1]);
+
+        let rank = JacobsonRank::new(&bits);
+        assert_eq!(0, rank.rank1(63));
+        assert_eq!(2, rank.rank1(65));
+        assert_eq!(3, rank.total_ones());
+    }
+
+    #[test]
+    fn sliced() {
+        let bits = OrderedBits::new(vec![0xF000_0000u32], BitOrder::MsbFirst);
+        let slice = BitSlice::new(&bits, 0 .. 6);
+        assert_eq!(6, slice.bit_len());
+        assert_eq!(0b1111, slice.get_block(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn msb_partial_block() {
+        OrderedBits::new(BitVector::<u32>::with_fill(40, true), BitOrder::MsbFirst);
+    }
+
+    fn orders_prop(pattern: Vec<bool>) -> bool {
+        let (lsb, msb) = both(&pattern);
+        pattern.iter().enumerate().all(|(i, &bit)| {
+            lsb.get_bit(i as u64) == bit && msb.get_bit(i as u64) == bit
+        }) && (0 .. lsb.block_len()).all(|i| lsb.get_block(i) == msb.get_block(i))
+    }
+
+    #[test]
+    fn orders_qc() {
+        quickcheck(orders_prop as fn(Vec<bool>) -> bool);
+    }
+}
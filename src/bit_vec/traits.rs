@@ -1,3 +1,6 @@
+use std::cmp;
+use std::ops::Range;
+
 use num_traits::{One, Zero, ToPrimitive};
 
 use storage::{Address, BlockType};
@@ -17,7 +20,13 @@ pub trait BitVec {
 
     /// The length of the slice in blocks.
     fn block_len(&self) -> usize {
-        self.bit_len().ceil_div(Self::Block::nbits() as u64) as usize
+        Self::Block::blocks_for_bits(self.bit_len())
+    }
+
+    /// The number of bytes needed to hold the bits, `ceil(bit_len / 8)`.
+    #[inline]
+    fn byte_len(&self) -> usize {
+        self.bit_len().ceil_div(8) as usize
     }
 
     /// Gets the bit at `position`
@@ -48,7 +57,9 @@ pub trait BitVec {
     ///
     /// # Panics
     ///
-    /// Panics if `position` is out of bounds.
+    /// Panics if `position >= self.block_len()`. Implementations must
+    /// check this rather than return a block past the end, even when
+    /// their storage has room for one; use `try_get_block` to probe.
     fn get_block(&self, position: usize) -> Self::Block {
         assert!(position < self.block_len(),
                 "IntSlice::get_block: out of bounds");
@@ -68,6 +79,71 @@ pub trait BitVec {
         result
     }
 
+    /// Gets the bit at `position` without checking bounds, except in
+    /// debug builds.
+    ///
+    /// The default implementation reads the bit from
+    /// `get_block_unchecked`, so it skips the bounds check whenever that
+    /// does.
+    ///
+    /// # Safety
+    ///
+    /// `position` must be less than `self.bit_len()`. Implementations
+    /// may read out of bounds memory otherwise.
+    #[inline]
+    unsafe fn get_bit_unchecked(&self, position: u64) -> bool {
+        debug_assert!(position < self.bit_len(),
+                      "BitVec::get_bit_unchecked: out of bounds");
+
+        let address = Address::new::<Self::Block>(position);
+        let block = self.get_block_unchecked(address.block_index);
+        block.get_bit(address.bit_offset)
+    }
+
+    /// Gets the block at `position` without checking bounds, except in
+    /// debug builds.
+    ///
+    /// The default implementation calls `get_block`, which checks
+    /// anyway; types backed by a slice override it to skip the check.
+    ///
+    /// # Safety
+    ///
+    /// `position` must be less than `self.block_len()`. Implementations
+    /// may read out of bounds memory otherwise.
+    #[inline]
+    unsafe fn get_block_unchecked(&self, position: usize) -> Self::Block {
+        debug_assert!(position < self.block_len(),
+                      "BitVec::get_block_unchecked: out of bounds");
+        self.get_block(position)
+    }
+
+    /// Gets the block at `position`, or `None` if
+    /// `position >= self.block_len()`.
+    #[inline]
+    fn try_get_block(&self, position: usize) -> Option<Self::Block> {
+        if position < self.block_len() {
+            Some(self.get_block(position))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the block at `position` with its padding cleared, so that
+    /// bits past `self.bit_len()` read as 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.block_len()`.
+    #[inline]
+    fn get_block_masked(&self, position: usize) -> Self::Block {
+        let block = self.get_block(position);
+        if position + 1 == self.block_len() {
+            block & Self::Block::last_block_mask(self.bit_len())
+        } else {
+            block
+        }
+    }
+
     /// Gets `count` bits starting at bit index `start`, interpreted as a
     /// little-endian integer.
     ///
@@ -150,6 +226,28 @@ pub trait BitVecMut: BitVec {
         }
     }
 
+    /// Sets the block at `position` to `value`, keeping whatever bits
+    /// the last block already has past `self.bit_len()`.
+    ///
+    /// Use this rather than `set_block` when `value` may have bits set
+    /// past the end: not every implementation of `set_block` masks them,
+    /// and rank structures over the raw blocks count on the padding
+    /// staying zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    fn set_block_masked(&mut self, position: usize, value: Self::Block) {
+        let value = if position + 1 == self.block_len() {
+            let mask = Self::Block::last_block_mask(self.bit_len());
+            value & mask | self.get_block(position) & !mask
+        } else {
+            value
+        };
+
+        self.set_block(position, value);
+    }
+
     /// Sets `count` bits starting at bit index `start`, interpreted as a
     /// little-endian integer.
     ///
@@ -184,6 +282,130 @@ pub trait BitVecMut: BitVec {
         self.set_block(address.block_index, new_block1);
         self.set_block(address.block_index + 1, new_block2);
     }
+
+    /// Sets every bit in the half-open interval `range` to `value`.
+    ///
+    /// Blocks entirely inside the range are written whole, and only the
+    /// first and last are read and masked. Empty ranges change nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range.start > range.end` or `range.end > self.bit_len()`,
+    /// even if the range is empty.
+    fn set_range(&mut self, range: Range<u64>, value: bool) {
+        assert!(range.start <= range.end, "BitVecMut::set_range: bad range");
+        assert!(range.end <= self.bit_len(), "BitVecMut::set_range: out of bounds");
+        if range.start == range.end { return; }
+
+        let fill = if value { !Self::Block::zero() } else { Self::Block::zero() };
+        let first = Address::new::<Self::Block>(range.start);
+        let last = Address::new::<Self::Block>(range.end - 1);
+
+        let update = |bits: &mut Self, index: usize, mask: Self::Block| {
+            let old_block = bits.get_block(index);
+            bits.set_block(index, old_block & !mask | fill & mask);
+        };
+
+        if first.block_index == last.block_index {
+            let mask = Self::Block::range_mask(first.bit_offset, last.bit_offset + 1);
+            update(self, first.block_index, mask);
+            return;
+        }
+
+        update(self, first.block_index, !Self::Block::low_mask(first.bit_offset));
+        for i in first.block_index + 1 .. last.block_index {
+            self.set_block(i, fill);
+        }
+        update(self, last.block_index, Self::Block::low_mask(last.bit_offset + 1));
+    }
+
+    /// Copies the bits of `src` in the half-open interval `src_range` into
+    /// `self`, starting at bit `dst_start`.
+    ///
+    /// The source and destination needn’t have the same offset within a
+    /// block: each destination block is assembled from at most two
+    /// source blocks, and only the first and last destination blocks are
+    /// read and masked. Empty ranges copy nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `src_range.start > src_range.end`, if `src_range.end >
+    /// src.bit_len()`, or if the copied bits would extend past
+    /// `self.bit_len()`, even if the range is empty.
+    fn copy_bits_from<Other>(&mut self, dst_start: u64, src: &Other,
+                             src_range: Range<u64>)
+        where Other: BitVec<Block = Self::Block> + ?Sized
+    {
+        assert!(src_range.start <= src_range.end,
+                "BitVecMut::copy_bits_from: bad range");
+        assert!(src_range.end <= src.bit_len(),
+                "BitVecMut::copy_bits_from: source out of bounds");
+        let len = src_range.end - src_range.start;
+        assert!(dst_start <= self.bit_len() && len <= self.bit_len() - dst_start,
+                "BitVecMut::copy_bits_from: destination out of bounds");
+        if len == 0 { return; }
+
+        let nbits = Self::Block::nbits();
+        let mut src_pos = src_range.start;
+        let mut dst_pos = dst_start;
+        let dst_end = dst_start + len;
+
+        // Bring the destination up to a block boundary.
+        let head = Self::Block::mod_nbits(dst_pos);
+        if head != 0 {
+            let count = cmp::min((nbits - head) as u64, len) as usize;
+            self.set_bits(dst_pos, count, src.get_bits(src_pos, count));
+            src_pos += count as u64;
+            dst_pos += count as u64;
+        }
+
+        while dst_end - dst_pos >= nbits as u64 {
+            let block = src.get_bits(src_pos, nbits);
+            self.set_block(Self::Block::div_nbits(dst_pos), block);
+            src_pos += nbits as u64;
+            dst_pos += nbits as u64;
+        }
+
+        if dst_pos < dst_end {
+            let count = (dst_end - dst_pos) as usize;
+            self.set_bits(dst_pos, count, src.get_bits(src_pos, count));
+        }
+    }
+
+    /// Exchanges the bits in the half-open interval `a` with those in `b`.
+    ///
+    /// The ranges needn’t have the same offset within a block; the bits
+    /// are exchanged up to a block at a time, through a pair of
+    /// temporary blocks. Empty ranges swap nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either range is backwards or extends past
+    /// `self.bit_len()`, or if the ranges differ in length, or if they
+    /// are non-empty and overlap.
+    fn swap_ranges(&mut self, a: Range<u64>, b: Range<u64>) {
+        assert!(a.start <= a.end && b.start <= b.end,
+                "BitVecMut::swap_ranges: bad range");
+        let len = a.end - a.start;
+        assert_eq!(len, b.end - b.start,
+                   "BitVecMut::swap_ranges: lengths differ");
+        assert!(a.end <= self.bit_len() && b.end <= self.bit_len(),
+                "BitVecMut::swap_ranges: out of bounds");
+        if len == 0 { return; }
+        assert!(a.end <= b.start || b.end <= a.start,
+                "BitVecMut::swap_ranges: ranges overlap");
+
+        let nbits = Self::Block::nbits() as u64;
+        let mut offset = 0;
+        while offset < len {
+            let count = cmp::min(nbits, len - offset) as usize;
+            let from_a = self.get_bits(a.start + offset, count);
+            let from_b = self.get_bits(b.start + offset, count);
+            self.set_bits(a.start + offset, count, from_b);
+            self.set_bits(b.start + offset, count, from_a);
+            offset += count as u64;
+        }
+    }
 }
 
 /// Bit vector operations that change the length.
@@ -236,6 +458,13 @@ impl<Block: BlockType> BitVec for [Block] {
     fn get_block(&self, position: usize) -> Block {
         self[position]
     }
+
+    #[inline]
+    unsafe fn get_block_unchecked(&self, position: usize) -> Block {
+        debug_assert!(position < self.len(),
+                      "BitVec::get_block_unchecked: out of bounds");
+        *self.get_unchecked(position)
+    }
 }
 
 impl<Block: BlockType> BitVecMut for [Block] {
@@ -245,48 +474,91 @@ impl<Block: BlockType> BitVecMut for [Block] {
     }
 }
 
-impl<'a, Block: BlockType> BitVec for &'a [Block] {
-    type Block = Block;
+impl<Bits: BitVec + ?Sized> BitVec for &Bits {
+    type Block = Bits::Block;
 
     #[inline]
-    fn bit_len(&self) -> u64 {
-        self.len() as u64 * Block::nbits() as u64
+    fn bit_len(&self) -> u64 { (**self).bit_len() }
+
+    #[inline]
+    fn block_len(&self) -> usize { (**self).block_len() }
+
+    #[inline]
+    fn get_bit(&self, position: u64) -> bool { (**self).get_bit(position) }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> Bits::Block {
+        (**self).get_block(position)
     }
 
     #[inline]
-    fn block_len(&self) -> usize {
-        self.len()
+    fn get_bits(&self, start: u64, count: usize) -> Bits::Block {
+        (**self).get_bits(start, count)
     }
 
     #[inline]
-    fn get_block(&self, position: usize) -> Block {
-        self[position]
+    unsafe fn get_bit_unchecked(&self, position: u64) -> bool {
+        (**self).get_bit_unchecked(position)
+    }
+
+    #[inline]
+    unsafe fn get_block_unchecked(&self, position: usize) -> Bits::Block {
+        (**self).get_block_unchecked(position)
     }
 }
 
-impl<'a, Block: BlockType> BitVec for &'a mut [Block] {
-    type Block = Block;
+impl<Bits: BitVec + ?Sized> BitVec for &mut Bits {
+    type Block = Bits::Block;
 
     #[inline]
-    fn bit_len(&self) -> u64 {
-        self.len() as u64 * Block::nbits() as u64
+    fn bit_len(&self) -> u64 { (**self).bit_len() }
+
+    #[inline]
+    fn block_len(&self) -> usize { (**self).block_len() }
+
+    #[inline]
+    fn get_bit(&self, position: u64) -> bool { (**self).get_bit(position) }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> Bits::Block {
+        (**self).get_block(position)
     }
 
     #[inline]
-    fn block_len(&self) -> usize {
-        self.len()
+    fn get_bits(&self, start: u64, count: usize) -> Bits::Block {
+        (**self).get_bits(start, count)
     }
 
     #[inline]
-    fn get_block(&self, position: usize) -> Block {
-        self[position]
+    unsafe fn get_bit_unchecked(&self, position: u64) -> bool {
+        (**self).get_bit_unchecked(position)
+    }
+
+    #[inline]
+    unsafe fn get_block_unchecked(&self, position: usize) -> Bits::Block {
+        (**self).get_block_unchecked(position)
     }
 }
 
-impl<'a, Block: BlockType> BitVecMut for &'a mut [Block] {
+impl<Bits: BitVecMut + ?Sized> BitVecMut for &mut Bits {
     #[inline]
-    fn set_block(&mut self, position: usize, value: Block) {
-        self[position] = value;
+    fn set_bit(&mut self, position: u64, value: bool) {
+        (**self).set_bit(position, value)
+    }
+
+    #[inline]
+    fn set_block(&mut self, position: usize, value: Bits::Block) {
+        (**self).set_block(position, value)
+    }
+
+    #[inline]
+    fn set_block_masked(&mut self, position: usize, value: Bits::Block) {
+        (**self).set_block_masked(position, value)
+    }
+
+    #[inline]
+    fn set_bits(&mut self, start: u64, count: usize, value: Bits::Block) {
+        (**self).set_bits(start, count, value)
     }
 }
 
@@ -307,6 +579,11 @@ impl<Block: BlockType> BitVec for Vec<Block> {
     fn get_block(&self, position: usize) -> Block {
         self[position]
     }
+
+    #[inline]
+    unsafe fn get_block_unchecked(&self, position: usize) -> Block {
+        self[..].get_block_unchecked(position)
+    }
 }
 
 impl<Block: BlockType> BitVecMut for Vec<Block> {
@@ -0,0 +1,402 @@
+//! Balanced parentheses, the basis of succinct ordered trees.
+//!
+//! A sequence of parentheses is stored as bits, 1 for `(` and 0 for `)`.
+//! Navigation reduces to searching for the nearest position where the
+//! *excess*, the number of opens minus the number of closes in a prefix,
+//! reaches a given value. A range-min-max tree over the blocks of the bit
+//! vector answers these searches in *O*(lg *n*) block operations.
+
+use std::cmp;
+
+use bit_vec::BitVec;
+use error::{Error, Result};
+use rank::{BitRankSupport, JacobsonRank};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A balanced sequence of parentheses supporting `find_close`,
+/// `find_open` and `enclose`.
+///
+/// Construct with `BalancedParens::new`.
+#[derive(Clone, Debug)]
+pub struct BalancedParens<Store> {
+    rank: JacobsonRank<Store>,
+    // The range-min-max tree in heap order: node 1 is the root, node `i`
+    // has children `2 * i` and `2 * i + 1`, and the leaves start at
+    // `leaf_count`. Each node holds the excess over its range and the
+    // minimum excess over the prefixes of its range (including the
+    // empty prefix).
+    leaf_count: usize,
+    sums: Vec<i64>,
+    mins: Vec<i64>,
+}
+
+impl<Store: BitVec> BalancedParens<Store> {
+    /// Creates a balanced parentheses structure from the given bits,
+    /// where 1 is an open parenthesis and 0 a close parenthesis.
+    ///
+    /// Returns `None` if the sequence isn’t balanced, that is, if some
+    /// prefix has more closes than opens or the whole sequence doesn’t
+    /// have equal numbers of each.
+    pub fn new(bits: Store) -> Option<Self> {
+        let n = bits.bit_len();
+        let block_bits = Store::Block::nbits() as u64;
+        let block_len = bits.block_len();
+
+        let mut leaf_count = 1;
+        while leaf_count < block_len {
+            leaf_count *= 2;
+        }
+
+        let mut sums = vec![0; 2 * leaf_count];
+        let mut mins = vec![0; 2 * leaf_count];
+
+        for i in 0 .. block_len {
+            let start = i as u64 * block_bits;
+            let limit = cmp::min(start + block_bits, n);
+            let mut sum = 0;
+            let mut min = 0;
+            for position in start .. limit {
+                sum += delta(&bits, position);
+                min = cmp::min(min, sum);
+            }
+            sums[leaf_count + i] = sum;
+            mins[leaf_count + i] = min;
+        }
+
+        for node in (1 .. leaf_count).rev() {
+            let (left, right) = (2 * node, 2 * node + 1);
+            sums[node] = sums[left] + sums[right];
+            mins[node] = cmp::min(mins[left], sums[left] + mins[right]);
+        }
+
+        if sums[1] != 0 || mins[1] < 0 {
+            return None;
+        }
+
+        Some(BalancedParens {
+            rank: JacobsonRank::new(bits),
+            leaf_count,
+            sums,
+            mins,
+        })
+    }
+
+    /// Creates a balanced parentheses structure like `new`, failing with
+    /// `Error::Unbalanced` instead of returning `None`.
+    pub fn try_new(bits: Store) -> Result<Self> {
+        Self::new(bits).ok_or(Error::Unbalanced)
+    }
+
+    /// Borrows a reference to the underlying bit store.
+    pub fn inner(&self) -> &Store {
+        self.rank.inner()
+    }
+
+    /// The number of parentheses.
+    pub fn len(&self) -> u64 {
+        self.rank.bit_len()
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Is the parenthesis at `position` an open parenthesis?
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn is_open(&self, position: u64) -> bool {
+        self.rank.get_bit(position)
+    }
+
+    /// The number of unmatched open parentheses up to and including
+    /// `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn excess(&self, position: u64) -> u64 {
+        2 * self.rank.rank1(position) - (position + 1)
+    }
+
+    /// Returns the position of the close parenthesis matching the open
+    /// parenthesis at `position`, or `None` if `position` holds a close
+    /// parenthesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn find_close(&self, position: u64) -> Option<u64> {
+        if !self.is_open(position) { return None; }
+
+        let before = self.prefix_excess(position);
+        self.forward_search(position + 1, before + 1, before)
+            .map(|prefix| prefix - 1)
+    }
+
+    /// Returns the position of the open parenthesis matching the close
+    /// parenthesis at `position`, or `None` if `position` holds an open
+    /// parenthesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn find_open(&self, position: u64) -> Option<u64> {
+        if self.is_open(position) { return None; }
+
+        let before = self.prefix_excess(position);
+        self.backward_search(position, before, before - 1)
+    }
+
+    /// Returns the position of the open parenthesis of the nearest pair
+    /// enclosing the open parenthesis at `position`, or `None` if there
+    /// is no enclosing pair or `position` holds a close parenthesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn enclose(&self, position: u64) -> Option<u64> {
+        if !self.is_open(position) { return None; }
+
+        let before = self.prefix_excess(position);
+        if before == 0 { return None; }
+        self.backward_search(position, before, before - 1)
+    }
+
+    // The excess of the first `prefix` parentheses.
+    fn prefix_excess(&self, prefix: u64) -> i64 {
+        if prefix == 0 { 0 } else { self.excess(prefix - 1) as i64 }
+    }
+
+    fn block_bits() -> u64 {
+        Store::Block::nbits() as u64
+    }
+
+    // Returns the smallest prefix length `k > start` whose excess is
+    // `target`, given that the excess at `start` is `current > target`.
+    fn forward_search(&self, start: u64, mut current: i64, target: i64)
+                      -> Option<u64> {
+        let n = self.len();
+        if start >= n { return None; }
+
+        let block = (start / Self::block_bits()) as usize;
+        let block_limit = cmp::min((block as u64 + 1) * Self::block_bits(), n);
+        for position in start .. block_limit {
+            current += delta(self.inner(), position);
+            if current == target { return Some(position + 1); }
+        }
+
+        // Climb until some right sibling reaches the target.
+        let mut node = self.leaf_count + block;
+        loop {
+            if node == 1 { return None; }
+            if node & 1 == 0 {
+                let sibling = node + 1;
+                if current + self.mins[sibling] <= target {
+                    node = sibling;
+                    break;
+                }
+                current += self.sums[sibling];
+            }
+            node /= 2;
+        }
+
+        // Descend to the leftmost leaf that reaches it.
+        while node < self.leaf_count {
+            let left = 2 * node;
+            if current + self.mins[left] <= target {
+                node = left;
+            } else {
+                current += self.sums[left];
+                node = left + 1;
+            }
+        }
+
+        let start = (node - self.leaf_count) as u64 * Self::block_bits();
+        let limit = cmp::min(start + Self::block_bits(), n);
+        for position in start .. limit {
+            current += delta(self.inner(), position);
+            if current == target { return Some(position + 1); }
+        }
+
+        unreachable!("BalancedParens::forward_search: inconsistent tree")
+    }
+
+    // Returns the largest prefix length `k < start` whose excess is
+    // `target`, given that the excess at `start` is `current > target`.
+    fn backward_search(&self, start: u64, mut current: i64, target: i64)
+                       -> Option<u64> {
+        if start == 0 { return None; }
+
+        let block = ((start - 1) / Self::block_bits()) as usize;
+        let block_start = block as u64 * Self::block_bits();
+        for position in (block_start .. start).rev() {
+            current -= delta(self.inner(), position);
+            if current == target { return Some(position); }
+        }
+
+        // Climb until some left sibling reaches the target. A node’s
+        // minimum is relative to the excess at its left end, which is
+        // `current - sum` when `current` is the excess at its right end.
+        let mut node = self.leaf_count + block;
+        loop {
+            if node == 1 { return None; }
+            if node & 1 == 1 {
+                let sibling = node - 1;
+                let left_end = current - self.sums[sibling];
+                if left_end + self.mins[sibling] <= target {
+                    node = sibling;
+                    break;
+                }
+                current = left_end;
+            }
+            node /= 2;
+        }
+
+        // Descend to the rightmost leaf that reaches it.
+        while node < self.leaf_count {
+            let right = 2 * node + 1;
+            let left_end = current - self.sums[right];
+            if left_end + self.mins[right] <= target {
+                node = right;
+            } else {
+                current = left_end;
+                node = right - 1;
+            }
+        }
+
+        let start = (node - self.leaf_count) as u64 * Self::block_bits();
+        let limit = start + Self::block_bits();
+        for position in (start .. limit).rev() {
+            current -= delta(self.inner(), position);
+            if current == target { return Some(position); }
+        }
+
+        unreachable!("BalancedParens::backward_search: inconsistent tree")
+    }
+}
+
+// The change in excess from the parenthesis at `position`.
+fn delta<Bits: BitVec + ?Sized>(bits: &Bits, position: u64) -> i64 {
+    if bits.get_bit(position) { 1 } else { -1 }
+}
+
+impl<Store: SpaceUsage> SpaceUsage for BalancedParens<Store> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.rank.heap_bytes() + self.sums.heap_bytes() + self.mins.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+
+    fn parse(parens: &str) -> BitVector<u8> {
+        let mut result = BitVector::new();
+        for c in parens.chars() {
+            result.push_bit(c == '(');
+        }
+        result
+    }
+
+    fn build(parens: &str) -> BalancedParens<BitVector<u8>> {
+        BalancedParens::new(parse(parens)).expect("balanced")
+    }
+
+    type Answers = Vec<Option<u64>>;
+
+    // Navigation computed with an explicit stack.
+    fn naive(parens: &str) -> (Answers, Answers, Answers) {
+        let n = parens.len();
+        let mut close = vec![None; n];
+        let mut open = vec![None; n];
+        let mut enclose = vec![None; n];
+        let mut stack: Vec<u64> = Vec::new();
+
+        for (i, c) in parens.chars().enumerate() {
+            if c == '(' {
+                enclose[i] = stack.last().cloned();
+                stack.push(i as u64);
+            } else {
+                let j = stack.pop().unwrap();
+                close[j as usize] = Some(i as u64);
+                open[i] = Some(j);
+            }
+        }
+
+        (close, open, enclose)
+    }
+
+    fn check(parens: &str) {
+        let bp = build(parens);
+        let (close, open, enclose) = naive(parens);
+        for i in 0 .. parens.len() {
+            assert_eq!(close[i], bp.find_close(i as u64));
+            assert_eq!(open[i], bp.find_open(i as u64));
+            assert_eq!(enclose[i], bp.enclose(i as u64));
+        }
+    }
+
+    #[test]
+    fn small_tree() {
+        //          0123456789
+        let bp = build("(()(()))()");
+        assert_eq!(Some(7), bp.find_close(0));
+        assert_eq!(Some(2), bp.find_close(1));
+        assert_eq!(Some(6), bp.find_close(3));
+        assert_eq!(None, bp.find_close(2));
+        assert_eq!(Some(0), bp.find_open(7));
+        assert_eq!(Some(4), bp.find_open(5));
+        assert_eq!(Some(8), bp.find_open(9));
+        assert_eq!(None, bp.enclose(0));
+        assert_eq!(Some(0), bp.enclose(3));
+        assert_eq!(Some(3), bp.enclose(4));
+        assert_eq!(None, bp.enclose(8));
+        assert_eq!(3, bp.excess(4));
+
+        check("(()(()))()");
+    }
+
+    #[test]
+    fn across_blocks() {
+        // Deep nesting and long runs of siblings, spanning many u8 blocks.
+        let deep = format!("{}{}", "(".repeat(100), ")".repeat(100));
+        check(&deep);
+
+        let wide = format!("({})", "()".repeat(100));
+        check(&wide);
+
+        let mut mixed = String::new();
+        for i in 0 .. 40 {
+            mixed.push_str(&"(".repeat(i % 7 + 1));
+            mixed.push_str(&")".repeat(i % 7 + 1));
+            if i % 5 < 1 { mixed = format!("({})", mixed); }
+        }
+        check(&mixed);
+    }
+
+    #[test]
+    fn empty() {
+        let bp = build("");
+        assert!(bp.is_empty());
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert!(BalancedParens::new(parse("(")).is_none());
+        assert!(BalancedParens::new(parse(")(")).is_none());
+        assert!(BalancedParens::new(parse("(()")).is_none());
+        assert!(BalancedParens::new(parse("())(")).is_none());
+        assert!(BalancedParens::new(parse(&"(".repeat(17))).is_none());
+        assert_eq!(Some(Error::Unbalanced),
+                   BalancedParens::try_new(parse("(()")).err());
+        assert!(BalancedParens::try_new(parse("(())")).is_ok());
+    }
+}
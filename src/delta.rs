@@ -0,0 +1,209 @@
+//! Delta-encoded sorted sequences.
+//!
+//! A [`DeltaVector`](struct.DeltaVector.html) stores the differences
+//! between consecutive values of a sorted sequence, each in as many bits
+//! as the largest difference needs, and samples every `SAMPLE_RATE`th
+//! value in full. Access sums at most `SAMPLE_RATE - 1` differences from
+//! the nearest sample. When the gaps are uniform this is simpler and
+//! about as small as [Elias–Fano](../elias_fano/index.html), but a single
+//! large gap widens every stored difference.
+
+use int_vec::{IntVec, IntVector};
+use space_usage::SpaceUsage;
+
+// The number of values between full samples.
+const SAMPLE_RATE: u64 = 32;
+
+/// A delta-encoded sorted sequence of `u64`s.
+///
+/// Construct with `DeltaVector::new`.
+#[derive(Clone, Debug)]
+pub struct DeltaVector {
+    gaps: IntVector<u64>,
+    samples: IntVector<u64>,
+}
+
+impl DeltaVector {
+    /// Encodes a sorted sequence of values.
+    ///
+    /// Duplicate values are allowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is not sorted in non-decreasing order.
+    pub fn new(values: &[u64]) -> Self {
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]),
+                "DeltaVector::new: values not sorted");
+
+        let max_gap = values.windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .max().unwrap_or(0);
+        let max = values.last().cloned().unwrap_or(0);
+
+        let len = values.len() as u64;
+        let mut gaps = IntVector::with_capacity(width(max_gap), len);
+        let mut samples = IntVector::with_capacity(width(max),
+                                                   len.div_ceil(SAMPLE_RATE));
+
+        for (i, &value) in values.iter().enumerate() {
+            if (i as u64).is_multiple_of(SAMPLE_RATE) {
+                samples.push(value);
+                gaps.push(0);
+            } else {
+                gaps.push(value - values[i - 1]);
+            }
+        }
+
+        DeltaVector { gaps, samples }
+    }
+
+    /// The number of values in the sequence.
+    pub fn len(&self) -> u64 {
+        self.gaps.len()
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.gaps.is_empty()
+    }
+
+    /// The bit width of each stored difference.
+    pub fn gap_bits(&self) -> usize {
+        self.gaps.element_bits()
+    }
+
+    /// Returns the `index`th value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn get(&self, index: u64) -> u64 {
+        assert!(index < self.len(), "DeltaVector::get: out of bounds");
+
+        let sample = index / SAMPLE_RATE;
+        let mut result = self.samples.get(sample);
+        for i in sample * SAMPLE_RATE + 1 ..= index {
+            result += self.gaps.get(i);
+        }
+        result
+    }
+
+    /// Iterates over the values in order.
+    pub fn iter(&self) -> DeltaIter<'_> {
+        DeltaIter {
+            vector: self,
+            index: 0,
+            current: 0,
+        }
+    }
+}
+
+// The number of bits needed for values up to `max`.
+fn width(max: u64) -> usize {
+    if max == 0 { 1 } else { 64 - max.leading_zeros() as usize }
+}
+
+/// An iterator over the values of a `DeltaVector`.
+#[derive(Clone, Debug)]
+pub struct DeltaIter<'a> {
+    vector: &'a DeltaVector,
+    index: u64,
+    current: u64,
+}
+
+impl<'a> Iterator for DeltaIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index == self.vector.len() { return None; }
+
+        self.current = if self.index.is_multiple_of(SAMPLE_RATE) {
+            self.vector.samples.get(self.index / SAMPLE_RATE)
+        } else {
+            self.current + self.vector.gaps.get(self.index)
+        };
+        self.index += 1;
+        Some(self.current)
+    }
+}
+
+impl SpaceUsage for DeltaVector {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.gaps.heap_bytes() + self.samples.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use internal::test_util::sorted_values;
+    use quickcheck::quickcheck;
+
+    fn check(values: &[u64]) {
+        let delta = DeltaVector::new(values);
+        assert_eq!(values.len() as u64, delta.len());
+
+        for (i, &value) in values.iter().enumerate() {
+            assert_eq!(value, delta.get(i as u64));
+        }
+
+        let decoded: Vec<u64> = delta.iter().collect();
+        assert_eq!(values.to_vec(), decoded);
+        assert!(decoded.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn random() {
+        for &gap in &[0, 1, 5, 100, 1 << 20] {
+            check(&sorted_values(1000, gap, gap + 3));
+        }
+    }
+
+    #[test]
+    fn edges() {
+        check(&[]);
+        check(&[0]);
+        check(&[7, 7, 7]);
+        check(&[0, u64::MAX]);
+        check(&[u64::MAX; 40]);
+    }
+
+    #[test]
+    fn gap_width() {
+        let delta = DeltaVector::new(&[100, 101, 103, 110, 115]);
+        assert_eq!(3, delta.gap_bits());
+
+        let values = sorted_values(10_000, 8, 1);
+        let delta = DeltaVector::new(&values);
+        assert_eq!(5, delta.gap_bits());
+        assert!(delta.total_bytes() < values.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_oob() {
+        DeltaVector::new(&[1, 2]).get(2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        DeltaVector::new(&[3, 2]);
+    }
+
+    fn get_prop(mut values: Vec<u32>) -> bool {
+        values.sort();
+        let values: Vec<u64> = values.into_iter().map(|x| x as u64).collect();
+        let delta = DeltaVector::new(&values);
+        values.iter().enumerate().all(|(i, &value)| delta.get(i as u64) == value)
+            && delta.iter().eq(values.iter().cloned())
+    }
+
+    #[test]
+    fn get_qc() {
+        quickcheck(get_prop as fn(Vec<u32>) -> bool);
+    }
+}
@@ -0,0 +1,252 @@
+//! Elias–Fano encoding of monotone sequences.
+//!
+//! An [`EliasFano`](struct.EliasFano.html) stores `n` sorted values
+//! drawn from `[0, u)` in about `2n + n lg(u/n)` bits: the low
+//! `lg(u/n)` bits of each value are stored verbatim, and the remaining
+//! high bits are stored in unary in a bit vector that supports select.
+
+use bit_vec::{BitVec, BitVecMut, BitVector};
+use rank::JacobsonRank;
+use select::{BinSearchSelect, Select0Support, Select1Support};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// An Elias–Fano encoded sorted sequence of `u64`s.
+///
+/// Construct with `EliasFano::new`.
+#[derive(Clone, Debug)]
+pub struct EliasFano {
+    len: u64,
+    low_bits: usize,
+    lows: BitVector<u64>,
+    highs: BinSearchSelect<JacobsonRank<BitVector<u64>>>,
+}
+
+impl EliasFano {
+    /// Encodes a sorted sequence of values.
+    ///
+    /// Duplicate values are allowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is not sorted in non-decreasing order.
+    pub fn new(values: &[u64]) -> Self {
+        assert!(values.windows(2).all(|pair| pair[0] <= pair[1]),
+                "EliasFano::new: values not sorted");
+
+        let len = values.len() as u64;
+        let max = values.last().cloned().unwrap_or(0);
+        let low_bits = max.checked_div(len).map_or(0, |ratio| ratio.floor_lg());
+
+        let mut lows = BitVector::with_fill(len * low_bits as u64, false);
+        let mut highs = BitVector::with_fill(len + (max >> low_bits) + 1, false);
+
+        for (i, &value) in values.iter().enumerate() {
+            if low_bits > 0 {
+                lows.set_bits(i as u64 * low_bits as u64, low_bits,
+                              value & u64::low_mask(low_bits));
+            }
+            highs.set_bit((value >> low_bits) + i as u64, true);
+        }
+
+        EliasFano {
+            len,
+            low_bits,
+            lows,
+            highs: BinSearchSelect::new(JacobsonRank::new(highs)),
+        }
+    }
+
+    /// The number of values in the sequence.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the `index`th value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn get(&self, index: u64) -> u64 {
+        assert!(index < self.len, "EliasFano::get: out of bounds");
+
+        let high = self.highs.select1(index)
+                       .expect("EliasFano::get: missing high bit") - index;
+        (high << self.low_bits) | self.get_low(index)
+    }
+
+    /// Returns the number of values strictly less than `value`.
+    pub fn rank(&self, value: u64) -> u64 {
+        self.count_from_bucket(value, |x| x < value)
+    }
+
+    /// Returns the largest value in the sequence that is `<= value`, or
+    /// `None` if there is no such value.
+    pub fn predecessor(&self, value: u64) -> Option<u64> {
+        match self.count_from_bucket(value, |x| x <= value) {
+            0     => None,
+            count => Some(self.get(count - 1)),
+        }
+    }
+
+    /// Returns the smallest value in the sequence that is `>= value`, or
+    /// `None` if there is no such value.
+    pub fn successor(&self, value: u64) -> Option<u64> {
+        let count = self.rank(value);
+        if count < self.len { Some(self.get(count)) } else { None }
+    }
+
+    fn get_low(&self, index: u64) -> u64 {
+        if self.low_bits == 0 {
+            0
+        } else {
+            self.lows.get_bits(index * self.low_bits as u64, self.low_bits)
+        }
+    }
+
+    // Counts the values that satisfy `pred`, which must hold for a
+    // prefix of the sequence, and hold for every value whose high bits
+    // are less than those of `value`.
+    fn count_from_bucket<F: Fn(u64) -> bool>(&self, value: u64, pred: F) -> u64 {
+        let bucket = value >> self.low_bits;
+
+        // The number of values with high bits less than `bucket`.
+        let mut count = if bucket == 0 {
+            0
+        } else {
+            match self.highs.select0(bucket - 1) {
+                Some(position) => position + 1 - bucket,
+                None           => return self.len,
+            }
+        };
+
+        while count < self.len && pred(self.get(count)) {
+            count += 1;
+        }
+
+        count
+    }
+}
+
+impl SpaceUsage for EliasFano {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.lows.heap_bytes() + self.highs.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use internal::test_util::sorted_values;
+    use quickcheck::quickcheck;
+
+    fn naive_predecessor(values: &[u64], value: u64) -> Option<u64> {
+        values.iter().cloned().rev().find(|&x| x <= value)
+    }
+
+    fn naive_successor(values: &[u64], value: u64) -> Option<u64> {
+        values.iter().cloned().find(|&x| x >= value)
+    }
+
+    fn check(values: &[u64]) {
+        let ef = EliasFano::new(values);
+        assert_eq!(values.len() as u64, ef.len());
+
+        for (i, &value) in values.iter().enumerate() {
+            assert_eq!(value, ef.get(i as u64));
+        }
+
+        // Every value and its neighbours, plus the ends of the universe.
+        let mut probes = vec![0, 1];
+        for &value in values {
+            probes.extend_from_slice(&[value.saturating_sub(1), value,
+                                       value.saturating_add(1)]);
+        }
+        let max = values.last().cloned().unwrap_or(0);
+        probes.push(max.saturating_add(2));
+
+        for probe in probes {
+            let rank = values.iter().filter(|&&x| x < probe).count() as u64;
+            assert_eq!(rank, ef.rank(probe));
+            assert_eq!(naive_predecessor(values, probe), ef.predecessor(probe));
+            assert_eq!(naive_successor(values, probe), ef.successor(probe));
+        }
+    }
+
+    #[test]
+    fn small() {
+        let values = [2, 3, 5, 7, 11, 13, 24];
+        let ef = EliasFano::new(&values);
+
+        assert_eq!(None, ef.predecessor(1));
+        assert_eq!(Some(2), ef.predecessor(2));
+        assert_eq!(Some(13), ef.predecessor(23));
+        assert_eq!(Some(24), ef.predecessor(1000));
+        assert_eq!(Some(2), ef.successor(0));
+        assert_eq!(Some(11), ef.successor(8));
+        assert_eq!(Some(24), ef.successor(24));
+        assert_eq!(None, ef.successor(25));
+
+        check(&values);
+    }
+
+    #[test]
+    fn random() {
+        for &gap in &[0, 1, 5, 100] {
+            check(&sorted_values(300, gap, gap + 7));
+        }
+    }
+
+    #[test]
+    fn duplicates_and_edges() {
+        check(&[]);
+        check(&[0]);
+        check(&[0, 0, 0]);
+        check(&[5, 5, 9, 9, 9]);
+        check(&[1 << 20]);
+    }
+
+    #[test]
+    fn huge_values() {
+        let values = [0, 1 << 40, u64::MAX - 1, u64::MAX];
+        let ef = EliasFano::new(&values);
+        for (i, &value) in values.iter().enumerate() {
+            assert_eq!(value, ef.get(i as u64));
+        }
+        assert_eq!(Some(1 << 40), ef.predecessor(u64::MAX - 2));
+        assert_eq!(Some(u64::MAX - 1), ef.successor(1 << 41));
+    }
+
+    fn get_prop(mut values: Vec<u32>) -> bool {
+        values.sort();
+        let values: Vec<u64> = values.into_iter().map(|x| x as u64).collect();
+        let ef = EliasFano::new(&values);
+        values.iter().enumerate().all(|(i, &value)| ef.get(i as u64) == value)
+    }
+
+    #[test]
+    fn get_qc() {
+        quickcheck(get_prop as fn(Vec<u32>) -> bool);
+    }
+
+    #[test]
+    fn space() {
+        let values = sorted_values(10_000, 100, 3);
+        let ef = EliasFano::new(&values);
+        assert!(ef.total_bytes() < values.len() * 8 / 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        EliasFano::new(&[3, 2]);
+    }
+}
@@ -0,0 +1,48 @@
+//! The error type for the fallible constructors and methods.
+
+use std::error;
+use std::fmt;
+use std::result;
+
+/// Why a checked constructor or method rejected its arguments.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Error {
+    /// A position or range lies outside the data.
+    OutOfBounds,
+    /// An element or block width is zero or too large.
+    InvalidWidth,
+    /// A parenthesis sequence isn’t balanced.
+    Unbalanced,
+    /// A value is too large for the element width.
+    Overflow,
+}
+
+/// A `Result` whose error type is [`Error`](enum.Error.html).
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match *self {
+            Error::OutOfBounds  => "out of bounds",
+            Error::InvalidWidth => "invalid width",
+            Error::Unbalanced   => "unbalanced parentheses",
+            Error::Overflow     => "value too large for element width",
+        })
+    }
+}
+
+impl error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!("out of bounds", Error::OutOfBounds.to_string());
+        assert_eq!("invalid width", Error::InvalidWidth.to_string());
+        assert_eq!("unbalanced parentheses", Error::Unbalanced.to_string());
+        assert_eq!("value too large for element width",
+                   Error::Overflow.to_string());
+    }
+}
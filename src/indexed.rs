@@ -0,0 +1,237 @@
+//! Bit vectors with both rank and select support built in.
+//!
+//! Most uses of rank also want select, and the reverse, so an
+//! [`IndexedBitVector`](struct.IndexedBitVector.html) owns its bits along
+//! with [rank](../struct.JacobsonRank.html) tables and a
+//! [select](../select/struct.SampledSelect.html) directory over them,
+//! built together by a single call.
+
+use bit_vec::{BitVec, BitVector};
+use internal::search::binary_search_function;
+use rank::{BitRankSupport, JacobsonRank, RankSupport};
+use select::{SampledSelect, Select0Support, Select1Support, SelectSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A bit vector with constant-time rank and sampled select.
+///
+/// Select for 1s jumps to the nearest sampled 1 and scans forward, as in
+/// `SampledSelect`; select for 0s binary searches the rank tables. The
+/// sample rate trades the space of the directory against the length of
+/// the scan.
+///
+/// Construct with `IndexedBitVector::build`.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::{BitRankSupport, BitVector, IndexedBitVector, Select1Support};
+///
+/// let bits = BitVector::<u64>::every_nth(1000, 10);
+/// let indexed = IndexedBitVector::build(bits, IndexedBitVector::<u64>::DEFAULT_SAMPLE_RATE);
+/// assert_eq!(100, indexed.total_ones());
+/// assert_eq!(Some(500), indexed.select1(50));
+/// assert_eq!(51, indexed.rank1(500));
+/// ```
+#[derive(Clone, Debug)]
+pub struct IndexedBitVector<Block: BlockType = u64> {
+    index: SampledSelect<JacobsonRank<BitVector<Block>>>,
+}
+
+impl<Block: BlockType> IndexedBitVector<Block> {
+    /// A sample rate that keeps the select directory small next to the
+    /// rank tables.
+    pub const DEFAULT_SAMPLE_RATE: usize = 512;
+
+    /// Builds rank and select support over `bits`, sampling the position
+    /// of every `sample_rate`th 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is 0.
+    pub fn build(bits: BitVector<Block>, sample_rate: usize) -> Self {
+        IndexedBitVector {
+            index: SampledSelect::new(JacobsonRank::new(bits), sample_rate),
+        }
+    }
+
+    /// The number of 1s per select sample.
+    pub fn sample_rate(&self) -> usize {
+        self.index.sample_rate()
+    }
+
+    /// Borrows a reference to the underlying bits.
+    pub fn inner(&self) -> &BitVector<Block> {
+        self.index.inner().inner()
+    }
+
+    /// Returns the underlying bits.
+    pub fn into_inner(self) -> BitVector<Block> {
+        self.index.into_inner().into_inner()
+    }
+
+    #[inline]
+    fn rank_support(&self) -> &JacobsonRank<BitVector<Block>> {
+        self.index.inner()
+    }
+}
+
+impl<Block: BlockType> BitVec for IndexedBitVector<Block> {
+    impl_bit_vec_adapter!(Block, index);
+}
+
+impl<Block: BlockType> RankSupport for IndexedBitVector<Block> {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        self.rank_support().rank(position, value)
+    }
+
+    fn limit(&self) -> u64 {
+        self.rank_support().limit()
+    }
+}
+
+impl<Block: BlockType> BitRankSupport for IndexedBitVector<Block> {
+    fn rank1(&self, position: u64) -> u64 {
+        self.rank_support().rank1(position)
+    }
+
+    fn total_ones(&self) -> u64 {
+        self.rank_support().total_ones()
+    }
+
+    fn rank1_batch(&self, positions: &[u64]) -> Vec<u64> {
+        self.rank_support().rank1_batch(positions)
+    }
+}
+
+impl<Block: BlockType> Select1Support for IndexedBitVector<Block> {
+    impl_select1_support_adapter!(index);
+}
+
+impl<Block: BlockType> Select0Support for IndexedBitVector<Block> {
+    fn select0(&self, index: u64) -> Option<u64> {
+        if index >= self.total_zeros() { return None; }
+        // The `index`th 0 is at least `index` bits in.
+        binary_search_function(index, self.limit(), index + 1,
+                               |i| self.rank0(i))
+    }
+}
+
+impl<Block: BlockType> SelectSupport for IndexedBitVector<Block> {
+    type Over = bool;
+
+    fn select(&self, index: u64, value: bool) -> Option<u64> {
+        if value {self.select1(index)} else {self.select0(index)}
+    }
+}
+
+impl<Block: BlockType> SpaceUsage for IndexedBitVector<Block> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.index.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVecPush;
+    use internal::test_util::random_bits;
+    use quickcheck::quickcheck;
+
+    fn check<Block: BlockType>(bits: BitVector<Block>, sample_rate: usize) {
+        let expected = JacobsonRank::new(bits.clone());
+        let ones: Vec<u64> = (0 .. bits.bit_len()).filter(|&i| bits.get_bit(i)).collect();
+        let zeros: Vec<u64> = (0 .. bits.bit_len()).filter(|&i| !bits.get_bit(i)).collect();
+        let indexed = IndexedBitVector::build(bits, sample_rate);
+
+        assert_eq!(sample_rate, indexed.sample_rate());
+        assert_eq!(ones.len() as u64, indexed.total_ones());
+        for i in 0 .. indexed.bit_len() {
+            assert_eq!(expected.rank1(i), indexed.rank1(i));
+            assert_eq!(expected.rank0(i), indexed.rank0(i));
+        }
+        for (index, &position) in ones.iter().enumerate() {
+            assert_eq!(Some(position), indexed.select1(index as u64));
+            assert_eq!(Some(position), indexed.select(index as u64, true));
+        }
+        for (index, &position) in zeros.iter().enumerate() {
+            assert_eq!(Some(position), indexed.select0(index as u64));
+            assert_eq!(Some(position), indexed.select(index as u64, false));
+        }
+        assert_eq!(None, indexed.select1(ones.len() as u64));
+        assert_eq!(None, indexed.select0(zeros.len() as u64));
+        assert_eq!(None, indexed.select0(u64::MAX));
+    }
+
+    #[test]
+    fn random() {
+        for &density in &[0, 1, 30, 128, 250, 256] {
+            check(random_bits::<u64>(3000, density, density), 8);
+            check(random_bits::<u8>(1001, density, density + 1), 3);
+        }
+        check(random_bits::<u32>(5000, 100, 7), IndexedBitVector::<u32>::DEFAULT_SAMPLE_RATE);
+    }
+
+    #[test]
+    fn empty() {
+        let indexed = IndexedBitVector::build(BitVector::<u64>::new(), 4);
+        assert_eq!(0, indexed.bit_len());
+        assert_eq!(0, indexed.total_ones());
+        assert_eq!(None, indexed.select1(0));
+        assert_eq!(None, indexed.select0(0));
+        assert_eq!(None, indexed.rank_checked(0, true));
+    }
+
+    #[test]
+    fn space() {
+        let bits = random_bits::<u64>(1 << 20, 128, 3);
+        let bits_bytes = bits.heap_bytes();
+        let indexed = IndexedBitVector::build(bits, IndexedBitVector::<u64>::DEFAULT_SAMPLE_RATE);
+
+        // The rank tables and the select directory together take less
+        // than half again the space of the bits.
+        assert!(indexed.heap_bytes() > bits_bytes);
+        assert!(indexed.heap_bytes() < bits_bytes * 3 / 2);
+        assert_eq!(bits_bytes, indexed.inner().heap_bytes());
+
+        // A denser directory takes more space.
+        let dense = IndexedBitVector::build(indexed.clone().into_inner(), 8);
+        assert!(dense.heap_bytes() > indexed.heap_bytes());
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_sample_rate() {
+        let mut bits = BitVector::<u64>::new();
+        bits.push_bit(true);
+        IndexedBitVector::build(bits, 0);
+    }
+
+    // Rank and select are inverses: the `rank1(p) - 1`th 1 is at `p`
+    // whenever bit `p` is set, and the `k`th 1 has rank `k + 1`.
+    fn inverse_prop(bits: Vec<bool>, sample_rate: u8) -> bool {
+        let indexed = IndexedBitVector::build(BitVector::<u8>::from_bits(&bits),
+                                              sample_rate as usize % 9 + 1);
+        (0 .. indexed.bit_len()).all(|i| {
+            if indexed.get_bit(i) {
+                indexed.select1(indexed.rank1(i) - 1) == Some(i)
+            } else {
+                indexed.select0(indexed.rank0(i) - 1) == Some(i)
+            }
+        }) && (0 .. indexed.total_ones()).all(|k| {
+            indexed.select1(k).map(|p| indexed.rank1(p)) == Some(k + 1)
+        }) && (0 .. indexed.total_zeros()).all(|k| {
+            indexed.select0(k).map(|p| indexed.rank0(p)) == Some(k + 1)
+        })
+    }
+
+    #[test]
+    fn inverse_qc() {
+        quickcheck(inverse_prop as fn(Vec<bool>, u8) -> bool);
+    }
+}
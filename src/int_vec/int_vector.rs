@@ -2,6 +2,12 @@ use std::fmt;
 
 use super::*;
 use bit_vec::{BitVec, BitVecMut};
+use error;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use serde::de::Error;
+
 use internal::vector_base::{VectorBase, self};
 use space_usage::SpaceUsage;
 use storage::BlockType;
@@ -75,6 +81,16 @@ impl<Block: BlockType> IntVector<Block> {
         Self::create(element_bits, VectorBase::new())
     }
 
+    /// Creates a new integer vector, or fails with
+    /// `Error::InvalidWidth` unless `1 <= element_bits <= Block::nbits()`.
+    pub fn try_new(element_bits: usize) -> error::Result<Self> {
+        if element_bits == 0 || element_bits > Block::nbits() {
+            Err(error::Error::InvalidWidth)
+        } else {
+            Ok(Self::new(element_bits))
+        }
+    }
+
     /// Creates a new, empty integer vector, allocating sufficient storage
     /// for `capacity` elements.
     pub fn with_capacity(element_bits: usize, capacity: u64) -> Self {
@@ -146,8 +162,8 @@ impl<Block: BlockType> IntVector<Block> {
     ///     considered to the end of the support array, even if that goes
     ///     past the last element of the `IntArray`.
     ///
-    ///   - Debug mode only: Panics if `element_value` is too large to
-    ///     fit in the element size. (TODO: What’s the right thing here?)
+    ///   - Panics if `element_value` is too large to fit in the
+    ///     element size.
     pub fn set_random(&mut self, bit_offset: u64, element_bits: usize,
                       element_index: u64, element_value: Block) {
         Self::check_value_random(element_bits, element_value);
@@ -166,6 +182,35 @@ impl<Block: BlockType> IntVector<Block> {
         self.base.push_bits(self.element_bits, element_value);
     }
 
+    /// Sets the element at `element_index` to the low `element_bits`
+    /// bits of `element_value`, discarding the rest.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `element_index` is out of bounds.
+    pub fn set_wrapping(&mut self, element_index: u64, element_value: Block) {
+        let mask = Block::low_mask(self.element_bits);
+        self.set(element_index, element_value & mask);
+    }
+
+    /// Sets the element at `element_index` to `element_value`, or fails
+    /// without changing anything.
+    ///
+    /// Fails with `Error::OutOfBounds` if `element_index` is out of
+    /// bounds, or with `Error::Overflow` if `element_value` doesn’t fit
+    /// in `element_bits` bits.
+    pub fn set_checked(&mut self, element_index: u64, element_value: Block)
+                       -> error::Result<()> {
+        if element_index >= self.len() {
+            Err(error::Error::OutOfBounds)
+        } else if element_value > Block::low_mask(self.element_bits) {
+            Err(error::Error::Overflow)
+        } else {
+            self.set(element_index, element_value);
+            Ok(())
+        }
+    }
+
     /// Removes and returns the last element of the vector, if present.
     pub fn pop(&mut self) -> Option<Block> {
         self.base.pop_bits(self.element_bits)
@@ -317,6 +362,14 @@ impl<Block: BlockType> IntVec for IntVector<Block> {
 }
 
 impl<Block: BlockType> IntVecMut for IntVector<Block> {
+    /// Updates the value of the `element_index`th element.
+    ///
+    /// # Panics
+    ///
+    /// Panics, in release builds too, if `element_index` is out of
+    /// bounds or `element_value` doesn’t fit in `element_bits` bits.
+    /// Use `set_wrapping` to truncate the value instead, or
+    /// `set_checked` to get an error.
     fn set(&mut self, element_index: u64, element_value: Block) {
         if self.is_block_sized() {
             self.base.set_block(self.element_bits,
@@ -421,6 +474,37 @@ impl<Block> fmt::Debug for IntVector<Block>
     }
 }
 
+/// Serializes as the triple `(element_bits, len, blocks)`.
+#[cfg(feature = "serde")]
+impl<Block: BlockType + Serialize> Serialize for IntVector<Block> {
+    fn serialize<S: Serializer>(&self, serializer: S)
+                                -> Result<S::Ok, S::Error> {
+        (self.element_bits, self.base.len(), self.base.blocks())
+            .serialize(serializer)
+    }
+}
+
+/// Rejects bad element sizes, data whose block count doesn’t match its
+/// length, and data whose last block has bits set past the end.
+#[cfg(feature = "serde")]
+impl<'de, Block> Deserialize<'de> for IntVector<Block>
+        where Block: BlockType + Deserialize<'de> {
+
+    fn deserialize<D: Deserializer<'de>>(deserializer: D)
+                                         -> Result<Self, D::Error> {
+        let (element_bits, len, blocks) =
+            <(usize, u64, Vec<Block>)>::deserialize(deserializer)?;
+
+        if element_bits == 0 || element_bits > Block::nbits() {
+            return Err(D::Error::custom("IntVector: bad element size"));
+        }
+
+        VectorBase::from_parts(element_bits, len, blocks)
+            .map(|base| IntVector { element_bits, base })
+            .ok_or_else(|| D::Error::custom("IntVector: inconsistent length"))
+    }
+}
+
 impl<A: BlockType> SpaceUsage for IntVector<A> {
     #[inline]
     fn is_stack_only() -> bool { false }
@@ -435,6 +519,57 @@ impl<A: BlockType> SpaceUsage for IntVector<A> {
 mod test {
     use int_vec::{IntVector, IntVec, IntVecMut};
     use bit_vec::*;
+    use internal::test_util::Lcg;
+    use quickcheck::quickcheck;
+    use storage::BlockType;
+
+    #[test]
+    fn try_new() {
+        use error::Error;
+
+        assert!(IntVector::<u32>::try_new(1).is_ok());
+        assert!(IntVector::<u32>::try_new(32).is_ok());
+        assert_eq!(Some(Error::InvalidWidth), IntVector::<u32>::try_new(0).err());
+        assert_eq!(Some(Error::InvalidWidth), IntVector::<u32>::try_new(33).err());
+    }
+
+    // Small values, the same ones every time.
+    fn small_values(len: u64, element_bits: usize) -> IntVector<u32> {
+        let mut lcg = Lcg::new(3);
+        let mut result = IntVector::with_capacity(element_bits, len);
+        for _ in 0 .. len {
+            result.push((lcg.next_u64() >> 40) as u32 % 5);
+        }
+        result
+    }
+
+    #[test]
+    fn rank_select_value() {
+        let v = small_values(500, 3);
+        let values: Vec<u32> = v.iter().collect();
+
+        for value in 0 .. 8 {
+            for up_to in 0 .. v.len() + 1 {
+                let expected = values[.. up_to as usize].iter()
+                    .filter(|&&x| x == value).count() as u64;
+                assert_eq!(expected, v.rank_value(value, up_to));
+            }
+
+            let positions: Vec<u64> = (0 .. v.len())
+                .filter(|&i| values[i as usize] == value)
+                .collect();
+            for (index, &position) in positions.iter().enumerate() {
+                assert_eq!(Some(position), v.select_value(value, index as u64));
+            }
+            assert_eq!(None, v.select_value(value, positions.len() as u64));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rank_value_oob() {
+        small_values(10, 3).rank_value(0, 11);
+    }
 
     #[test]
     fn create_empty() {
@@ -535,6 +670,44 @@ mod test {
         assert_eq!(0, v.get(20));
     }
 
+    fn push_get_prop(element_bits: usize, values: Vec<u64>) -> bool {
+        let mask = (1 << element_bits) - 1;
+        let values: Vec<u64> = values.into_iter().map(|v| v & mask).collect();
+
+        let mut v = IntVector::<u64>::new(element_bits);
+        for &value in &values {
+            v.push(value);
+        }
+
+        v.len() == values.len() as u64
+            && values.iter().enumerate().all(|(i, &value)| v.get(i as u64) == value)
+            && v.iter().eq(values.iter().cloned())
+    }
+
+    fn set_get_prop(element_bits: usize, values: Vec<u64>) -> bool {
+        let mask = (1 << element_bits) - 1;
+        let mut v = IntVector::<u32>::with_fill(element_bits, values.len() as u64, 0);
+
+        for (i, &value) in values.iter().enumerate().rev() {
+            v.set(i as u64, (value & mask) as u32);
+        }
+
+        values.iter().enumerate()
+              .all(|(i, &value)| v.get(i as u64) as u64 == value & mask)
+    }
+
+    #[test]
+    fn random_5_bit() {
+        quickcheck((|values| push_get_prop(5, values)) as fn(Vec<u64>) -> bool);
+        quickcheck((|values| set_get_prop(5, values)) as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn random_17_bit() {
+        quickcheck((|values| push_get_prop(17, values)) as fn(Vec<u64>) -> bool);
+        quickcheck((|values| set_get_prop(17, values)) as fn(Vec<u64>) -> bool);
+    }
+
     #[test]
     fn pop() {
         let mut v = IntVector::<u32>::new(7);
@@ -577,6 +750,64 @@ mod test {
                    format!("{:?}", v));
     }
 
+    fn neighbors(element_bits: usize) -> IntVector<u32> {
+        let mut v = IntVector::new(element_bits);
+        let all = u32::low_mask(element_bits);
+        for i in 0 .. 9 { v.push(if i % 2 == 0 { all } else { 0 }); }
+        v
+    }
+
+    fn assert_neighbors(v: &IntVector<u32>, except: u64) {
+        let all = u32::low_mask(v.element_bits());
+        for i in (0 .. v.len()).filter(|&i| i != except) {
+            assert_eq!(if i % 2 == 0 { all } else { 0 }, v.get(i));
+        }
+    }
+
+    #[test]
+    fn set_wrapping() {
+        for &element_bits in &[3, 7, 8, 13, 32] {
+            let mut v = neighbors(element_bits);
+            v.set_wrapping(3, !0);
+            assert_eq!(u32::low_mask(element_bits), v.get(3));
+            assert_neighbors(&v, 3);
+
+            if element_bits < 32 {
+                v.set_wrapping(3, 6 | 1 << element_bits);
+                assert_eq!(6, v.get(3));
+                assert_neighbors(&v, 3);
+            }
+        }
+    }
+
+    #[test]
+    fn set_checked() {
+        use error::Error;
+
+        let mut v = neighbors(5);
+        assert_eq!(Err(Error::Overflow), v.set_checked(3, 32));
+        assert_eq!(Err(Error::Overflow), v.set_checked(4, !0));
+        assert_neighbors(&v, 9);
+
+        assert_eq!(Ok(()), v.set_checked(3, 31));
+        assert_eq!(31, v.get(3));
+        assert_eq!(Ok(()), v.set_checked(4, 17));
+        assert_eq!(17, v.get(4));
+        assert_eq!(Err(Error::OutOfBounds), v.set_checked(9, 0));
+        assert_eq!(0, v.get(5));
+
+        let mut wide = neighbors(32);
+        assert_eq!(Ok(()), wide.set_checked(1, !0));
+        assert_eq!(!0, wide.get(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_overflow() {
+        let mut v = neighbors(4);
+        v.set(2, 16);
+    }
+
     #[test]
     #[should_panic]
     fn value_overflow() {
@@ -623,6 +854,40 @@ mod test {
         assert!( v == u );
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use bincode;
+        use serde_json;
+
+        let mut v = IntVector::<u32>::new(17);
+        for i in 0 .. 50 {
+            v.push(i * 2017 % 131072);
+        }
+
+        let json = serde_json::to_string(&v).unwrap();
+        assert_eq!(v, serde_json::from_str(&json).unwrap());
+
+        let binary = bincode::serialize(&v).unwrap();
+        assert_eq!(v, bincode::deserialize(&binary).unwrap());
+        assert!(bincode::deserialize::<IntVector<u32>>(&binary[.. 20])
+                    .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_bad_data() {
+        use serde_json;
+
+        type IV = IntVector<u8>;
+
+        assert!(serde_json::from_str::<IV>("[5,3,[90,41]]").is_ok());
+        assert!(serde_json::from_str::<IV>("[5,3,[90]]").is_err());
+        assert!(serde_json::from_str::<IV>("[5,3,[90,169]]").is_err());
+        assert!(serde_json::from_str::<IV>("[0,0,[]]").is_err());
+        assert!(serde_json::from_str::<IV>("[9,1,[0,0]]").is_err());
+    }
+
     #[test]
     fn block_size_elements_u16() {
         let mut v = IntVector::<u16>::new(16);
@@ -23,6 +23,29 @@ pub trait IntVec {
     ///
     /// Panics if `index` is out of bounds.
     fn get(&self, index: u64) -> Self::Block;
+
+    /// The number of the first `up_to` elements that equal `value`.
+    ///
+    /// The default implementation scans the elements, taking
+    /// *O*(`up_to`) time and no extra space. For many queries over a
+    /// fixed vector of small values, a `WaveletTree` answers each in
+    /// time proportional to the value width instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `up_to > self.len()`.
+    fn rank_value(&self, value: Self::Block, up_to: u64) -> u64 {
+        assert!(up_to <= self.len(), "IntVec::rank_value: out of bounds");
+        (0 .. up_to).filter(|&i| self.get(i) == value).count() as u64
+    }
+
+    /// The position of the `index`th element (zero-based) that equals
+    /// `value`, or `None` if there are not that many.
+    ///
+    /// Like `rank_value`, the default implementation is a linear scan.
+    fn select_value(&self, value: Self::Block, index: u64) -> Option<u64> {
+        (0 .. self.len()).filter(|&i| self.get(i) == value).nth(index as usize)
+    }
 }
 
 /// A mutable array of integers of limited width.
@@ -33,7 +56,8 @@ pub trait IntVecMut: IntVec {
     ///
     ///   - Panics if `index` is out of bounds.
     ///
-    ///   - May panic (?) if `element_value` is too large to
-    ///     fit in the element size. (TODO: What’s the right thing here?)
+    ///   - May panic if `value` is too large to fit in the element
+    ///     size. Implementations must never let such a value change
+    ///     neighboring elements.
     fn set(&mut self, index: u64, value: Self::Block);
 }
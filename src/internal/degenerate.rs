@@ -0,0 +1,162 @@
+// Behavior on empty and all-0 inputs, across the structures that accept
+// them: lengths and counts are 0, every select is `None`, checked rank
+// is `None`, and empty ranges and slices are fine.
+
+use bit_vec::*;
+use elias_fano::EliasFano;
+use partitioned::PartitionedBitVector;
+use rank::*;
+use rrr::RrrVector;
+use select::*;
+use sparse::SparseBitVector;
+use storage::BlockType;
+
+fn empty() -> BitVector<u64> {
+    BitVector::new()
+}
+
+fn zeros() -> BitVector<u64> {
+    BitVector::with_fill(200, false)
+}
+
+// The rank operations that are defined on an empty vector.
+fn check_empty_rank<Rank: BitRankSupport + ?Sized>(rank: &Rank) {
+    assert_eq!(0, rank.limit());
+    assert_eq!(0, rank.total_ones());
+    assert_eq!(0, rank.total_zeros());
+    assert_eq!(None, rank.rank_checked(0, true));
+    assert_eq!(None, rank.rank_checked(0, false));
+    assert_eq!(None, rank.rank1_checked(0));
+    assert_eq!(0, rank.rank_range(0 .. 0));
+    assert_eq!(0, rank.rank0_range(0 .. 0));
+    assert!(rank.rank1_batch(&[]).is_empty());
+}
+
+fn check_all_zeros_rank<Rank: BitRankSupport + ?Sized>(rank: &Rank) {
+    let len = rank.limit();
+    assert_eq!(0, rank.total_ones());
+    assert_eq!(len, rank.total_zeros());
+    for i in 0 .. len {
+        assert_eq!(0, rank.rank1(i));
+        assert_eq!(i + 1, rank.rank0(i));
+    }
+    assert_eq!(0, rank.rank_range(0 .. len));
+    assert_eq!(None, rank.rank_checked(len, true));
+}
+
+fn check_no_ones<Select: Select1Support + ?Sized>(select: &Select) {
+    assert_eq!(None, select.select1(0));
+    assert_eq!(None, select.select1(1));
+    assert_eq!(None, select.select1(u64::MAX));
+    assert_eq!(vec![None, None], select.select1_batch(&[1, 0]));
+    // No hint is valid, but none can produce an answer either.
+    assert_eq!(None, select.select1_with_hint(0, (0, 0)));
+}
+
+#[test]
+fn bit_vec() {
+    let bits = empty();
+    assert_eq!(0, bits.bit_len());
+    assert_eq!(0, bits.block_len());
+    assert_eq!(0, bits.byte_len());
+    assert_eq!(None, bits.try_get_block(0));
+    assert_eq!(0, bits.bits().count());
+    assert_eq!(0, bits.blocks().count());
+    assert_eq!(0, bits.ones().count());
+    assert_eq!(0, bits.zeros().count());
+    assert_eq!(0, bits.runs().count());
+    assert_eq!(0, bits.popcount_windows(1).count());
+    assert_eq!(0, bits.hamming_distance(&empty()));
+    assert!(bits.bits_eq(&empty()));
+    assert!(bits.is_subset(&empty()));
+    assert!(!bits.intersects(&empty()));
+    assert_eq!(0, bits.count_trailing_zeros());
+    assert_eq!(0, bits.count_leading_ones());
+    assert_eq!(0, bits.diff(&empty()).bit_len());
+    assert_eq!("", format!("{}", bits.display()));
+    assert!(bits.to_bytes().is_empty());
+    assert_eq!(0, u64::count_ones_slice(bits.as_blocks()));
+
+    let blocks: &[u32] = &[];
+    assert_eq!(0, blocks.bit_len());
+    assert_eq!(0, blocks.ones().count());
+}
+
+#[test]
+fn slices() {
+    let bits = empty();
+    let slice = BitSlice::new(&bits, 0 .. 0);
+    assert_eq!(0, slice.bit_len());
+    assert_eq!(0, slice.block_len());
+    assert_eq!(0, slice.count_ones());
+    assert_eq!(0, slice.slice(..).bit_len());
+
+    let full = BitVector::<u64>::with_fill(100, true);
+    for &start in &[0, 64, 99, 100] {
+        let slice = BitSlice::new(&full, start .. start);
+        assert_eq!(0, slice.bit_len());
+        assert_eq!(0, slice.count_ones());
+        assert_eq!(0, JacobsonRank::new(slice).total_ones());
+    }
+
+    let mut data = empty();
+    let mut slice = BitSliceMut::new(&mut data, 0 .. 0);
+    assert_eq!(0, slice.bit_len());
+    slice.set_range(0 .. 0, true);
+}
+
+#[test]
+fn rank() {
+    check_empty_rank(&JacobsonRank::new(empty()));
+    check_empty_rank(&JacobsonRank::new(&[0u32; 0][..]));
+    check_empty_rank(&JacobsonRank::from_blocks(Vec::<u64>::new(), 0, empty()));
+    check_empty_rank(&Rank9::new(empty()));
+    check_empty_rank(&InterleavedRank::new(&empty()));
+    check_empty_rank(&RrrVector::new(&empty(), 15));
+    check_empty_rank(&SparseBitVector::new(&empty()));
+    check_empty_rank(&PartitionedBitVector::new(&empty(), 4));
+
+    check_all_zeros_rank(&JacobsonRank::new(zeros()));
+    check_all_zeros_rank(&Rank9::new(zeros()));
+    check_all_zeros_rank(&InterleavedRank::new(&zeros()));
+    check_all_zeros_rank(&RrrVector::new(&zeros(), 15));
+    check_all_zeros_rank(&SparseBitVector::new(&zeros()));
+    check_all_zeros_rank(&PartitionedBitVector::new(&zeros(), 1));
+}
+
+#[test]
+#[should_panic]
+fn rank1_empty() {
+    JacobsonRank::new(empty()).rank1(0);
+}
+
+#[test]
+fn select() {
+    check_no_ones(&BinSearchSelect::new(JacobsonRank::new(empty())));
+    check_no_ones(&SampledSelect::new(empty(), 4));
+    check_no_ones(&SparseBitVector::new(&empty()));
+
+    check_no_ones(&BinSearchSelect::new(JacobsonRank::new(zeros())));
+    check_no_ones(&SampledSelect::new(zeros(), 4));
+    check_no_ones(&SparseBitVector::new(&zeros()));
+
+    assert_eq!(None, BinSearchSelect::new(JacobsonRank::new(empty())).select0(0));
+    assert_eq!(None, SampledSelect0::new(empty(), 4).select0(0));
+    assert_eq!(None, SparseBitVector::new(&empty()).select0(0));
+    assert_eq!(None, BinSearchSelect::new(JacobsonRank::new(empty())).select(0, true));
+
+    // All 0s do have 0s to select.
+    assert_eq!(Some(199), SampledSelect0::new(zeros(), 4).select0(199));
+    assert_eq!(None, SampledSelect0::new(zeros(), 4).select0(200));
+    assert_eq!(None, SampledSelect0::new(zeros(), 4).select0(u64::MAX));
+    assert_eq!(None, BinSearchSelect::new(JacobsonRank::new(zeros())).select0(u64::MAX));
+    assert_eq!(None, SparseBitVector::new(&zeros()).select0(u64::MAX));
+}
+
+#[test]
+fn sequences() {
+    let elias_fano = EliasFano::new(&[]);
+    assert_eq!(0, elias_fano.len());
+    assert_eq!(0, elias_fano.rank(0));
+    assert_eq!(None, elias_fano.successor(0));
+}
@@ -1,3 +1,8 @@
 pub mod errors;
 pub mod search;
 pub mod vector_base;
+
+#[cfg(test)]
+mod degenerate;
+#[cfg(test)]
+pub mod test_util;
@@ -0,0 +1,52 @@
+// Pseudo-random inputs for the unit tests. Everything is drawn from a
+// fixed linear congruential generator, so each seed always gives the
+// same input and failures can be reproduced.
+
+use bit_vec::BitVector;
+use storage::BlockType;
+
+/// A linear congruential generator with Knuth’s MMIX constants.
+pub struct Lcg(u64);
+
+impl Lcg {
+    /// Starts the generator at `seed`.
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    /// Advances the generator and returns its new state. The high bits
+    /// are the most random; use those.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005)
+                       .wrapping_add(1442695040888963407);
+        self.0
+    }
+
+    /// True with probability `density / 256`.
+    pub fn next_bit(&mut self, density: u64) -> bool {
+        (self.next_u64() >> 56) < density
+    }
+}
+
+/// `len` bits, each set with probability `density / 256`.
+pub fn random_bits<Block: BlockType>(len: u64, density: u64, seed: u64)
+                                     -> BitVector<Block> {
+    let mut lcg = Lcg::new(seed);
+    BitVector::from_fn(len, |_| lcg.next_bit(density))
+}
+
+/// `len` bools, each true with probability `density / 256`.
+pub fn random_bools(len: usize, density: u64, seed: u64) -> Vec<bool> {
+    let mut lcg = Lcg::new(seed);
+    (0 .. len).map(|_| lcg.next_bit(density)).collect()
+}
+
+/// A sorted sequence of `len` values with gaps averaging about `gap`.
+pub fn sorted_values(len: usize, gap: u64, seed: u64) -> Vec<u64> {
+    let mut lcg = Lcg::new(seed);
+    let mut current = 0;
+    (0 .. len).map(|_| {
+        current += (lcg.next_u64() >> 33) % (2 * gap + 1);
+        current
+    }).collect()
+}
@@ -100,6 +100,42 @@ impl<Block: BlockType> VectorBase<Block> {
         result
     }
 
+    // Reassembles a vector from its length and blocks, as produced by
+    // `blocks`. Returns `None` unless there are exactly enough blocks for
+    // `len` elements and the unused bits of the last block are zero.
+    pub fn from_parts(element_bits: usize, len: u64, vec: Vec<Block>)
+                      -> Option<Self> {
+        if len_to_block_len::<Block>(element_bits, len) != Some(vec.len()) {
+            return None;
+        }
+
+        let mut result = VectorBase { len, vec };
+        let last_block = result.vec.last().cloned();
+        result.clear_extra_bits(element_bits);
+
+        if result.vec.last().cloned() == last_block {
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn blocks(&self) -> &[Block] {
+        &self.vec
+    }
+
+    #[inline]
+    pub fn into_vec(self) -> Vec<Block> {
+        self.vec
+    }
+
+    // Callers must leave the bits past `len` zero.
+    #[inline]
+    pub fn blocks_mut(&mut self) -> &mut [Block] {
+        &mut self.vec
+    }
+
     #[inline]
     pub fn get_block(&self, block_index: usize) -> Block {
         self.vec[block_index]
@@ -760,6 +796,20 @@ mod test {
         assert_eq!(0b00011010, v.get_block(0));
     }
 
+    #[test]
+    fn from_parts() {
+        let v = VB::from_parts(5, 3, vec![ 0b01011010, 0b00101001 ]).unwrap();
+        assert_eq!(3, v.len());
+        assert_eq!(0b01010, v.get_bits(5, 10, 5));
+        assert_eq!(&[ 0b01011010, 0b00101001 ], v.blocks());
+
+        assert_eq!(Some(VB::new()), VB::from_parts(5, 0, vec![]));
+        assert_eq!(None, VB::from_parts(5, 3, vec![ 0b01011010 ]));
+        assert_eq!(None, VB::from_parts(5, 3, vec![ 0, 0, 0 ]));
+        assert_eq!(None, VB::from_parts(5, 3, vec![ 0, 0b10000000 ]));
+        assert_eq!(None, VB::from_parts(5, !0, vec![]));
+    }
+
     #[test] #[should_panic]
     fn with_capacity_overflow() {
         VB::with_capacity(5, !0);
@@ -7,9 +7,31 @@
 //!   - [integer vectors](int_vector/struct.IntVec.html) with arbitrary-sized
 //!     (1- to 64-bit) elements;
 //!   - a variety of [universal codes](coding/index.html;
-//!   - constant-time [rank](struct.JacobsonRank.html) queries; and
+//!   - constant-time [rank](struct.JacobsonRank.html) queries;
 //!   - *O*(lg lg *n*)-time [select](struct.BinSearchSelect.html) queries
-//!     based on binary search over ranks.
+//!     based on binary search over ranks;
+//!   - [RRR-compressed](rrr/struct.RrrVector.html) bit vectors with rank
+//!     support;
+//!   - [Elias–Fano](elias_fano/struct.EliasFano.html) encoded sorted
+//!     sequences;
+//!   - [delta-encoded](delta/struct.DeltaVector.html) sorted sequences;
+//!   - [variable-byte](vbyte/struct.VByteVector.html) encoded integer
+//!     sequences;
+//!   - [sparse](sparse/struct.SparseBitVector.html) bit vectors that
+//!     store only the positions of their 1s;
+//!   - [balanced parentheses](bp/struct.BalancedParens.html) with
+//!     matching and enclosing-pair navigation;
+//!   - [LOUDS](louds/struct.Louds.html) ordinal trees;
+//!   - [indexed](indexed/struct.IndexedBitVector.html) bit vectors that
+//!     build rank and select support together;
+//!   - [partitioned](partitioned/struct.PartitionedBitVector.html) bit
+//!     vectors with per-shard rank support, for concurrent reads;
+//!   - [2-bit symbol](quad/struct.QuadVector.html) sequences with
+//!     single-query rank and select for each symbol;
+//!   - [permutations](permutation/struct.Permutation.html) with fast
+//!     inverses; and
+//!   - [wavelet trees](wavelet/struct.WaveletTree.html) over byte
+//!     sequences.
 //!
 //! # Usage
 //!
@@ -21,6 +43,13 @@
 //! ```
 //!
 //! to your `Cargo.toml`.
+//!
+//! Enable the `serde` feature to get `Serialize` and `Deserialize`
+//! implementations for `BitVector` and `IntVector`, and the `rayon`
+//! feature to get `JacobsonRank::from_bits_parallel`. The `memmap`
+//! feature adds `MmapBitVector`, a read-only bit vector mapped from a
+//! file written by `BitVector::write_to`, and the `bitvec-compat`
+//! feature adds `BitvecBits`, a view of a `bitvec` crate `BitSlice`.
 
 #![doc(html_root_url = "https://docs.rs/succinct/0.5.2")]
 #![warn(missing_docs)]
@@ -28,9 +57,27 @@
 extern crate byteorder;
 extern crate num_traits;
 
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+#[cfg(feature = "memmap")]
+extern crate memmap2;
+
+#[cfg(feature = "bitvec-compat")]
+extern crate bitvec;
+
 #[cfg(test)]
 extern crate quickcheck;
 
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
 #[macro_use]
 mod macros;
 
@@ -41,11 +88,15 @@ pub mod coding;
 pub mod storage;
 pub mod stream;
 
+pub mod error;
+pub use error::Error;
+
 mod space_usage;
 pub use space_usage::SpaceUsage;
 
 pub mod bit_vec;
-pub use bit_vec::{BitVec, BitVecMut, BitVecPush, BitVector};
+pub use bit_vec::{BitOrder, BitVec, BitVecBuilder, BitVecExt, BitVecMut, BitVecOps,
+                  BitVecPush, BitVector};
 
 pub mod int_vec;
 pub use int_vec::{IntVec, IntVecMut, IntVector};
@@ -56,3 +107,39 @@ pub use rank::{BitRankSupport, JacobsonRank, Rank9};
 pub mod select;
 pub use select::{Select1Support, BinSearchSelect};
 
+pub mod rrr;
+pub use rrr::RrrVector;
+
+pub mod elias_fano;
+pub use elias_fano::EliasFano;
+
+pub mod delta;
+pub use delta::DeltaVector;
+
+pub mod vbyte;
+pub use vbyte::VByteVector;
+
+pub mod sparse;
+pub use sparse::SparseBitVector;
+
+pub mod bp;
+pub use bp::BalancedParens;
+
+pub mod louds;
+pub use louds::Louds;
+
+pub mod indexed;
+pub use indexed::IndexedBitVector;
+
+pub mod partitioned;
+pub use partitioned::PartitionedBitVector;
+
+pub mod quad;
+pub use quad::QuadVector;
+
+pub mod permutation;
+pub use permutation::Permutation;
+
+pub mod wavelet;
+pub use wavelet::WaveletTree;
+
@@ -0,0 +1,257 @@
+//! Level-order unary degree sequence (LOUDS) trees.
+//!
+//! A [`Louds`](struct.Louds.html) tree writes `10` for a super-root and
+//! then, for each node in breadth-first order, its degree in unary: one 1
+//! per child followed by a 0. A tree of *n* nodes takes 2*n* + 1 bits plus
+//! rank and select support.
+//!
+//! Nodes are identified by the position of the 1 bit that introduces
+//! them, so the root is at position 0. The *i*th node in breadth-first
+//! order (its *ordinal*) is at `select1(i)`.
+
+use bit_vec::{BitVec, BitVecPush, BitVector};
+use rank::{BitRankSupport, JacobsonRank};
+use select::{BinSearchSelect, Select0Support, Select1Support};
+use space_usage::SpaceUsage;
+
+/// An ordinal tree in LOUDS encoding.
+///
+/// Construct with `Louds::from_children`.
+#[derive(Clone, Debug)]
+pub struct Louds {
+    bits: BinSearchSelect<JacobsonRank<BitVector<u64>>>,
+}
+
+impl Louds {
+    /// Encodes the tree whose nodes are `0 .. children.len()`, with root
+    /// 0, where `children[v]` lists the children of `v` in order.
+    ///
+    /// The result numbers nodes in breadth-first order, which differs
+    /// from the input numbering unless the input is already breadth-first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `children` is empty, or if it isn’t a tree rooted at 0:
+    /// some node is unreachable, has two parents, or is out of range.
+    pub fn from_children(children: &[Vec<usize>]) -> Self {
+        assert!(!children.is_empty(), "Louds::from_children: empty tree");
+
+        let mut bits = BitVector::with_capacity(2 * children.len() as u64 + 1);
+        bits.push_bit(true);
+        bits.push_bit(false);
+
+        let mut seen = vec![false; children.len()];
+        let mut queue = vec![0];
+        seen[0] = true;
+        let mut next = 0;
+
+        while next < queue.len() {
+            let node = queue[next];
+            next += 1;
+
+            for &child in &children[node] {
+                assert!(child < children.len() && !seen[child],
+                        "Louds::from_children: not a tree");
+                seen[child] = true;
+                queue.push(child);
+                bits.push_bit(true);
+            }
+            bits.push_bit(false);
+        }
+
+        assert!(queue.len() == children.len(),
+                "Louds::from_children: unreachable nodes");
+
+        Louds {
+            bits: BinSearchSelect::new(JacobsonRank::new(bits)),
+        }
+    }
+
+    /// The number of nodes in the tree.
+    pub fn node_count(&self) -> u64 {
+        (self.bits.bit_len() - 1) / 2
+    }
+
+    /// The root node.
+    pub fn root(&self) -> u64 {
+        0
+    }
+
+    /// The breadth-first ordinal of the node at `node`.
+    pub fn ordinal(&self, node: u64) -> u64 {
+        self.bits.rank1(node) - 1
+    }
+
+    /// Returns the node with the given breadth-first ordinal, or `None`
+    /// if there are not that many nodes.
+    pub fn node(&self, ordinal: u64) -> Option<u64> {
+        self.bits.select1(ordinal)
+    }
+
+    /// The first child of `node`, or `None` if it is a leaf.
+    pub fn first_child(&self, node: u64) -> Option<u64> {
+        let start = self.children_start(node);
+        if self.bits.get_bit(start) { Some(start) } else { None }
+    }
+
+    /// The next sibling of `node`, or `None` if it is the last child of
+    /// its parent (or the root).
+    pub fn next_sibling(&self, node: u64) -> Option<u64> {
+        let next = node + 1;
+        if next < self.bits.bit_len() && self.bits.get_bit(next) {
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    /// The parent of `node`, or `None` if it is the root.
+    pub fn parent(&self, node: u64) -> Option<u64> {
+        // `node`’s 1 bit is in the unary degree of the parent whose
+        // ordinal is one less than the number of 0s before it.
+        match self.bits.rank0(node) {
+            0     => None,
+            zeros => self.bits.select1(zeros - 1),
+        }
+    }
+
+    /// The number of children of `node`.
+    pub fn degree(&self, node: u64) -> u64 {
+        let start = self.children_start(node);
+        let end = self.bits.select0(self.ordinal(node) + 1)
+                      .expect("Louds::degree: missing terminator");
+        end - start
+    }
+
+    // The position of the unary degree of `node`.
+    fn children_start(&self, node: u64) -> u64 {
+        self.bits.select0(self.ordinal(node))
+            .expect("Louds: missing terminator") + 1
+    }
+}
+
+impl BitVec for Louds {
+    impl_bit_vec_adapter!(u64, bits);
+}
+
+impl SpaceUsage for Louds {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.bits.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    //         0
+    //       / | \
+    //      1  2  3
+    //     / \     \
+    //    4   5     6
+    //              |
+    //              7
+    fn example() -> Vec<Vec<usize>> {
+        vec![vec![1, 2, 3], vec![4, 5], vec![], vec![6],
+             vec![], vec![], vec![7], vec![]]
+    }
+
+    fn children_of(louds: &Louds, node: u64) -> Vec<u64> {
+        let mut result = Vec::new();
+        let mut child = louds.first_child(node);
+        while let Some(c) = child {
+            result.push(c);
+            child = louds.next_sibling(c);
+        }
+        result
+    }
+
+    // Rebuilds the adjacency list, in breadth-first numbering.
+    fn decode(louds: &Louds) -> Vec<Vec<usize>> {
+        (0 .. louds.node_count()).map(|ordinal| {
+            let node = louds.node(ordinal).unwrap();
+            children_of(louds, node).into_iter()
+                .map(|child| louds.ordinal(child) as usize)
+                .collect()
+        }).collect()
+    }
+
+    #[test]
+    fn encoding() {
+        let louds = Louds::from_children(&example());
+        let bits: String = (0 .. louds.bit_len())
+            .map(|i| if louds.get_bit(i) { '1' } else { '0' })
+            .collect();
+        assert_eq!("10111011001000100", bits);
+        assert_eq!(8, louds.node_count());
+    }
+
+    #[test]
+    fn navigation() {
+        let tree = example();
+        let louds = Louds::from_children(&tree);
+        assert_eq!(tree, decode(&louds));
+
+        let root = louds.root();
+        assert_eq!(None, louds.parent(root));
+        assert_eq!(None, louds.next_sibling(root));
+        assert_eq!(3, louds.degree(root));
+
+        for ordinal in 0 .. louds.node_count() {
+            let node = louds.node(ordinal).unwrap();
+            assert_eq!(ordinal, louds.ordinal(node));
+
+            let children = children_of(&louds, node);
+            assert_eq!(children.len() as u64, louds.degree(node));
+            assert_eq!(tree[ordinal as usize].is_empty(),
+                       louds.first_child(node).is_none());
+            for child in children {
+                assert_eq!(Some(node), louds.parent(child));
+            }
+        }
+
+        assert_eq!(None, louds.node(8));
+    }
+
+    #[test]
+    fn relabels_breadth_first() {
+        // Input numbered depth-first: 0 -> [1, 3], 1 -> [2].
+        let louds = Louds::from_children(&[vec![1, 3], vec![2], vec![], vec![]]);
+        assert_eq!(vec![vec![1, 2], vec![3], vec![], vec![]], decode(&louds));
+    }
+
+    #[test]
+    fn single_node() {
+        let louds = Louds::from_children(&[vec![]]);
+        assert_eq!(1, louds.node_count());
+        assert_eq!(None, louds.first_child(0));
+        assert_eq!(0, louds.degree(0));
+    }
+
+    #[test]
+    fn deep_path() {
+        let n = 300;
+        let tree: Vec<Vec<usize>> = (0 .. n)
+            .map(|i| if i + 1 < n { vec![i + 1] } else { vec![] })
+            .collect();
+        let louds = Louds::from_children(&tree);
+        assert_eq!(tree, decode(&louds));
+
+        let mut node = louds.node(n as u64 - 1).unwrap();
+        let mut depth = 0;
+        while let Some(parent) = louds.parent(node) {
+            node = parent;
+            depth += 1;
+        }
+        assert_eq!(n - 1, depth);
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_a_tree() {
+        Louds::from_children(&[vec![1], vec![0]]);
+    }
+}
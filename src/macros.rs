@@ -53,6 +53,14 @@ macro_rules! impl_bit_vec_adapter {
         fn get_bits(&self, index: u64, count: usize) -> $block {
             self.$field.get_bits(index, count)
         }
+
+        unsafe fn get_bit_unchecked(&self, index: u64) -> bool {
+            self.$field.get_bit_unchecked(index)
+        }
+
+        unsafe fn get_block_unchecked(&self, index: usize) -> $block {
+            self.$field.get_block_unchecked(index)
+        }
     }
 }
 
@@ -88,6 +96,14 @@ macro_rules! impl_bit_rank_support_adapter {
         fn rank0(&self, index: u64) -> u64 {
             self.$field.rank0(index)
         }
+
+        fn total_ones(&self) -> u64 {
+            self.$field.total_ones()
+        }
+
+        fn rank1_batch(&self, positions: &[u64]) -> Vec<u64> {
+            self.$field.rank1_batch(positions)
+        }
     }
 }
 
@@ -101,6 +117,15 @@ macro_rules! impl_select1_support_adapter {
         fn select1(&self, index: u64) -> Option<u64> {
             self.$field.select1(index)
         }
+
+        fn select1_batch(&self, indices: &[u64]) -> Vec<Option<u64>> {
+            self.$field.select1_batch(indices)
+        }
+
+        fn select1_with_hint(&self, index: u64, hint: (u64, u64))
+                             -> Option<u64> {
+            self.$field.select1_with_hint(index, hint)
+        }
     }
 }
 
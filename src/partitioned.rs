@@ -0,0 +1,264 @@
+//! Bit vectors split into independently stored shards.
+//!
+//! A [`PartitionedBitVector`](struct.PartitionedBitVector.html) cuts the
+//! bits into shards of a fixed number of blocks. Each shard has its own
+//! allocation and its own [rank](../struct.JacobsonRank.html) tables,
+//! plus the number of 1s in the shards before it, so threads reading
+//! disjoint ranges touch disjoint memory. Queries go to a single shard,
+//! found by division, since shard boundaries are block-aligned.
+
+use std::ops::Range;
+
+use bit_vec::{BitVec, BitVecMut, BitVector};
+use rank::{BitRankSupport, JacobsonRank, RankSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A bit vector stored as block-aligned shards, each with its own rank
+/// support.
+///
+/// The vector is `Sync`, so it can be shared between threads by
+/// reference; see `shard_range` for splitting work along the shards.
+///
+/// Construct with `PartitionedBitVector::new`.
+#[derive(Clone, Debug)]
+pub struct PartitionedBitVector<Block: BlockType = u64> {
+    len: u64,
+    shard_blocks: usize,
+    shards: Vec<Shard<Block>>,
+}
+
+#[derive(Clone, Debug)]
+struct Shard<Block: BlockType> {
+    // The number of 1s in the shards before this one.
+    offset: u64,
+    rank: JacobsonRank<BitVector<Block>>,
+}
+
+impl<Block: BlockType> PartitionedBitVector<Block> {
+    /// Copies `bits` into shards of `shard_blocks` blocks each; the last
+    /// shard may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard_blocks` is 0.
+    pub fn new<Bits>(bits: &Bits, shard_blocks: usize) -> Self
+        where Bits: BitVec<Block = Block> + ?Sized {
+
+        assert!(shard_blocks > 0, "PartitionedBitVector::new: zero shard size");
+
+        let len = bits.bit_len();
+        let shard_bits = Block::mul_nbits(shard_blocks);
+        let mut shards = Vec::with_capacity(bits.block_len().div_ceil(shard_blocks));
+        let mut offset = 0;
+
+        let mut start = 0;
+        while start < len {
+            let end = len.min(start + shard_bits);
+            let mut shard = BitVector::with_fill(end - start, false);
+            shard.copy_bits_from(0, bits, start .. end);
+
+            let rank = JacobsonRank::new(shard);
+            let ones = rank.total_ones();
+            shards.push(Shard { offset, rank });
+            offset += ones;
+            start = end;
+        }
+
+        PartitionedBitVector { len, shard_blocks, shards }
+    }
+
+    /// The number of shards.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The number of blocks in each shard but the last.
+    pub fn shard_blocks(&self) -> usize {
+        self.shard_blocks
+    }
+
+    /// The range of bit positions stored in shard `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.shard_count()`.
+    pub fn shard_range(&self, index: usize) -> Range<u64> {
+        assert!(index < self.shards.len(),
+                "PartitionedBitVector::shard_range: out of bounds");
+        let start = Block::mul_nbits(index * self.shard_blocks);
+        start .. start + self.shards[index].rank.bit_len()
+    }
+
+    // The shard holding block `position`, and the block's index within it.
+    #[inline]
+    fn locate_block(&self, position: usize) -> (&Shard<Block>, usize) {
+        (&self.shards[position / self.shard_blocks], position % self.shard_blocks)
+    }
+
+    // The shard holding bit `position`, and the bit's index within it.
+    #[inline]
+    fn locate_bit(&self, position: u64) -> (&Shard<Block>, u64) {
+        let (shard, block) = self.locate_block(Block::div_nbits(position));
+        (shard, Block::mul_nbits(block) + Block::mod_nbits(position) as u64)
+    }
+}
+
+impl<Block: BlockType> BitVec for PartitionedBitVector<Block> {
+    type Block = Block;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.len
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.len, "PartitionedBitVector::get_bit: out of bounds");
+        let (shard, position) = self.locate_bit(position);
+        shard.rank.get_bit(position)
+    }
+
+    fn get_block(&self, position: usize) -> Block {
+        assert!(position < self.block_len(),
+                "PartitionedBitVector::get_block: out of bounds");
+        let (shard, position) = self.locate_block(position);
+        shard.rank.get_block(position)
+    }
+}
+
+impl<Block: BlockType> RankSupport for PartitionedBitVector<Block> {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl<Block: BlockType> BitRankSupport for PartitionedBitVector<Block> {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.len, "PartitionedBitVector::rank1: out of bounds");
+        let (shard, position) = self.locate_bit(position);
+        shard.offset + shard.rank.rank1(position)
+    }
+
+    fn total_ones(&self) -> u64 {
+        self.shards.last().map_or(0, |shard| shard.offset + shard.rank.total_ones())
+    }
+}
+
+impl<Block: BlockType> SpaceUsage for PartitionedBitVector<Block> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.shards.iter()
+            .map(|shard| shard.rank.total_bytes())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use internal::test_util::random_bits;
+    use quickcheck::quickcheck;
+
+    fn check<Block: BlockType>(bits: &BitVector<Block>, shard_blocks: usize) {
+        let expected = JacobsonRank::new(bits);
+        let partitioned = PartitionedBitVector::new(bits, shard_blocks);
+
+        assert_eq!(bits.bit_len(), partitioned.bit_len());
+        assert_eq!(expected.total_ones(), partitioned.total_ones());
+        for i in 0 .. bits.block_len() {
+            assert_eq!(bits.get_block(i), partitioned.get_block(i));
+        }
+        for i in 0 .. bits.bit_len() {
+            assert_eq!(bits.get_bit(i), partitioned.get_bit(i));
+            assert_eq!(expected.rank1(i), partitioned.rank1(i));
+            assert_eq!(expected.rank0(i), partitioned.rank0(i));
+        }
+    }
+
+    #[test]
+    fn random() {
+        for &shard_blocks in &[1, 3, 16, 1000] {
+            check(&random_bits::<u64>(5000, 100, 1), shard_blocks);
+            check(&random_bits::<u8>(1001, 30, 2), shard_blocks);
+        }
+    }
+
+    #[test]
+    fn shards() {
+        let bits = random_bits::<u32>(1000, 128, 3);
+        let partitioned = PartitionedBitVector::new(&bits, 8);
+        assert_eq!(4, partitioned.shard_count());
+        assert_eq!(8, partitioned.shard_blocks());
+        assert_eq!(0 .. 256, partitioned.shard_range(0));
+        assert_eq!(768 .. 1000, partitioned.shard_range(3));
+
+        // Rank at the last bit of a shard and the first of the next.
+        let expected = JacobsonRank::new(&bits);
+        for &i in &[255, 256, 511, 512, 767, 768, 999] {
+            assert_eq!(expected.rank1(i), partitioned.rank1(i));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let partitioned = PartitionedBitVector::new(&BitVector::<u64>::new(), 4);
+        assert_eq!(0, partitioned.bit_len());
+        assert_eq!(0, partitioned.shard_count());
+        assert_eq!(0, partitioned.total_ones());
+        assert_eq!(None, partitioned.rank_checked(0, true));
+    }
+
+    #[test]
+    fn concurrent() {
+        let bits = random_bits::<u64>(64 * 4000, 50, 4);
+        let expected = JacobsonRank::new(&bits);
+        let partitioned = PartitionedBitVector::new(&bits, 1000);
+        assert_eq!(4, partitioned.shard_count());
+
+        thread::scope(|scope| {
+            for shard in 0 .. partitioned.shard_count() {
+                let partitioned = &partitioned;
+                let expected = &expected;
+                scope.spawn(move || {
+                    for i in partitioned.shard_range(shard) {
+                        assert_eq!(expected.rank1(i), partitioned.rank1(i));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_shard_size() {
+        PartitionedBitVector::new(&BitVector::<u64>::new(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rank_oob() {
+        PartitionedBitVector::new(&BitVector::<u8>::with_fill(10, true), 1).rank1(10);
+    }
+
+    fn rank_prop(bits: Vec<bool>, shard_blocks: u8) -> bool {
+        let bits = BitVector::<u8>::from_bits(&bits);
+        let expected = JacobsonRank::new(&bits);
+        let partitioned = PartitionedBitVector::new(&bits, shard_blocks as usize % 5 + 1);
+        (0 .. bits.bit_len()).all(|i| expected.rank1(i) == partitioned.rank1(i))
+    }
+
+    #[test]
+    fn rank_qc() {
+        quickcheck(rank_prop as fn(Vec<bool>, u8) -> bool);
+    }
+}
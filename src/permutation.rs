@@ -0,0 +1,271 @@
+//! Permutations with fast inverses.
+//!
+//! A [`Permutation`](struct.Permutation.html) stores `π` as an integer
+//! vector, so `apply` is a single lookup, and supports `inverse` with the
+//! shortcut technique of Munro, Raman, Raman and Rao: along every cycle
+//! longer than the sample rate `t`, every `t`th element is marked in a bit
+//! vector and stores a back pointer to the previous mark. To invert, walk
+//! forward from `i` until the first mark, jump back, and walk forward
+//! again until reaching the element that maps to `i`, for at most about
+//! `2t` steps. The marks take `n` bits and the back pointers `(n/t) lg n`,
+//! so with `t = lg n` this adds about `2n` bits to the `n lg n` of the
+//! permutation itself.
+
+use std::cmp;
+
+use bit_vec::{BitVec, BitVecMut, BitVector};
+use int_vec::{IntVec, IntVector};
+use rank::{BitRankSupport, JacobsonRank};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A permutation of `0 .. n` supporting constant-time application and
+/// *O*(`t`)-time inversion for a sample rate `t`.
+///
+/// Construct with `Permutation::new` or `Permutation::with_sample_rate`.
+#[derive(Clone, Debug)]
+pub struct Permutation {
+    forward: IntVector<u64>,
+    sample_rate: u64,
+    marks: JacobsonRank<BitVector<u64>>,
+    back: IntVector<u64>,
+}
+
+impl Permutation {
+    /// Stores the permutation that maps `i` to `values[i]`, with a sample
+    /// rate of `lg n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is not a permutation of `0 .. values.len()`.
+    pub fn new(values: &[u64]) -> Self {
+        let rate = cmp::max(1, (values.len() as u64).ceil_lg());
+        Self::with_sample_rate(values, rate)
+    }
+
+    /// Stores the permutation that maps `i` to `values[i]`, marking every
+    /// `sample_rate`th element of each cycle longer than `sample_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is 0, or if `values` is not a permutation
+    /// of `0 .. values.len()`.
+    pub fn with_sample_rate(values: &[u64], sample_rate: usize) -> Self {
+        assert!(sample_rate > 0, "Permutation::with_sample_rate: zero sample rate");
+
+        let len = values.len() as u64;
+        let mut seen = BitVector::<u64>::with_fill(len, false);
+        for &value in values {
+            assert!(value < len && !seen.get_bit(value),
+                    "Permutation::with_sample_rate: not a permutation");
+            seen.set_bit(value, true);
+        }
+
+        let width = cmp::max(1, len.ceil_lg());
+        let mut forward = IntVector::with_capacity(width, len);
+        for &value in values {
+            forward.push(value);
+        }
+
+        // Walk each cycle once, marking every `sample_rate`th element and
+        // recording the previous mark of each.
+        let sample_rate = sample_rate as u64;
+        let mut marks = BitVector::<u64>::with_fill(len, false);
+        let mut back_of = Vec::new();
+        let mut visited = BitVector::<u64>::with_fill(len, false);
+
+        for start in 0 .. len {
+            if visited.get_bit(start) { continue; }
+
+            let mut cycle = vec![start];
+            visited.set_bit(start, true);
+            let mut next = values[start as usize];
+            while next != start {
+                visited.set_bit(next, true);
+                cycle.push(next);
+                next = values[next as usize];
+            }
+
+            if cycle.len() as u64 <= sample_rate { continue; }
+
+            let sampled: Vec<u64> = cycle.iter().cloned()
+                .step_by(sample_rate as usize).collect();
+            for (k, &element) in sampled.iter().enumerate() {
+                let previous = if k == 0 { sampled[sampled.len() - 1] }
+                               else { sampled[k - 1] };
+                marks.set_bit(element, true);
+                back_of.push((element, previous));
+            }
+        }
+
+        // Back pointers are stored in order of the marked positions, so
+        // rank over the marks finds them.
+        back_of.sort();
+        let mut back = IntVector::with_capacity(width, back_of.len() as u64);
+        for &(_, previous) in &back_of {
+            back.push(previous);
+        }
+
+        Permutation {
+            forward,
+            sample_rate,
+            marks: JacobsonRank::new(marks),
+            back,
+        }
+    }
+
+    /// The size `n` of the permuted set `0 .. n`.
+    pub fn len(&self) -> u64 {
+        self.forward.len()
+    }
+
+    /// Is this the permutation of the empty set?
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
+
+    /// The number of elements per cycle between marks.
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate as usize
+    }
+
+    /// Returns `π(index)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn apply(&self, index: u64) -> u64 {
+        assert!(index < self.len(), "Permutation::apply: out of bounds");
+        self.forward.get(index)
+    }
+
+    /// Returns `π⁻¹(index)`, the element that `π` maps to `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn inverse(&self, index: u64) -> u64 {
+        assert!(index < self.len(), "Permutation::inverse: out of bounds");
+
+        let mut current = index;
+        let mut jumped = false;
+        loop {
+            let next = self.forward.get(current);
+            if next == index { return current; }
+
+            current = if !jumped && self.marks.get_bit(current) {
+                jumped = true;
+                self.back.get(self.marks.rank1(current) - 1)
+            } else {
+                next
+            };
+        }
+    }
+}
+
+impl SpaceUsage for Permutation {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.forward.heap_bytes() + self.marks.heap_bytes()
+            + self.back.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use internal::test_util::Lcg;
+    use quickcheck::quickcheck;
+
+    // A random permutation of `0 .. len` by Fisher–Yates.
+    fn random_permutation(len: u64, seed: u64) -> Vec<u64> {
+        let mut lcg = Lcg::new(seed);
+        let mut result: Vec<u64> = (0 .. len).collect();
+        for i in (1 .. result.len()).rev() {
+            let j = (lcg.next_u64() >> 33) as usize % (i + 1);
+            result.swap(i, j);
+        }
+        result
+    }
+
+    fn check(values: &[u64], permutation: &Permutation) {
+        assert_eq!(values.len() as u64, permutation.len());
+        for (i, &value) in values.iter().enumerate() {
+            let i = i as u64;
+            assert_eq!(value, permutation.apply(i));
+            assert_eq!(i, permutation.inverse(value));
+            assert_eq!(i, permutation.inverse(permutation.apply(i)));
+        }
+    }
+
+    #[test]
+    fn random() {
+        for &len in &[1, 2, 10, 1000, 20_000] {
+            let values = random_permutation(len, len);
+            check(&values, &Permutation::new(&values));
+            for &rate in &[1, 2, 7, 100] {
+                check(&values, &Permutation::with_sample_rate(&values, rate));
+            }
+        }
+    }
+
+    #[test]
+    fn edges() {
+        let empty = Permutation::new(&[]);
+        assert!(empty.is_empty());
+
+        let identity: Vec<u64> = (0 .. 100).collect();
+        check(&identity, &Permutation::new(&identity));
+
+        // One long cycle, where every inverse uses a back pointer.
+        let rotation: Vec<u64> = (0 .. 1000).map(|i| (i + 1) % 1000).collect();
+        let permutation = Permutation::with_sample_rate(&rotation, 10);
+        check(&rotation, &permutation);
+        assert_eq!(100, permutation.back.len());
+
+        let reversal: Vec<u64> = (0 .. 101).rev().collect();
+        check(&reversal, &Permutation::with_sample_rate(&reversal, 1));
+    }
+
+    #[test]
+    fn space() {
+        let values = random_permutation(100_000, 4);
+        let permutation = Permutation::new(&values);
+        // 17 bits per element for the permutation itself.
+        assert!(permutation.heap_bytes() * 8 < 100_000 * 21);
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_a_permutation() {
+        Permutation::new(&[0, 2, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        Permutation::new(&[0, 3, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn inverse_oob() {
+        Permutation::new(&[1, 0]).inverse(2);
+    }
+
+    fn inverse_prop(len: u16, seed: u64, rate: u8) -> bool {
+        let values = random_permutation(len as u64 % 500, seed);
+        let permutation = Permutation::with_sample_rate(&values, rate as usize % 20 + 1);
+        values.iter().enumerate().all(|(i, &value)| {
+            permutation.apply(i as u64) == value
+                && permutation.inverse(value) == i as u64
+        })
+    }
+
+    #[test]
+    fn inverse_qc() {
+        quickcheck(inverse_prop as fn(u16, u64, u8) -> bool);
+    }
+}
@@ -0,0 +1,231 @@
+//! Sequences of 2-bit symbols.
+//!
+//! A [`QuadVector`](struct.QuadVector.html) stores a sequence over the
+//! alphabet `0 .. 4`, such as DNA bases, as one indicator bit vector per
+//! symbol, each with its own [rank and
+//! select](../indexed/struct.IndexedBitVector.html) support. Rank and
+//! select for a symbol are then a single query on its indicator, rather
+//! than one per level as in a [wavelet
+//! tree](../wavelet/struct.WaveletTree.html).
+
+use bit_vec::{BitVec, BitVector};
+use indexed::IndexedBitVector;
+use rank::{BitRankSupport, RankSupport};
+use select::{Select1Support, SelectSupport};
+use space_usage::SpaceUsage;
+
+/// A sequence of symbols in `0 .. 4`, supporting access, rank and select.
+///
+/// Construct with `QuadVector::new`.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::QuadVector;
+/// use succinct::rank::RankSupport;
+/// use succinct::select::SelectSupport;
+///
+/// // ACGTTGCA, with A = 0, C = 1, G = 2, T = 3.
+/// let bases = QuadVector::new(&[0, 1, 2, 3, 3, 2, 1, 0]);
+/// assert_eq!(3, bases.access(4));
+/// assert_eq!(2, bases.rank(5, 2));
+/// assert_eq!(Some(7), bases.select(1, 0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct QuadVector {
+    len: u64,
+    // Bit `i` of indicator `s` is set when symbol `i` is `s`.
+    indicators: [IndexedBitVector<u64>; 4],
+}
+
+impl QuadVector {
+    /// Builds the indicators for the given sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any symbol is not less than 4.
+    pub fn new(symbols: &[u8]) -> Self {
+        assert!(symbols.iter().all(|&symbol| symbol < 4),
+                "QuadVector::new: symbol out of range");
+
+        let len = symbols.len() as u64;
+        let indicator = |value: u8| {
+            let bits = BitVector::from_fn(len, |i| symbols[i as usize] == value);
+            IndexedBitVector::build(bits, IndexedBitVector::<u64>::DEFAULT_SAMPLE_RATE)
+        };
+
+        QuadVector {
+            len,
+            indicators: [indicator(0), indicator(1), indicator(2), indicator(3)],
+        }
+    }
+
+    /// The number of symbols in the sequence.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the symbol at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn access(&self, position: u64) -> u8 {
+        assert!(position < self.len, "QuadVector::access: out of bounds");
+
+        // Exactly one indicator has the bit set, so the last needn’t be
+        // read.
+        (0 .. 3).find(|&value| self.indicators[value as usize].get_bit(position))
+                .unwrap_or(3)
+    }
+
+    /// The number of occurrences of `value` in the sequence.
+    pub fn count(&self, value: u8) -> u64 {
+        self.indicator(value).map_or(0, |bits| bits.total_ones())
+    }
+
+    fn indicator(&self, value: u8) -> Option<&IndexedBitVector<u64>> {
+        self.indicators.get(value as usize)
+    }
+}
+
+impl RankSupport for QuadVector {
+    type Over = u8;
+
+    fn rank(&self, position: u64, value: u8) -> u64 {
+        assert!(position < self.len, "QuadVector::rank: out of bounds");
+        self.indicator(value).map_or(0, |bits| bits.rank1(position))
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl SelectSupport for QuadVector {
+    type Over = u8;
+
+    fn select(&self, index: u64, value: u8) -> Option<u64> {
+        self.indicator(value)?.select1(index)
+    }
+}
+
+impl SpaceUsage for QuadVector {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.indicators.iter().map(|bits| bits.heap_bytes()).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+    use wavelet::WaveletTree;
+
+    fn naive_rank(symbols: &[u8], position: usize, value: u8) -> u64 {
+        symbols[.. position + 1].iter().filter(|&&s| s == value).count() as u64
+    }
+
+    fn naive_select(symbols: &[u8], index: u64, value: u8) -> Option<u64> {
+        symbols.iter().enumerate()
+            .filter(|&(_, &s)| s == value)
+            .nth(index as usize)
+            .map(|(i, _)| i as u64)
+    }
+
+    fn check(symbols: &[u8]) {
+        let quad = QuadVector::new(symbols);
+        assert_eq!(symbols.len() as u64, quad.len());
+
+        for (i, &symbol) in symbols.iter().enumerate() {
+            assert_eq!(symbol, quad.access(i as u64));
+        }
+
+        for value in 0 .. 5 {
+            let count = symbols.iter().filter(|&&s| s == value).count() as u64;
+            assert_eq!(count, quad.count(value));
+            for i in 0 .. symbols.len() {
+                assert_eq!(naive_rank(symbols, i, value), quad.rank(i as u64, value));
+            }
+            for index in 0 .. symbols.len() as u64 + 1 {
+                assert_eq!(naive_select(symbols, index, value),
+                           quad.select(index, value));
+            }
+        }
+    }
+
+    #[test]
+    fn bases() {
+        // GATTACA
+        let symbols = [2, 0, 3, 3, 0, 1, 0];
+        let quad = QuadVector::new(&symbols);
+
+        assert_eq!(1, quad.access(5));
+        assert_eq!(3, quad.rank(6, 0));
+        assert_eq!(2, quad.rank(3, 3));
+        assert_eq!(0, quad.rank(4, 1));
+        assert_eq!(Some(4), quad.select(1, 0));
+        assert_eq!(Some(3), quad.select(1, 3));
+        assert_eq!(None, quad.select(1, 2));
+        assert_eq!(None, quad.select(0, 4));
+
+        check(&symbols);
+    }
+
+    #[test]
+    fn sequences() {
+        check(&[]);
+        check(&[0, 0, 0]);
+        check(&[3; 200]);
+        check(&(0 .. 1000).map(|i| (i * i % 7 % 4) as u8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn matches_wavelet_tree() {
+        let symbols: Vec<u8> = (0 .. 500).map(|i| (i * 31 % 17 % 4) as u8).collect();
+        let quad = QuadVector::new(&symbols);
+        let tree = WaveletTree::new(&symbols);
+        for value in 0 .. 4 {
+            for i in 0 .. symbols.len() as u64 {
+                assert_eq!(tree.rank(i, value), quad.rank(i, value));
+                assert_eq!(tree.select(i, value), quad.select(i, value));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn symbol_out_of_range() {
+        QuadVector::new(&[0, 1, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn access_oob() {
+        QuadVector::new(&[0, 1, 2]).access(3);
+    }
+
+    fn access_rank_prop(symbols: Vec<u8>) -> bool {
+        let symbols: Vec<u8> = symbols.into_iter().map(|s| s % 4).collect();
+        let quad = QuadVector::new(&symbols);
+        symbols.iter().enumerate().all(|(i, &symbol)| {
+            quad.access(i as u64) == symbol
+                && quad.rank(i as u64, symbol) == naive_rank(&symbols, i, symbol)
+                && quad.select(quad.rank(i as u64, symbol) - 1, symbol)
+                       == Some(i as u64)
+        })
+    }
+
+    #[test]
+    fn access_rank_qc() {
+        quickcheck(access_rank_prop as fn(Vec<u8>) -> bool);
+    }
+}
@@ -0,0 +1,183 @@
+use bit_vec::{BitVec, BitVector};
+use space_usage::SpaceUsage;
+use super::{BitRankSupport, JacobsonRank};
+
+/// A minimal perfect hash from the set bits of a bit vector to `0 .. n`.
+///
+/// For a bit vector with `n` 1s, the rank of each 1 just before it is a
+/// distinct number in `0 .. n`, so a rank structure over a bit vector
+/// with a 1 at each key hashes the keys densely, in increasing order.
+/// Positions whose bit is 0 are not keys and hash to `None`.
+///
+/// Construct with `BitRankHash::new` over any rank structure, or with
+/// `BitRankHash::from_keys`.
+///
+/// # Examples
+///
+/// ```
+/// use succinct::rank::BitRankHash;
+///
+/// let hash = BitRankHash::from_keys(1000, &[17, 900, 3]);
+/// assert_eq!(Some(0), hash.hash(3));
+/// assert_eq!(Some(1), hash.hash(17));
+/// assert_eq!(Some(2), hash.hash(900));
+/// assert_eq!(None, hash.hash(4));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitRankHash<Rank> {
+    rank_support: Rank,
+}
+
+impl<Rank: BitRankSupport + BitVec> BitRankHash<Rank> {
+    /// Hashes the set bits of the vector underlying `rank_support`.
+    pub fn new(rank_support: Rank) -> Self {
+        BitRankHash { rank_support }
+    }
+
+    /// The number of keys, which is the size of the hash range.
+    pub fn len(&self) -> u64 {
+        self.rank_support.total_ones()
+    }
+
+    /// Are there no keys?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The size of the key universe, `0 .. universe()`.
+    pub fn universe(&self) -> u64 {
+        self.rank_support.limit()
+    }
+
+    /// Returns the index of `key` among the keys, or `None` if `key` is
+    /// not a key. Keys at or past the universe are never keys.
+    pub fn hash(&self, key: u64) -> Option<u64> {
+        if key < self.universe() && self.rank_support.get_bit(key) {
+            Some(self.rank_support.rank1(key) - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Borrows a reference to the underlying rank support.
+    pub fn inner(&self) -> &Rank {
+        &self.rank_support
+    }
+
+    /// Returns the underlying rank support.
+    pub fn into_inner(self) -> Rank {
+        self.rank_support
+    }
+}
+
+impl BitRankHash<JacobsonRank<BitVector<u64>>> {
+    /// Hashes the given keys, drawn from the universe `0 .. universe`.
+    ///
+    /// Keys may repeat and come in any order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any key is not less than `universe`.
+    pub fn from_keys(universe: u64, keys: &[u64]) -> Self {
+        let bits = BitVector::with_bits_set(universe, keys.iter().cloned());
+        Self::new(JacobsonRank::new(bits))
+    }
+}
+
+impl<Rank: SpaceUsage> SpaceUsage for BitRankHash<Rank> {
+    fn is_stack_only() -> bool { Rank::is_stack_only() }
+    fn heap_bytes(&self) -> usize { self.rank_support.heap_bytes() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use internal::test_util::Lcg;
+    use quickcheck::quickcheck;
+    use rank::Rank9;
+    use sparse::SparseBitVector;
+
+    // Distinct keys below `universe`.
+    fn random_keys(universe: u64, count: usize, seed: u64) -> Vec<u64> {
+        let mut lcg = Lcg::new(seed);
+        let mut keys = Vec::with_capacity(count);
+        for _ in 0 .. count {
+            keys.push((lcg.next_u64() >> 20) % universe);
+        }
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    fn check<Rank: BitRankSupport + BitVec>(hash: &BitRankHash<Rank>, keys: &[u64]) {
+        assert_eq!(keys.len() as u64, hash.len());
+
+        let mut seen = vec![false; keys.len()];
+        for &key in keys {
+            let index = hash.hash(key).unwrap();
+            assert!(index < hash.len());
+            assert!(!seen[index as usize]);
+            seen[index as usize] = true;
+        }
+
+        for key in 0 .. hash.universe() {
+            assert_eq!(keys.binary_search(&key).is_ok(), hash.hash(key).is_some());
+        }
+        assert_eq!(None, hash.hash(hash.universe()));
+        assert_eq!(None, hash.hash(u64::MAX));
+    }
+
+    #[test]
+    fn random() {
+        for &(universe, count) in &[(1, 1), (100, 10), (5000, 300), (5000, 4000)] {
+            let keys = random_keys(universe, count, universe);
+            let hash = BitRankHash::from_keys(universe, &keys);
+            check(&hash, &keys);
+            // Keys hash in increasing order.
+            for (index, &key) in keys.iter().enumerate() {
+                assert_eq!(Some(index as u64), hash.hash(key));
+            }
+        }
+    }
+
+    #[test]
+    fn other_rank_structures() {
+        let keys = random_keys(3000, 200, 7);
+        let bits = BitVector::<u64>::with_bits_set(3000, keys.iter().cloned());
+        check(&BitRankHash::new(Rank9::new(bits.clone())), &keys);
+        check(&BitRankHash::new(SparseBitVector::new(&bits)), &keys);
+        check(&BitRankHash::new(JacobsonRank::new(&bits)), &keys);
+    }
+
+    #[test]
+    fn empty() {
+        let hash = BitRankHash::from_keys(0, &[]);
+        assert!(hash.is_empty());
+        assert_eq!(None, hash.hash(0));
+
+        let hash = BitRankHash::from_keys(100, &[]);
+        assert!(hash.is_empty());
+        assert_eq!(None, hash.hash(50));
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_out_of_universe() {
+        BitRankHash::from_keys(10, &[3, 10]);
+    }
+
+    fn hash_prop(keys: Vec<u16>) -> bool {
+        let keys: Vec<u64> = keys.into_iter().map(|key| key as u64).collect();
+        let hash = BitRankHash::from_keys(1 << 16, &keys);
+        let mut sorted = keys.clone();
+        sorted.sort();
+        sorted.dedup();
+        sorted.iter().enumerate().all(|(index, &key)| hash.hash(key) == Some(index as u64))
+            && hash.len() == sorted.len() as u64
+    }
+
+    #[test]
+    fn hash_qc() {
+        quickcheck(hash_prop as fn(Vec<u16>) -> bool);
+    }
+}
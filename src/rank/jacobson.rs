@@ -1,6 +1,10 @@
+use std::cmp;
+use std::io;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::PrimInt;
 
-use bit_vec::BitVec;
+use bit_vec::{BitVec, BitVecMut, BitVector};
 use int_vec::{IntVec, IntVector};
 use space_usage::SpaceUsage;
 use storage::{Address, BlockType};
@@ -9,7 +13,9 @@ use super::{RankSupport, BitRankSupport};
 
 /// Jacobson’s rank structure for fast rank queries over a `BitVec`.
 ///
-/// Construct with `JacobsonRank::new`.
+/// Construct with `JacobsonRank::new`. The structure can own its bits or
+/// borrow them, since `&Bits` is a `BitVec` whenever `Bits` is; in the
+/// latter case `into_inner` gives back the reference.
 #[derive(Clone, Debug)]
 pub struct JacobsonRank<Store> {
     bit_store: Store,
@@ -21,19 +27,300 @@ pub struct JacobsonRank<Store> {
 impl<Store: BitVec> JacobsonRank<Store> {
     /// Creates a new rank support structure for the given bit vector.
     pub fn new(bits: Store) -> Self {
-        let n = bits.bit_len();
+        let counts = (0 .. bits.block_len())
+            .map(|i| bits.get_block(i).count_ones() as u64);
+        let tables = Tables::build::<Store::Block, _>(bits.bit_len(), counts);
+        Self::from_tables(bits, tables)
+    }
+
+    /// Creates a new rank support structure for the given bit vector,
+    /// counting the bits of its blocks in parallel.
+    ///
+    /// The result is identical to that of `new`; only the block
+    /// popcounts are computed in parallel, followed by the same
+    /// sequential prefix sum.
+    #[cfg(feature = "rayon")]
+    pub fn from_bits_parallel(bits: Store) -> Self where Store: Sync {
+        use rayon::prelude::*;
+
+        let counts: Vec<u64> = (0 .. bits.block_len())
+            .into_par_iter()
+            .map(|i| bits.get_block(i).count_ones() as u64)
+            .collect();
+        let tables = Tables::build::<Store::Block, _>(bits.bit_len(),
+                                                      counts.into_iter());
+        Self::from_tables(bits, tables)
+    }
+
+    /// Creates a new rank support structure over `bits`, counting the 1s
+    /// in a single pass over `blocks`, which holds the same `bit_len` bits.
+    ///
+    /// Only the running counts are kept, not the blocks, so the source
+    /// needn’t be a `BitVec`, support multiple passes or fit in memory.
+    /// Queries read `bits`, which can live elsewhere: a borrowed vector or
+    /// one backed by a file, say. Any bits of the last block past
+    /// `bit_len` are ignored, and blocks after it are not consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits.bit_len() != bit_len`, or if the iterator yields
+    /// fewer than `ceil(bit_len / Block::nbits())` blocks.
+    pub fn from_blocks<I>(blocks: I, bit_len: u64, bits: Store) -> Self
+        where I: IntoIterator<Item = Store::Block>
+    {
+        assert!(bits.bit_len() == bit_len,
+                "JacobsonRank::from_blocks: length mismatch");
+
+        let block_len = Store::Block::ceil_div_nbits(bit_len);
+        let mut consumed = 0;
+        let counts = blocks.into_iter().take(block_len).map(|block| {
+            consumed += 1;
+            let block = if consumed == block_len {
+                block & Store::Block::last_block_mask(bit_len)
+            } else {
+                block
+            };
+            block.count_ones() as u64
+        });
+        let tables = Tables::build::<Store::Block, _>(bit_len, counts);
+
+        assert!(consumed == block_len,
+                "JacobsonRank::from_blocks: too few blocks");
+
+        Self::from_tables(bits, tables)
+    }
+
+    fn from_tables(bits: Store, tables: Tables) -> Self {
+        JacobsonRank {
+            bit_store: bits,
+            large_block_size: tables.large_block_size,
+            large_block_ranks: tables.large_block_ranks,
+            small_block_ranks: tables.small_block_ranks,
+        }
+    }
+
+    /// Borrows a reference to the underlying bit store.
+    pub fn inner(&self) -> &Store {
+        &self.bit_store
+    }
+
+    /// Returns the underlying bit store.
+    pub fn into_inner(self) -> Store {
+        self.bit_store
+    }
+
+    /// The number of bits covered by each superblock.
+    ///
+    /// This is a multiple of the block width, chosen from the length of
+    /// the vector, and may change between versions.
+    pub fn superblock_size(&self) -> usize {
+        self.large_block_size
+    }
+
+    /// The cumulative rank table, one entry per superblock.
+    ///
+    /// Entry `k` is the number of 1s before bit
+    /// `k * self.superblock_size()`, and a final entry holds the total.
+    /// Like the other table accessors this exposes an implementation
+    /// detail: the tables are bit-packed, and their layout depends on the
+    /// block type and superblock size.
+    pub fn superblock_counts(&self) -> &IntVector<u64> {
+        &self.large_block_ranks
+    }
+
+    /// The relative rank table, one entry per block of the underlying bit
+    /// store.
+    ///
+    /// Entry `i` is the number of 1s between the start of the superblock
+    /// containing block `i` and the start of block `i`, and a final entry
+    /// covers the whole vector. See `superblock_counts` for the caveats.
+    pub fn block_counts(&self) -> &IntVector<u64> {
+        &self.small_block_ranks
+    }
+}
+
+impl<Block: BlockType> JacobsonRank<BitVector<Block>> {
+    /// Writes the bits and the rank tables to `sink`.
+    ///
+    /// The format is that of `BitVector::write_to`, followed by the large
+    /// block size and then each table as its element width, length and
+    /// blocks, all little-endian `u64`s. The tables depend on `Block`, so
+    /// read the result back with the same block type. To store only the
+    /// bits, and rebuild the tables when loading, write `self.inner()`
+    /// and read it with `read_from_rebuilt`.
+    pub fn write_to<W: io::Write>(&self, sink: &mut W) -> io::Result<()> {
+        self.bit_store.write_to(sink)?;
+        sink.write_u64::<LittleEndian>(self.large_block_size as u64)?;
+        write_table(&self.large_block_ranks, sink)?;
+        write_table(&self.small_block_ranks, sink)
+    }
+
+    /// Reads bits and rank tables in the format written by
+    /// [`write_to`](#method.write_to), without recomputing the tables.
+    ///
+    /// Fails with `ErrorKind::InvalidData` if the tables don’t have the
+    /// size and shape that `new` would build for the bits read, or if
+    /// their entries couldn’t be counts of those bits: superblock counts
+    /// must start at 0 and grow by at most a superblock at a time, and
+    /// block counts must start each superblock at 0 and grow by at most a
+    /// block at a time. Otherwise fails with the errors of
+    /// `BitVector::read_from`. The entries aren’t compared against the
+    /// bits themselves, which would take as long as rebuilding them.
+    pub fn read_from<R: io::Read>(source: &mut R) -> io::Result<Self> {
+        let bits = BitVector::read_from(source)?;
+        let (large_block_size, large_meta_size, small_meta_size) =
+            Tables::shape::<Block>(bits.bit_len());
+        let small_per_large = large_block_size / Block::nbits();
+        let large_block_count =
+            bits.block_len().ceil_div(small_per_large) as u64 + 1;
+        let small_block_count = bits.block_len() as u64 + 1;
+
+        if source.read_u64::<LittleEndian>()? != large_block_size as u64 {
+            return Err(invalid_table());
+        }
+        let large_block_ranks =
+            read_table(source, large_meta_size, large_block_count)?;
+        let small_block_ranks =
+            read_table(source, small_meta_size, small_block_count)?;
+
+        if !plausible_tables::<Block>(&large_block_ranks, &small_block_ranks,
+                                      large_block_size) {
+            return Err(invalid_table());
+        }
+
+        Ok(JacobsonRank {
+            bit_store: bits,
+            large_block_size,
+            large_block_ranks,
+            small_block_ranks,
+        })
+    }
+
+    /// Reads bits in the format written by `BitVector::write_to` and
+    /// builds the rank tables for them.
+    ///
+    /// This trades load time for space compared with
+    /// [`read_from`](#method.read_from).
+    pub fn read_from_rebuilt<R: io::Read>(source: &mut R) -> io::Result<Self> {
+        Ok(Self::new(BitVector::read_from(source)?))
+    }
+}
+
+fn write_table<W: io::Write>(table: &IntVector<u64>, sink: &mut W)
+                             -> io::Result<()> {
+    sink.write_u64::<LittleEndian>(table.element_bits() as u64)?;
+    sink.write_u64::<LittleEndian>(table.len())?;
+    for i in 0 .. table.block_len() {
+        sink.write_u64::<LittleEndian>(table.get_block(i))?;
+    }
+    Ok(())
+}
+
+// Reads a table written by `write_table`, checking that it has the
+// expected width and length.
+fn read_table<R: io::Read>(source: &mut R, element_bits: usize, len: u64)
+                           -> io::Result<IntVector<u64>> {
+    if source.read_u64::<LittleEndian>()? != element_bits as u64
+            || source.read_u64::<LittleEndian>()? != len {
+        return Err(invalid_table());
+    }
+
+    let mut table = IntVector::with_fill(element_bits, len, 0);
+    for i in 0 .. table.block_len() {
+        let block = source.read_u64::<LittleEndian>()?;
+        table.set_block_masked(i, block);
+    }
+    Ok(table)
+}
+
+// Checks the invariants of tables built by `Tables::build` that don’t
+// depend on the bits: counts start at 0 and never decrease, grow by at
+// most the number of bits they cover, and agree with each other where
+// superblocks meet.
+fn plausible_tables<Block: BlockType>(large_block_ranks: &IntVector<u64>,
+                                      small_block_ranks: &IntVector<u64>,
+                                      large_block_size: usize) -> bool {
+    let nbits = Block::nbits() as u64;
+    let small_per_large = (large_block_size / Block::nbits()) as u64;
+    let block_len = small_block_ranks.len() - 1;
+
+    let large_ok = large_block_ranks.get(0) == 0
+        && (1 .. large_block_ranks.len()).all(|k| {
+            let (before, after) = (large_block_ranks.get(k - 1), large_block_ranks.get(k));
+            before <= after && after - before <= large_block_size as u64
+        });
+    if !large_ok { return false; }
+
+    // The final entry continues the last superblock that has blocks, if
+    // there are any.
+    let small_ok = (0 .. block_len + 1).all(|i| {
+        let rank = small_block_ranks.get(i);
+        if i == 0 || i % small_per_large == 0 && i < block_len {
+            rank == 0
+        } else {
+            let before = small_block_ranks.get(i - 1);
+            before <= rank && rank - before <= nbits
+        }
+    });
+    if !small_ok { return false; }
+
+    // Each superblock’s count is the previous one’s plus the count up to
+    // its last block, plus at most a block.
+    let joins_ok = (1 .. large_block_ranks.len() - 1).all(|k| {
+        let step = large_block_ranks.get(k) - large_block_ranks.get(k - 1);
+        let before_last = small_block_ranks.get(k * small_per_large - 1);
+        before_last <= step && step - before_last <= nbits
+    });
+    if !joins_ok { return false; }
+
+    let last_superblock = block_len.saturating_sub(1) / small_per_large;
+    large_block_ranks.get(last_superblock) + small_block_ranks.get(block_len)
+        == large_block_ranks.get(large_block_ranks.len() - 1)
+}
+
+fn invalid_table() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData,
+                   "JacobsonRank::read_from: tables don’t match bits")
+}
+
+// The auxiliary tables of a `JacobsonRank`, computed from the popcounts
+// of the blocks of a bit vector of `n` bits.
+struct Tables {
+    large_block_size: usize,
+    large_block_ranks: IntVector<u64>,
+    small_block_ranks: IntVector<u64>,
+}
+
+impl Tables {
+    // The large block size and the widths of the large and small block
+    // table entries for a vector of `n` bits.
+    fn shape<Block: BlockType>(n: u64) -> (usize, usize, usize) {
         let lg_n = n.ceil_lg();
         let lg2_n = lg_n * lg_n;
 
-        let small_block_size  = Store::Block::nbits();
-        let small_per_large   = lg2_n.ceil_div(small_block_size);
+        let small_block_size  = Block::nbits();
+        // For tiny vectors lg²n is 0, but we still need at least one
+        // small block per large block.
+        let small_per_large   = cmp::max(1, lg2_n.ceil_div(small_block_size));
         let large_block_size  = small_block_size * small_per_large;
-        let large_block_count = n / large_block_size as u64 + 1;
-        let small_block_count = n / small_block_size as u64 + 1;
 
-        let large_meta_size   = (n + 1).ceil_lg();
+        let large_meta_size   = cmp::max(1, (n + 1).ceil_lg());
         let small_meta_size   = (large_block_size + 1).ceil_lg();
 
+        (large_block_size, large_meta_size, small_meta_size)
+    }
+
+    fn build<Block, Counts>(n: u64, counts: Counts) -> Self
+        where Block: BlockType,
+              Counts: Iterator<Item = u64>
+    {
+        let (large_block_size, large_meta_size, small_meta_size) =
+            Self::shape::<Block>(n);
+        let small_block_size  = Block::nbits();
+        let small_per_large   = large_block_size / small_block_size;
+        let large_block_count = n / large_block_size as u64 + 1;
+        let small_block_count = n / small_block_size as u64 + 1;
+
         let mut large_block_ranks =
             IntVector::with_capacity(large_meta_size, large_block_count);
         let mut small_block_ranks =
@@ -43,7 +330,7 @@ impl<Store: BitVec> JacobsonRank<Store> {
         let mut last_large_rank: u64 = 0;
         let mut small_block_index: usize = 0;
 
-        for i in 0 .. bits.block_len() {
+        for count in counts {
             if small_block_index == 0 {
                 large_block_ranks.push(current_rank);
                 last_large_rank = current_rank;
@@ -52,7 +339,7 @@ impl<Store: BitVec> JacobsonRank<Store> {
             let excess_rank = current_rank - last_large_rank;
             small_block_ranks.push(excess_rank);
 
-            current_rank += bits.get_block(i).count_ones() as u64;
+            current_rank += count;
             small_block_index += 1;
 
             if small_block_index == small_per_large {
@@ -64,23 +351,12 @@ impl<Store: BitVec> JacobsonRank<Store> {
         let excess_rank = current_rank - last_large_rank;
         small_block_ranks.push(excess_rank);
 
-        JacobsonRank {
-            bit_store: bits,
-            large_block_size: large_block_size,
-            large_block_ranks: large_block_ranks,
-            small_block_ranks: small_block_ranks,
+        Tables {
+            large_block_size,
+            large_block_ranks,
+            small_block_ranks,
         }
     }
-
-    /// Borrows a reference to the underlying bit store.
-    pub fn inner(&self) -> &Store {
-        &self.bit_store
-    }
-
-    /// Returns the underlying bit store.
-    pub fn into_inner(self) -> Store {
-        self.bit_store
-    }
 }
 
 impl<Store: BitVec> RankSupport for JacobsonRank<Store> {
@@ -105,11 +381,52 @@ impl<Store: BitVec> BitRankSupport for JacobsonRank<Store> {
 
         let large_rank = self.large_block_ranks.get(large_block);
         let small_rank = self.small_block_ranks.get(address.block_index as u64);
-        let bits_rank  = self.bit_store.get_block(address.block_index)
-                             .rank1(address.bit_offset as u64);
+        // The position was checked above, so its block is in bounds.
+        let bits_rank  = unsafe {
+            self.bit_store.get_block_unchecked(address.block_index)
+        }.rank1(address.bit_offset as u64);
 
         large_rank + small_rank + bits_rank
     }
+
+    fn rank1_batch(&self, positions: &[u64]) -> Vec<u64> {
+        let mut order: Vec<usize> = (0 .. positions.len()).collect();
+        order.sort_by_key(|&i| positions[i]);
+
+        // Visiting the positions in order, the table lookups for a block
+        // are shared by every query that falls in it.
+        let mut result = vec![0; positions.len()];
+        let mut cached: Option<(usize, u64, Store::Block)> = None;
+
+        for i in order {
+            let position = positions[i];
+            assert!(position < self.bit_len(),
+                    "JacobsonRank::rank1_batch: out of bounds");
+
+            let address = Address::new::<Store::Block>(position);
+            let (base_rank, block) = match cached {
+                Some((index, base_rank, block))
+                    if index == address.block_index => (base_rank, block),
+                _ => {
+                    let large_block = position / self.large_block_size as u64;
+                    let base_rank = self.large_block_ranks.get(large_block)
+                        + self.small_block_ranks.get(address.block_index as u64);
+                    let block = self.bit_store.get_block(address.block_index);
+                    cached = Some((address.block_index, base_rank, block));
+                    (base_rank, block)
+                }
+            };
+
+            result[i] = base_rank + block.rank1(address.bit_offset as u64);
+        }
+
+        result
+    }
+
+    fn total_ones(&self) -> u64 {
+        // The last large block entry is the sum over the whole vector.
+        self.large_block_ranks.get(self.large_block_ranks.len() - 1)
+    }
 }
 
 impl<Store: BitVec> BitVec for JacobsonRank<Store> {
@@ -129,7 +446,12 @@ impl<Store: SpaceUsage> SpaceUsage for JacobsonRank<Store> {
 
 #[cfg(test)]
 mod test {
+    use std::ptr;
+
     use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use internal::test_util::{random_bits, random_bools, Lcg};
+    use quickcheck::quickcheck;
     use rank::BitRankSupport;
 
     #[test]
@@ -155,6 +477,447 @@ mod test {
         assert_eq!(4096, rank.rank1(1024 * 32 - 1));
     }
 
+    fn naive_rank_prop<Block: BlockType>(bits: Vec<bool>) -> bool {
+        let mut bit_vector = BitVector::<Block>::new();
+        for &bit in &bits {
+            bit_vector.push_bit(bit);
+        }
+
+        let rank = JacobsonRank::new(bit_vector);
+        let mut expected = 0;
+
+        bits.iter().enumerate().all(|(i, &bit)| {
+            if bit { expected += 1; }
+            rank.rank1(i as u64) == expected
+        })
+    }
+
+    #[test]
+    fn naive_rank_qc() {
+        quickcheck(naive_rank_prop::<u8> as fn(Vec<bool>) -> bool);
+        quickcheck(naive_rank_prop::<u32> as fn(Vec<bool>) -> bool);
+        quickcheck(naive_rank_prop::<u64> as fn(Vec<bool>) -> bool);
+        quickcheck(naive_rank_prop::<u128> as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn naive_rank_lengths() {
+        for &len in &[ 1, 7, 8, 9, 63, 64, 65, 1000, 4097 ] {
+            let bits = (0 .. len).map(|i| (i * 7919) % 13 < 5).collect();
+            assert!(naive_rank_prop::<u32>(bits));
+        }
+    }
+
+    fn ranks_for<Block: BlockType>(pattern: &[bool]) -> Vec<u64> {
+        let mut bit_vector = BitVector::<Block>::new();
+        for &bit in pattern {
+            bit_vector.push_bit(bit);
+        }
+        let rank = JacobsonRank::new(bit_vector);
+        (0 .. pattern.len() as u64).map(|i| rank.rank1(i)).collect()
+    }
+
+    // The block width changes the table layout, but never the answers.
+    #[test]
+    fn rank_independent_of_block_width() {
+        for &len in &[1, 15, 16, 17, 255, 256, 257, 5000] {
+            let pattern: Vec<bool> = (0 .. len).map(|i| (i * i + 3 * i) % 7 < 3).collect();
+            let expected = ranks_for::<u64>(&pattern);
+
+            assert_eq!(expected, ranks_for::<u8>(&pattern));
+            assert_eq!(expected, ranks_for::<u16>(&pattern));
+            assert_eq!(expected, ranks_for::<u32>(&pattern));
+            assert_eq!(expected, ranks_for::<usize>(&pattern));
+            assert_eq!(expected, ranks_for::<u128>(&pattern));
+        }
+    }
+
+    #[test]
+    fn owned_and_borrowed() {
+        let pattern: Vec<bool> = (0 .. 3000).map(|i| (i * 7 + i / 13) % 5 < 2).collect();
+        let bits = BitVector::<u32>::from_bits(&pattern);
+
+        let borrowed = JacobsonRank::new(&bits);
+        let owned = JacobsonRank::new(bits.clone());
+        for i in 0 .. pattern.len() as u64 {
+            assert_eq!(owned.rank1(i), borrowed.rank1(i));
+        }
+        assert_eq!(owned.total_ones(), borrowed.total_ones());
+
+        assert!(::std::ptr::eq(&bits, *borrowed.inner()));
+        assert!(::std::ptr::eq(&bits, borrowed.into_inner()));
+        assert_eq!(bits, owned.into_inner());
+    }
+
+    #[test]
+    fn rank_checked() {
+        let vec = vec![ 0b00000000000001110000000000000001u32; 3 ];
+        let rank = JacobsonRank::new(vec);
+
+        assert_eq!(Some(12), rank.rank_checked(95, true));
+        assert_eq!(Some(84), rank.rank_checked(95, false));
+        assert_eq!(None, rank.rank_checked(96, true));
+        assert_eq!(None, rank.rank_checked(96, false));
+        assert_eq!(Some(12), rank.rank1_checked(95));
+        assert_eq!(None, rank.rank1_checked(96));
+    }
+
+    fn rank_range_prop(bits: Vec<bool>, a: u64, b: u64) -> bool {
+        let mut bit_vector = BitVector::<u8>::new();
+        for &bit in &bits {
+            bit_vector.push_bit(bit);
+        }
+        let rank = JacobsonRank::new(bit_vector);
+
+        let len = bits.len() as u64;
+        let (start, end) = if len == 0 { (0, 0) } else { (a % len, b % (len + 1)) };
+        let expected = if start < end {
+            bits[start as usize .. end as usize].iter().filter(|&&bit| bit).count() as u64
+        } else {
+            0
+        };
+
+        rank.rank_range(start .. end) == expected
+            && (len == 0 || rank.rank_range(0 .. len) == rank.rank1(len - 1))
+    }
+
+    fn rank0_range_prop(bits: Vec<bool>, a: u64, b: u64) -> bool {
+        let mut bit_vector = BitVector::<u8>::new();
+        for &bit in &bits {
+            bit_vector.push_bit(bit);
+        }
+        let rank = JacobsonRank::new(bit_vector);
+
+        let len = bits.len() as u64;
+        let (start, end) = if len == 0 { (0, 0) } else { (a % len, b % (len + 1)) };
+        let expected = if start < end {
+            bits[start as usize .. end as usize].iter().filter(|&&bit| !bit).count() as u64
+        } else {
+            0
+        };
+
+        rank.rank0_range(start .. end) == expected
+    }
+
+    #[test]
+    fn rank0_range_qc() {
+        quickcheck(rank0_range_prop as fn(Vec<bool>, u64, u64) -> bool);
+    }
+
+    #[test]
+    fn rank0_range() {
+        let vec = vec![ 0b00000000000001110000000000000001u32; 3 ];
+        let rank = JacobsonRank::new(vec);
+
+        assert_eq!(84, rank.rank0_range(0 .. 96));
+        assert_eq!(0, rank.rank0_range(0 .. 1));
+        assert_eq!(15, rank.rank0_range(1 .. 16));
+        assert_eq!(13, rank.rank0_range(16 .. 32));
+        assert_eq!(74, rank.rank0_range(5 .. 90));
+        assert_eq!(0, rank.rank0_range(96 .. 96));
+        let (start, end) = (40, 20);
+        assert_eq!(0, rank.rank0_range(start .. end));
+    }
+
+    #[test]
+    fn rank_range_qc() {
+        quickcheck(rank_range_prop as fn(Vec<bool>, u64, u64) -> bool);
+    }
+
+    #[test]
+    fn rank_range() {
+        let vec = vec![ 0b00000000000001110000000000000001u32; 3 ];
+        let rank = JacobsonRank::new(vec);
+
+        assert_eq!(12, rank.rank_range(0 .. 96));
+        assert_eq!(1, rank.rank_range(0 .. 1));
+        assert_eq!(0, rank.rank_range(1 .. 16));
+        assert_eq!(3, rank.rank_range(16 .. 32));
+        assert_eq!(4, rank.rank_range(16 .. 33));
+        assert_eq!(0, rank.rank_range(0 .. 0));
+        assert_eq!(0, rank.rank_range(96 .. 96));
+        let (start, end) = (40, 20);
+        assert_eq!(0, rank.rank_range(start .. end));
+    }
+
+    #[test]
+    fn total_ones() {
+        let empty = JacobsonRank::new(BitVector::<u32>::new());
+        assert_eq!(0, empty.total_ones());
+        assert_eq!(0, empty.total_zeros());
+
+        let full = JacobsonRank::new(BitVector::<u32>::with_fill(1000, true));
+        assert_eq!(1000, full.total_ones());
+        assert_eq!(0, full.total_zeros());
+
+        let zeros = JacobsonRank::new(BitVector::<u8>::with_fill(77, false));
+        assert_eq!(0, zeros.total_ones());
+        assert_eq!(77, zeros.total_zeros());
+
+        for len in 0 .. 300 {
+            let rank = JacobsonRank::new(vec![ 0b10010110u8; len ]);
+            assert_eq!(4 * len as u64, rank.total_ones());
+            if len > 0 {
+                assert_eq!(rank.rank1(8 * len as u64 - 1), rank.total_ones());
+            }
+        }
+    }
+
+    #[test]
+    fn from_blocks() {
+        let mut lcg = Lcg::new(3);
+        let words: Vec<u32> = (0 .. 500).map(|_| (lcg.next_u64() >> 32) as u32)
+                                        .collect();
+
+        for &len in &[0, 1, 31, 32, 1000, 15_997, 16_000] {
+            let mut bits = BitVector::from(words.clone());
+            bits.truncate(len);
+            let expected = JacobsonRank::new(&bits);
+
+            let mut source = words.iter().cloned();
+            let rank = JacobsonRank::from_blocks(source.by_ref(), len, &bits);
+            assert_eq!(words.len() - bits.block_len(), source.count());
+
+            assert_eq!(expected.superblock_counts(), rank.superblock_counts());
+            assert_eq!(expected.block_counts(), rank.block_counts());
+            for i in 0 .. len {
+                assert_eq!(expected.rank1(i), rank.rank1(i));
+            }
+
+            // The blocks aren’t retained: the heap holds only the tables,
+            // and the bits are the ones passed in.
+            assert_eq!(rank.superblock_counts().heap_bytes()
+                           + rank.block_counts().heap_bytes(),
+                       rank.heap_bytes());
+            assert!(ptr::eq(&bits, rank.into_inner()));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_blocks_too_few() {
+        JacobsonRank::from_blocks(vec![!0u8; 3], 25,
+                                  BitVector::<u8>::with_fill(25, true));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_blocks_length_mismatch() {
+        JacobsonRank::from_blocks(vec![!0u8; 4], 25,
+                                  BitVector::<u8>::with_fill(24, true));
+    }
+
+    #[test]
+    fn rank1_batch() {
+        let bits: BitVector<u64> = (0 .. 5000).map(|i| i % 3 == 0 || i % 7 == 1)
+                                              .collect();
+        let rank = JacobsonRank::new(&bits);
+
+        let mut lcg = Lcg::new(7);
+        let mut positions = Vec::new();
+        for _ in 0 .. 2000 {
+            positions.push((lcg.next_u64() >> 33) % bits.bit_len());
+        }
+        // Duplicates, including adjacent ones and the ends.
+        positions.extend_from_slice(&[0, 0, 4999, 17, 4999, 17, 18]);
+
+        let expected: Vec<u64> = positions.iter().map(|&i| rank.rank1(i))
+                                          .collect();
+        assert_eq!(expected, rank.rank1_batch(&positions));
+
+        // The default implementation.
+        assert_eq!(vec![3, 1, 2, 3, 1],
+                   0b1011u64.rank1_batch(&[3, 0, 1, 63, 0]));
+
+        assert!(rank.rank1_batch(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn rank1_batch_oob() {
+        let rank = JacobsonRank::new(BitVector::<u8>::with_fill(10, true));
+        rank.rank1_batch(&[3, 10]);
+    }
+
+    #[test]
+    fn exposed_tables() {
+        let bits = random_bits::<u32>(20_000, 64, 99);
+
+        let rank = JacobsonRank::new(&bits);
+        let superblock_size = rank.superblock_size() as u64;
+        let per_superblock = superblock_size / 32;
+        let superblocks = rank.superblock_counts();
+        let blocks = rank.block_counts();
+        assert_eq!(0, superblock_size % 32);
+        assert_eq!(bits.block_len() as u64 + 1, blocks.len());
+
+        for k in 1 .. superblocks.len() - 1 {
+            let boundary = k * superblock_size;
+            assert_eq!(rank.rank1(boundary - 1), superblocks.get(k));
+            assert_eq!(0, blocks.get(k * per_superblock));
+        }
+
+        for i in 1 .. bits.block_len() as u64 {
+            let superblock = i / per_superblock;
+            assert_eq!(rank.rank1(32 * i - 1),
+                       superblocks.get(superblock) + blocks.get(i));
+        }
+
+        assert_eq!(rank.total_ones(), superblocks.get(superblocks.len() - 1));
+    }
+
+    fn assert_same_ranks<Block: BlockType>(expected: &JacobsonRank<BitVector<Block>>,
+                                           actual: &JacobsonRank<BitVector<Block>>) {
+        assert_eq!(expected.bit_len(), actual.bit_len());
+        assert_eq!(expected.total_ones(), actual.total_ones());
+        for i in 0 .. expected.bit_len() {
+            assert_eq!(expected.rank1(i), actual.rank1(i));
+        }
+    }
+
+    #[test]
+    fn write_read() {
+        let pattern = random_bools(3001, 64, 17);
+
+        for &len in &[0, 1, 64, 1000, 3001] {
+            let bits: BitVector<u32> = pattern[.. len].iter().cloned().collect();
+            let rank = JacobsonRank::new(bits);
+
+            let mut buffer = Vec::new();
+            rank.write_to(&mut buffer).unwrap();
+            let loaded: JacobsonRank<BitVector<u32>> =
+                JacobsonRank::read_from(&mut &*buffer).unwrap();
+            assert_same_ranks(&rank, &loaded);
+            assert!(rank.large_block_ranks == loaded.large_block_ranks);
+            assert!(rank.small_block_ranks == loaded.small_block_ranks);
+
+            let mut bits_only = Vec::new();
+            rank.inner().write_to(&mut bits_only).unwrap();
+            assert!(bits_only.len() < buffer.len());
+            let rebuilt: JacobsonRank<BitVector<u32>> =
+                JacobsonRank::read_from_rebuilt(&mut &*bits_only).unwrap();
+            assert_same_ranks(&rank, &rebuilt);
+        }
+    }
+
+    #[test]
+    fn read_mismatched_tables() {
+        use std::io::ErrorKind;
+
+        let rank = JacobsonRank::new(BitVector::<u64>::with_fill(5000, true));
+        let mut buffer = Vec::new();
+        rank.write_to(&mut buffer).unwrap();
+
+        let read = |bytes: &[u8]| {
+            JacobsonRank::<BitVector<u64>>::read_from(&mut &*bytes).map(|_| ())
+        };
+        assert!(read(&buffer).is_ok());
+
+        // Tables built for a different bit length.
+        let mut other = Vec::new();
+        BitVector::<u64>::with_fill(100, true).write_to(&mut other).unwrap();
+        other.extend_from_slice(&buffer[8 + 5000 / 8 ..]);
+        assert_eq!(ErrorKind::InvalidData, read(&other).unwrap_err().kind());
+
+        // Tables for another block type.
+        let mut narrow = Vec::new();
+        JacobsonRank::new(BitVector::<u8>::with_fill(5000, true))
+            .write_to(&mut narrow).unwrap();
+        assert_eq!(ErrorKind::InvalidData, read(&narrow).unwrap_err().kind());
+
+        assert_eq!(ErrorKind::UnexpectedEof,
+                   read(&buffer[.. buffer.len() - 1]).unwrap_err().kind());
+    }
+
+    // Tables written by `write_to` always pass the checks in `read_from`.
+    fn write_read_prop(bits: Vec<bool>) -> bool {
+        fn round_trip<Block: BlockType>(bits: &[bool]) -> bool {
+            let rank = JacobsonRank::new(BitVector::<Block>::from_bits(bits));
+            let mut buffer = Vec::new();
+            rank.write_to(&mut buffer).unwrap();
+            JacobsonRank::<BitVector<Block>>::read_from(&mut &*buffer).is_ok()
+        }
+        round_trip::<u8>(&bits) && round_trip::<u64>(&bits)
+    }
+
+    #[test]
+    fn write_read_qc() {
+        quickcheck(write_read_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn read_corrupt_tables() {
+        use std::io::ErrorKind;
+        use int_vec::IntVecMut;
+
+        let bits: BitVector<u32> = (0 .. 5000).map(|i| i % 3 == 0).collect();
+        let rank = JacobsonRank::new(bits);
+
+        // Writes `rank` with the given changes to its tables.
+        let write = |large: &dyn Fn(&mut IntVector<u64>),
+                     small: &dyn Fn(&mut IntVector<u64>)| {
+            let mut large_block_ranks = rank.large_block_ranks.clone();
+            let mut small_block_ranks = rank.small_block_ranks.clone();
+            large(&mut large_block_ranks);
+            small(&mut small_block_ranks);
+
+            let mut buffer = Vec::new();
+            rank.inner().write_to(&mut buffer).unwrap();
+            buffer.write_u64::<LittleEndian>(rank.large_block_size as u64).unwrap();
+            write_table(&large_block_ranks, &mut buffer).unwrap();
+            write_table(&small_block_ranks, &mut buffer).unwrap();
+            buffer
+        };
+        let read = |bytes: Vec<u8>| {
+            JacobsonRank::<BitVector<u32>>::read_from(&mut &*bytes).map(|_| ())
+        };
+        let unchanged = |_: &mut IntVector<u64>| ();
+
+        assert!(read(write(&unchanged, &unchanged)).is_ok());
+
+        type Corrupt<'a> = &'a dyn Fn(&mut IntVector<u64>);
+        let corruptions: Vec<(Corrupt, Corrupt)> = vec![
+            // Superblock counts that don’t start at 0, decrease, or grow
+            // by more than a superblock.
+            (&|large| large.set(0, 1), &unchanged),
+            (&|large| large.set(2, large.get(1) - 1), &unchanged),
+            (&|large| large.set(1, 0), &unchanged),
+            (&|large| { let last = large.get(1) + 1000; large.set(1, last) }, &unchanged),
+            // Block counts that don’t start a superblock at 0, decrease,
+            // or grow by more than a block.
+            (&unchanged, &|small| small.set(0, 1)),
+            (&unchanged, &|small| small.set(2, 0)),
+            (&unchanged, &|small| { let rank = small.get(1) + 33; small.set(1, rank) }),
+            // A total that disagrees with the last superblock.
+            (&|large| { let last = large.len() - 1; large.set(last, large.get(last) - 1) },
+             &unchanged),
+            (&unchanged, &|small| { let last = small.len() - 1; small.set(last, 0) }),
+        ];
+
+        for (large, small) in corruptions {
+            assert_eq!(ErrorKind::InvalidData,
+                       read(write(large, small)).unwrap_err().kind());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn from_bits_parallel() {
+        let bits = random_bits::<u64>(1_000_003, 128, 12345);
+
+        let sequential = JacobsonRank::new(bits.clone());
+        let parallel = JacobsonRank::from_bits_parallel(bits.clone());
+
+        assert_eq!(sequential.large_block_size, parallel.large_block_size);
+        assert!(sequential.large_block_ranks == parallel.large_block_ranks);
+        assert!(sequential.small_block_ranks == parallel.small_block_ranks);
+
+        for i in (0 .. bits.bit_len()).step_by(997) {
+            assert_eq!(sequential.rank1(i), parallel.rank1(i));
+        }
+        assert_eq!(sequential.total_ones(), parallel.total_ones());
+    }
+
     // This test is a sanity check that we aren’t taking up too much
     // space with the metadata.
     #[test]
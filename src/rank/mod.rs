@@ -6,6 +6,12 @@ pub use self::jacobson::*;
 mod rank9;
 pub use self::rank9::*;
 
+mod interleaved;
+pub use self::interleaved::*;
+
+mod hash;
+pub use self::hash::*;
+
 mod traits;
 pub use self::traits::*;
 
@@ -32,6 +32,7 @@ impl_rank_support_prim!(u8);
 impl_rank_support_prim!(u16);
 impl_rank_support_prim!(u32);
 impl_rank_support_prim!(u64);
+impl_rank_support_prim!(u128);
 impl_rank_support_prim!(usize);
 
 #[cfg(test)]
@@ -70,6 +71,27 @@ mod test {
         assert_eq!(2, 0b01010101u8.rank0(3));
     }
 
+    #[test]
+    fn rank_last() {
+        assert_eq!(4, 0b01010101u8.rank1(7));
+        assert_eq!(4, 0b01010101u8.rank0(7));
+        assert_eq!(Some(4), 0b01010101u8.rank_checked(7, true));
+        assert_eq!(Some(4), 0b01010101u8.rank_checked(7, false));
+    }
+
+    #[test]
+    fn rank_checked_oob() {
+        assert_eq!(None, 0b01010101u8.rank_checked(8, true));
+        assert_eq!(None, 0b01010101u8.rank_checked(8, false));
+        assert_eq!(None, 0u32.rank_checked(!0, true));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rank0_oob() {
+        0b01010101u8.rank0(8);
+    }
+
     #[test]
     fn rank() {
         assert_eq!(1, 0b00000000u8.rank(0, false));
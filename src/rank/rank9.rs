@@ -19,14 +19,17 @@ struct Rank9Cell {
     level2: Level2,
 }
 
+// Seven 9-bit counts packed into a word: entry `t` for `t` in `1 .. 8`
+// is the number of 1s in the first `t` words of a basic block, and
+// entry 0 is always 0.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
-struct Level2(u64);
+pub(super) struct Level2(pub(super) u64);
 
 impl Level2 {
-    fn new() -> Self { Level2(0) }
+    pub(super) fn new() -> Self { Level2(0) }
 
-    fn get(&self, t: usize) -> u64 {
+    pub(super) fn get(&self, t: usize) -> u64 {
         debug_assert!(t < 8);
 
         let t = t.wrapping_sub(1);
@@ -34,7 +37,7 @@ impl Level2 {
         self.0 >> shift & 0x1FF
     }
 
-    fn set(&mut self, t: usize, value: u64) {
+    pub(super) fn set(&mut self, t: usize, value: u64) {
         debug_assert!(t < 8);
 
         let t = t.wrapping_sub(1);
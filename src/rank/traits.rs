@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 /// Supports fast rank queries.
 ///
 /// Associated type `Over` gives the type that we can query about. For
@@ -14,19 +16,43 @@ pub trait RankSupport {
     ///
     /// # Panics
     ///
-    /// Panics if `position >= self.limit()`.
+    /// Panics if `position >= self.limit()`. Every position of an empty
+    /// vector is out of bounds; use `rank_checked` or, for bits,
+    /// `total_ones` and `rank_range`, which are defined there.
     fn rank(&self, position: u64, value: Self::Over) -> u64;
 
+    /// Returns the rank of the given value at a given position, or
+    /// `None` if `position >= self.limit()`.
+    ///
+    /// In particular, `rank_checked(self.limit() - 1, value)` is the
+    /// total number of occurrences of `value`, and never panics for a
+    /// non-empty vector.
+    fn rank_checked(&self, position: u64, value: Self::Over) -> Option<u64> {
+        if position < self.limit() {
+            Some(self.rank(position, value))
+        } else {
+            None
+        }
+    }
+
     /// The size of the vector being ranked.
     fn limit(&self) -> u64;
 }
 
 /// Supports fast rank queries over `bool`s.
+///
+/// The trait is object safe, so rank structures of different types can
+/// be stored together as `Box<dyn BitRankSupport>`.
 pub trait BitRankSupport: RankSupport<Over = bool> {
     /// Returns the rank of 1 at the given position.
     ///
     /// This is the number of occurrences of 1 up to and including that
     /// position.
+    ///
+    /// # Panics
+    ///
+    /// Panics (or, for some implementations, returns garbage) if
+    /// `position >= self.limit()`.
     fn rank1(&self, position: u64) -> u64 {
         self.rank(position, true)
     }
@@ -35,7 +61,162 @@ pub trait BitRankSupport: RankSupport<Over = bool> {
     ///
     /// This is the number of occurrences of 0 up to and including that
     /// position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.limit()`.
     fn rank0(&self, position: u64) -> u64 {
+        assert!(position < self.limit(), "BitRankSupport::rank0: out of bounds");
         position + 1 - self.rank1(position)
     }
+
+    /// Returns the rank of 1 at the given position, or `None` if
+    /// `position >= self.limit()`.
+    ///
+    /// This is `rank_checked(position, true)`, for callers that only
+    /// rank 1s.
+    fn rank1_checked(&self, position: u64) -> Option<u64> {
+        if position < self.limit() {
+            Some(self.rank1(position))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the total number of 1s.
+    ///
+    /// Unlike `rank1(self.limit() - 1)`, this is defined for empty
+    /// vectors, for which it returns 0. The default implementation does
+    /// exactly that; implementations that keep a running total should
+    /// override it.
+    fn total_ones(&self) -> u64 {
+        if self.limit() == 0 { 0 } else { self.rank1(self.limit() - 1) }
+    }
+
+    /// Returns the total number of 0s.
+    fn total_zeros(&self) -> u64 {
+        self.limit() - self.total_ones()
+    }
+
+    /// Returns the number of 1s in the half-open interval `range`.
+    ///
+    /// Empty (or backwards) ranges have no 1s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is non-empty and `range.end > self.limit()`.
+    fn rank_range(&self, range: Range<u64>) -> u64 {
+        if range.start >= range.end { return 0; }
+
+        let upto_end = self.rank1(range.end - 1);
+        let before_start = if range.start == 0 {
+            0
+        } else {
+            self.rank1(range.start - 1)
+        };
+
+        upto_end - before_start
+    }
+
+    /// Returns the number of 0s in the half-open interval `range`.
+    ///
+    /// Empty (or backwards) ranges have no 0s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is non-empty and `range.end > self.limit()`.
+    fn rank0_range(&self, range: Range<u64>) -> u64 {
+        if range.start >= range.end { return 0; }
+        (range.end - range.start) - self.rank_range(range)
+    }
+
+    /// Returns the rank of 1 at each of the given positions, in the same
+    /// order.
+    ///
+    /// The default implementation calls `rank1` for each position;
+    /// implementations may reorder the queries for better locality.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any position is not less than `self.limit()`.
+    fn rank1_batch(&self, positions: &[u64]) -> Vec<u64> {
+        positions.iter().map(|&position| self.rank1(position)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVector;
+    use rank::{JacobsonRank, Rank9};
+    use rrr::RrrVector;
+    use select::{BinSearchSelect, Select1Support};
+    use sparse::SparseBitVector;
+
+    fn pattern() -> BitVector<u64> {
+        (0 .. 2000).map(|i| i % 13 == 0 || i % 7 == 3).collect()
+    }
+
+    #[test]
+    fn boxed_rank() {
+        let bits = pattern();
+        let structures: Vec<Box<dyn BitRankSupport>> = vec![
+            Box::new(JacobsonRank::new(bits.clone())),
+            Box::new(Rank9::new(bits.clone())),
+            Box::new(SparseBitVector::new(&bits)),
+            Box::new(RrrVector::new(&bits, 15)),
+            Box::new(0x8000_0000_0000_0001u64),
+        ];
+
+        let expected = JacobsonRank::new(&bits);
+        for rank in &structures[.. 4] {
+            assert_eq!(2000, rank.limit());
+            assert_eq!(expected.total_ones(), rank.total_ones());
+            assert_eq!(expected.rank_range(100 .. 900), rank.rank_range(100 .. 900));
+            assert_eq!(None, rank.rank_checked(2000, true));
+            for i in (0 .. 2000).step_by(7) {
+                assert_eq!(expected.rank1(i), rank.rank1(i));
+                assert_eq!(expected.rank0(i), rank.rank0(i));
+                assert_eq!(expected.rank(i, false), rank.rank(i, false));
+            }
+            assert_eq!(expected.rank1_batch(&[5, 1999, 0]), rank.rank1_batch(&[5, 1999, 0]));
+        }
+
+        assert_eq!(64, structures[4].limit());
+        assert_eq!(2, structures[4].total_ones());
+    }
+
+    #[test]
+    fn dyn_bits_store() {
+        use bit_vec::BitVec;
+
+        // `BitVec` is object safe once its block type is fixed, so a rank
+        // structure can be built over bits of an erased type.
+        let dense = pattern();
+        let blocks: Vec<u64> = vec![0x0F0F, !0, 1];
+        let stores: Vec<&dyn BitVec<Block = u64>> = vec![&dense, &blocks];
+
+        let ranks: Vec<JacobsonRank<&dyn BitVec<Block = u64>>> =
+            stores.into_iter().map(JacobsonRank::new).collect();
+        assert_eq!(JacobsonRank::new(&dense).total_ones(), ranks[0].total_ones());
+        assert_eq!(8 + 64 + 1, ranks[1].total_ones());
+        assert_eq!(8, ranks[1].rank1(63));
+    }
+
+    #[test]
+    fn boxed_select() {
+        let bits = pattern();
+        let structures: Vec<Box<dyn Select1Support>> = vec![
+            Box::new(BinSearchSelect::new(JacobsonRank::new(bits.clone()))),
+            Box::new(SparseBitVector::new(&bits)),
+        ];
+
+        let ones: Vec<u64> = (0 .. 2000).filter(|i| i % 13 == 0 || i % 7 == 3).collect();
+        for select in &structures {
+            for (index, &position) in ones.iter().enumerate() {
+                assert_eq!(Some(position), select.select1(index as u64));
+            }
+            assert_eq!(None, select.select1(ones.len() as u64));
+        }
+    }
 }
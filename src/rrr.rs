@@ -0,0 +1,420 @@
+//! RRR-compressed bit vectors.
+//!
+//! An [`RrrVector`](struct.RrrVector.html) splits its bits into
+//! fixed-size blocks and stores each block as a *class*, its number of
+//! 1 bits, and an *offset*, its index among all blocks of that class.
+//! Offsets take `ceil(lg(C(b, class)))` bits, so blocks that are nearly
+//! all 0s (or all 1s) take almost no space, and the total size
+//! approaches the zeroth-order entropy of the bits.
+
+use std::cmp;
+
+use num_traits::ToPrimitive;
+
+use bit_vec::{BitVec, BitVecMut, BitVector};
+use error::{Error, Result};
+use int_vec::{IntVec, IntVector};
+use rank::{BitRankSupport, RankSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// The largest supported block size.
+pub const MAX_BLOCK_SIZE: usize = 63;
+
+// The number of blocks between rank and offset samples.
+const SAMPLE_RATE: usize = 32;
+
+// BINOMIAL[n][k] is n choose k.
+static BINOMIAL: [[u64; 64]; 64] = binomial_table();
+
+const fn binomial_table() -> [[u64; 64]; 64] {
+    let mut table = [[0; 64]; 64];
+    let mut n = 0;
+    while n < 64 {
+        table[n][0] = 1;
+        let mut k = 1;
+        while k <= n {
+            table[n][k] = table[n - 1][k - 1] + table[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+/// A bit vector compressed with the Raman–Raman–Rao encoding, supporting
+/// `get_bit` and rank queries without decompressing.
+///
+/// Construct with `RrrVector::new`.
+#[derive(Clone, Debug)]
+pub struct RrrVector {
+    len: u64,
+    block_size: usize,
+    classes: IntVector<u64>,
+    offsets: BitVector<u64>,
+    sample_ranks: IntVector<u64>,
+    sample_offsets: IntVector<u64>,
+}
+
+impl RrrVector {
+    /// Compresses the bits of `bits` using blocks of `block_size` bits.
+    ///
+    /// Larger blocks compress better but make each query decode more
+    /// bits; 15 and 63 are typical choices.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= block_size <= MAX_BLOCK_SIZE`.
+    pub fn new<Bits: BitVec + ?Sized>(bits: &Bits, block_size: usize) -> Self {
+        assert!((1 ..= MAX_BLOCK_SIZE).contains(&block_size),
+                "RrrVector::new: bad block size");
+
+        let len = bits.bit_len();
+        let block_count = len.ceil_div(block_size as u64);
+        let sample_count = block_count.ceil_div(SAMPLE_RATE as u64);
+
+        let class_bits = (block_size as u64 + 1).ceil_lg();
+        let meta_bits = cmp::max(1, (len + 1).ceil_lg());
+
+        let mut classes = IntVector::with_capacity(class_bits, block_count);
+        let mut offsets = BitVector::new();
+        let mut sample_ranks = IntVector::with_capacity(meta_bits, sample_count);
+        let mut sample_offsets = IntVector::with_capacity(meta_bits, sample_count);
+
+        let mut rank: u64 = 0;
+        let mut start: u64 = 0;
+        let mut sample_index: usize = 0;
+
+        while start < len {
+            if sample_index == 0 {
+                sample_ranks.push(rank);
+                sample_offsets.push(offsets.bit_len());
+            }
+
+            let count = cmp::min(block_size as u64, len - start) as usize;
+            let block = read_bits(bits, start, count);
+            let class = block.count_ones() as usize;
+            classes.push(class as u64);
+
+            let width = offset_bits(block_size, class);
+            if width > 0 {
+                let position = offsets.bit_len();
+                offsets.resize(position + width as u64, false);
+                offsets.set_bits(position, width, encode(block));
+            }
+
+            rank += class as u64;
+            start += count as u64;
+            sample_index += 1;
+
+            if sample_index == SAMPLE_RATE {
+                sample_index = 0;
+            }
+        }
+
+        offsets.shrink_to_fit();
+
+        RrrVector {
+            len,
+            block_size,
+            classes,
+            offsets,
+            sample_ranks,
+            sample_offsets,
+        }
+    }
+
+    /// Compresses the bits of `bits` like `new`, or fails with
+    /// `Error::InvalidWidth` unless `1 <= block_size <= MAX_BLOCK_SIZE`.
+    pub fn try_new<Bits: BitVec + ?Sized>(bits: &Bits, block_size: usize)
+                                          -> Result<Self> {
+        if (1 ..= MAX_BLOCK_SIZE).contains(&block_size) {
+            Ok(RrrVector::new(bits, block_size))
+        } else {
+            Err(Error::InvalidWidth)
+        }
+    }
+
+    /// The number of bits per block.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    // Returns the number of 1s before block `index`, and the decoded
+    // block itself.
+    fn locate(&self, index: usize) -> (u64, u64) {
+        let sample = index / SAMPLE_RATE;
+        let mut rank = self.sample_ranks.get(sample as u64);
+        let mut position = self.sample_offsets.get(sample as u64);
+
+        for i in sample * SAMPLE_RATE .. index {
+            let class = self.classes.get(i as u64) as usize;
+            rank += class as u64;
+            position += offset_bits(self.block_size, class) as u64;
+        }
+
+        let class = self.classes.get(index as u64) as usize;
+        let width = offset_bits(self.block_size, class);
+        let offset = if width == 0 {
+            0
+        } else {
+            self.offsets.get_bits(position, width)
+        };
+
+        (rank, decode(self.block_size, class, offset))
+    }
+}
+
+// The number of bits needed for the offset of a block of class `class`.
+fn offset_bits(block_size: usize, class: usize) -> usize {
+    BINOMIAL[block_size][class].ceil_lg()
+}
+
+// Ranks `block` among the blocks with the same number of 1s, using the
+// combinatorial number system: the 1s at positions p₁ < p₂ < ... < p_c
+// give offset C(p₁, 1) + C(p₂, 2) + ... + C(p_c, c).
+fn encode(mut block: u64) -> u64 {
+    let mut offset = 0;
+    let mut k = 0;
+
+    while block != 0 {
+        let position = block.trailing_zeros() as usize;
+        k += 1;
+        offset += BINOMIAL[position][k];
+        block &= block - 1;
+    }
+
+    offset
+}
+
+// Inverts `encode`, greedily finding the highest 1 first.
+fn decode(block_size: usize, class: usize, mut offset: u64) -> u64 {
+    let mut block = 0;
+    let mut position = block_size;
+
+    for k in (1 ..= class).rev() {
+        position -= 1;
+        while BINOMIAL[position][k] > offset {
+            position -= 1;
+        }
+        block |= 1 << position;
+        offset -= BINOMIAL[position][k];
+    }
+
+    block
+}
+
+// Reads `count <= 64` bits starting at `start`, whatever the source’s
+// block size.
+fn read_bits<Bits: BitVec + ?Sized>(bits: &Bits, start: u64, count: usize)
+                                    -> u64 {
+    let chunk = Bits::Block::nbits();
+    let mut result = 0;
+    let mut done = 0;
+
+    while done < count {
+        let n = cmp::min(chunk, count - done);
+        let piece = bits.get_bits(start + done as u64, n).to_u64().unwrap();
+        result |= piece << done;
+        done += n;
+    }
+
+    result
+}
+
+impl BitVec for RrrVector {
+    type Block = u64;
+
+    fn bit_len(&self) -> u64 {
+        self.len
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.len, "RrrVector::get_bit: out of bounds");
+
+        let block_size = self.block_size as u64;
+        let (_, block) = self.locate((position / block_size) as usize);
+        block.get_bit((position % block_size) as usize)
+    }
+
+    fn get_block(&self, position: usize) -> u64 {
+        let start = u64::mul_nbits(position);
+        assert!(start < self.len, "RrrVector::get_block: out of bounds");
+
+        let limit = cmp::min(start + 64, self.len);
+        let block_size = self.block_size as u64;
+        let mut result = 0;
+        let mut current = start;
+
+        while current < limit {
+            let bit = (current % block_size) as usize;
+            let (_, block) = self.locate((current / block_size) as usize);
+            let count = cmp::min(block_size - bit as u64, limit - current);
+            result |= block.get_bits(bit, count as usize) << (current - start);
+            current += count;
+        }
+
+        result
+    }
+}
+
+impl RankSupport for RrrVector {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl BitRankSupport for RrrVector {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.len, "RrrVector::rank1: out of bounds");
+
+        let block_size = self.block_size as u64;
+        let (rank, block) = self.locate((position / block_size) as usize);
+        let bit = (position % block_size) as usize;
+
+        rank + (block & u64::low_mask(bit + 1)).count_ones() as u64
+    }
+}
+
+impl SpaceUsage for RrrVector {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.classes.heap_bytes()
+            + self.offsets.heap_bytes()
+            + self.sample_ranks.heap_bytes()
+            + self.sample_offsets.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVecPush;
+    use internal::test_util::random_bits;
+    use quickcheck::quickcheck;
+
+    fn check_against<Bits: BitVec<Block = u32>>(bits: &Bits, block_size: usize) {
+        let rrr = RrrVector::new(bits, block_size);
+        assert_eq!(bits.bit_len(), rrr.bit_len());
+
+        let mut rank = 0;
+        for i in 0 .. bits.bit_len() {
+            if bits.get_bit(i) { rank += 1; }
+            assert_eq!(bits.get_bit(i), rrr.get_bit(i));
+            assert_eq!(rank, rrr.rank1(i));
+        }
+
+        for i in 0 .. rrr.block_len() {
+            let expected = bits.get_block(2 * i) as u64
+                | (if 2 * i + 1 < bits.block_len() {
+                       (bits.get_block(2 * i + 1) as u64) << 32
+                   } else {
+                       0
+                   });
+            assert_eq!(expected, rrr.get_block(i));
+        }
+    }
+
+    #[test]
+    fn binomial() {
+        assert_eq!(1, BINOMIAL[0][0]);
+        assert_eq!(10, BINOMIAL[5][2]);
+        assert_eq!(0, BINOMIAL[5][6]);
+        assert_eq!(916312070471295267, BINOMIAL[63][31]);
+    }
+
+    #[test]
+    fn encode_decode() {
+        use std::collections::HashSet;
+
+        for &block_size in &[1, 4, 8, 11] {
+            let mut seen = HashSet::new();
+            for block in 0 .. 1u64 << block_size {
+                let class = block.count_ones() as usize;
+                let offset = encode(block);
+                assert!(offset < BINOMIAL[block_size][class]);
+                assert_eq!(block, decode(block_size, class, offset));
+                assert!(seen.insert((class, offset)));
+            }
+        }
+
+        let full = u64::low_mask(63);
+        assert_eq!(full, decode(63, 63, encode(full)));
+        assert_eq!(1 << 62, decode(63, 1, encode(1 << 62)));
+    }
+
+    #[test]
+    fn sparse_block_sizes() {
+        for &block_size in &[1, 7, 15, 31, 63] {
+            check_against(&random_bits::<u32>(3000, 26, block_size as u64), block_size);
+        }
+    }
+
+    #[test]
+    fn dense_and_edge_lengths() {
+        for &len in &[0, 1, 14, 15, 16, 63, 64, 65, 15 * 32, 15 * 32 + 1] {
+            check_against(&BitVector::<u32>::with_fill(len, true), 15);
+            check_against(&random_bits::<u32>(len, 26, len), 15);
+        }
+    }
+
+    fn matches_source_prop(bits: Vec<bool>, block_size: usize) -> bool {
+        let block_size = block_size % MAX_BLOCK_SIZE + 1;
+        let mut source = BitVector::<u8>::new();
+        for &bit in &bits {
+            source.push_bit(bit);
+        }
+
+        let rrr = RrrVector::new(&source, block_size);
+        let mut rank = 0;
+        (0 .. source.bit_len()).all(|i| {
+            if source.get_bit(i) { rank += 1; }
+            rrr.get_bit(i) == source.get_bit(i) && rrr.rank1(i) == rank
+        })
+    }
+
+    #[test]
+    fn matches_source_qc() {
+        quickcheck(matches_source_prop as fn(Vec<bool>, usize) -> bool);
+    }
+
+    #[test]
+    fn rank_checked() {
+        let rrr = RrrVector::new(&random_bits::<u32>(100, 26, 7), 15);
+        assert!(rrr.rank_checked(99, true).is_some());
+        assert!(rrr.rank_checked(100, true).is_none());
+    }
+
+    #[test]
+    fn space() {
+        let bits = random_bits::<u32>(100_000, 26, 17);
+        let rrr = RrrVector::new(&bits, 63);
+        assert!(rrr.total_bytes() < bits.total_bytes());
+
+        let rrr = RrrVector::new(&bits, 15);
+        assert!(rrr.total_bytes() < bits.total_bytes());
+    }
+
+    #[test]
+    fn try_new() {
+        let bits = random_bits::<u32>(100, 26, 3);
+        assert!(RrrVector::try_new(&bits, 1).is_ok());
+        assert!(RrrVector::try_new(&bits, MAX_BLOCK_SIZE).is_ok());
+        assert_eq!(Some(Error::InvalidWidth), RrrVector::try_new(&bits, 0).err());
+        assert_eq!(Some(Error::InvalidWidth), RrrVector::try_new(&bits, 64).err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_block_size() {
+        RrrVector::new(&random_bits::<u32>(10, 26, 1), 64);
+    }
+}
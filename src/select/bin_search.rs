@@ -1,3 +1,5 @@
+use std::cmp;
+
 use internal::search::binary_search_function;
 use rank::{BitRankSupport, RankSupport};
 use space_usage::SpaceUsage;
@@ -5,6 +7,7 @@ use bit_vec::BitVec;
 use super::{SelectSupport, Select1Support, Select0Support};
 
 /// Performs a select query by binary searching rank queries.
+#[derive(Clone, Debug)]
 pub struct BinSearchSelect<Rank> {
     rank_support: Rank,
 }
@@ -53,6 +56,7 @@ macro_rules! impl_select_support_b {
         impl<Rank: BitRankSupport>
         $select_support for BinSearchSelect<Rank> {
             fn $select(&self, index: u64) -> Option<u64> {
+                if index >= self.limit() { return None; }
                 binary_search_function(0, self.limit(), index + 1,
                                        |i| self.$rank(i))
             }
@@ -60,13 +64,43 @@ macro_rules! impl_select_support_b {
     }
 }
 
-impl_select_support_b!(Select1Support, select1, rank1);
+impl<Rank: BitRankSupport> Select1Support for BinSearchSelect<Rank> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        if index >= self.limit() { return None; }
+        binary_search_function(0, self.limit(), index + 1,
+                               |i| self.rank1(i))
+    }
+
+    // Gallops forward from the hint to bracket the answer, so the
+    // search takes time logarithmic in the distance from the hint.
+    fn select1_with_hint(&self, index: u64, hint: (u64, u64)) -> Option<u64> {
+        let (prev_index, prev_position) = hint;
+        if index >= self.total_ones() { return None; }
+        if index < prev_index { return self.select1(index); }
+        if index == prev_index { return Some(prev_position); }
+
+        let limit = self.limit();
+        let mut start = prev_position + 1;
+        let mut step = 1;
+        loop {
+            let probe = start.saturating_add(step);
+            if probe >= limit || self.rank1(probe - 1) > index {
+                return binary_search_function(start, cmp::min(probe, limit),
+                                              index + 1, |i| self.rank1(i));
+            }
+            start = probe;
+            step *= 2;
+        }
+    }
+}
+
 impl_select_support_b!(Select0Support, select0, rank0);
 
 impl<Rank: RankSupport> SelectSupport for BinSearchSelect<Rank> {
     type Over = Rank::Over;
 
     fn select(&self, index: u64, value: Rank::Over) -> Option<u64> {
+        if index >= self.limit() { return None; }
         binary_search_function(0, self.limit(), index + 1,
                                |i| self.rank(i, value))
     }
@@ -79,9 +113,90 @@ impl<Rank: SpaceUsage> SpaceUsage for BinSearchSelect<Rank> {
 
 #[cfg(test)]
 mod test {
+    use bit_vec::{BitVec, BitVecPush, BitVector};
+    use quickcheck::quickcheck;
     use rank::*;
     use select::*;
 
+    fn build(bits: &[bool]) -> BinSearchSelect<JacobsonRank<BitVector<u8>>> {
+        let mut bit_vector = BitVector::new();
+        for &bit in bits {
+            bit_vector.push_bit(bit);
+        }
+        BinSearchSelect::new(JacobsonRank::new(bit_vector))
+    }
+
+    fn select_rank_inverse_prop(bits: Vec<bool>) -> bool {
+        let select = build(&bits);
+
+        (0 .. select.bit_len()).all(|p| {
+            if select.get_bit(p) {
+                select.select1(select.rank1(p) - 1) == Some(p)
+            } else {
+                select.select0(select.rank0(p) - 1) == Some(p)
+            }
+        })
+    }
+
+    fn select_past_end_prop(bits: Vec<bool>) -> bool {
+        let select = build(&bits);
+        let ones = bits.iter().filter(|&&bit| bit).count() as u64;
+        let zeros = bits.len() as u64 - ones;
+
+        select.select1(ones).is_none() && select.select0(zeros).is_none()
+    }
+
+    #[test]
+    fn select_rank_inverse_qc() {
+        quickcheck(select_rank_inverse_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn select_past_end_qc() {
+        quickcheck(select_past_end_prop as fn(Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn select1_with_hint() {
+        let bits: Vec<bool> = (0 .. 3000).map(|i| i % 7 == 0 || i % 97 < 3).collect();
+        let select = build(&bits);
+
+        let mut hint = (0, select.select1(0).unwrap());
+        let mut index = 0;
+        while let Some(position) = select.select1(index) {
+            assert_eq!(Some(position), select.select1_with_hint(index, hint));
+            hint = (index, position);
+            index += 1;
+        }
+        assert_eq!(None, select.select1_with_hint(index, hint));
+
+        let hint = (10, select.select1(10).unwrap());
+        assert_eq!(select.select1(300), select.select1_with_hint(300, hint));
+        assert_eq!(select.select1(2), select.select1_with_hint(2, hint));
+
+        // An index with no matching 1 gives `None`, even with a hint that
+        // claims to be at that index.
+        let ones = select.total_ones();
+        let last = select.select1(ones - 1).unwrap();
+        assert_eq!(None, select.select1_with_hint(ones, (ones, last)));
+        assert_eq!(None, select.select1_with_hint(ones + 5, (ones - 1, last)));
+        assert_eq!(None, build(&[false; 10]).select1_with_hint(0, (0, 0)));
+    }
+
+    fn select1_with_hint_prop(bits: Vec<bool>, prev: u8, index: u8) -> bool {
+        let select = build(&bits);
+        match select.select1(prev as u64) {
+            None => true,
+            Some(position) => select.select1(index as u64)
+                == select.select1_with_hint(index as u64, (prev as u64, position)),
+        }
+    }
+
+    #[test]
+    fn select1_with_hint_qc() {
+        quickcheck(select1_with_hint_prop as fn(Vec<bool>, u8, u8) -> bool);
+    }
+
     #[test]
     fn select1() {
         let vec = vec![ 0b00000000000001110000000000000001u32; 1024 ];
@@ -3,5 +3,14 @@
 mod bin_search;
 pub use self::bin_search::*;
 
+mod sampled;
+pub use self::sampled::*;
+
+mod sampled0;
+pub use self::sampled0::*;
+
+mod pred_succ;
+pub use self::pred_succ::*;
+
 mod traits;
 pub use self::traits::*;
@@ -0,0 +1,142 @@
+use std::cmp;
+
+use rank::BitRankSupport;
+use super::Select1Support;
+
+/// Supports predecessor and successor queries over the 1 bits.
+///
+/// This is implemented for every type that supports both rank and
+/// select over bits.
+pub trait PredSucc {
+    /// Returns the position of the last 1 bit at or before `position`,
+    /// or `None` if there is no such bit.
+    ///
+    /// Positions past the end are treated as the last position.
+    fn predecessor(&self, position: u64) -> Option<u64>;
+
+    /// Returns the position of the first 1 bit at or after `position`,
+    /// or `None` if there is no such bit.
+    fn successor(&self, position: u64) -> Option<u64>;
+
+    /// Returns the position of the 1 bit nearest to `position`, or `None`
+    /// if there are no 1 bits.
+    ///
+    /// If the predecessor and successor are equally far away, returns
+    /// the predecessor.
+    fn select_near(&self, position: u64) -> Option<u64>;
+}
+
+impl<T: BitRankSupport + Select1Support + ?Sized> PredSucc for T {
+    fn predecessor(&self, position: u64) -> Option<u64> {
+        if self.limit() == 0 { return None; }
+
+        let position = cmp::min(position, self.limit() - 1);
+        match self.rank1(position) {
+            0    => None,
+            rank => self.select1(rank - 1),
+        }
+    }
+
+    fn successor(&self, position: u64) -> Option<u64> {
+        if position >= self.limit() { return None; }
+
+        let before = if position == 0 { 0 } else { self.rank1(position - 1) };
+        self.select1(before)
+    }
+
+    fn select_near(&self, position: u64) -> Option<u64> {
+        match (self.predecessor(position), self.successor(position)) {
+            (Some(before), Some(after)) =>
+                if after - position < position - before {
+                    Some(after)
+                } else {
+                    Some(before)
+                },
+            (before, None) => before,
+            (None, after)  => after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bit_vec::{BitVecMut, BitVector};
+    use rank::JacobsonRank;
+    use select::*;
+
+    fn build(len: u64, ones: &[u64])
+             -> BinSearchSelect<JacobsonRank<BitVector<u32>>> {
+        let mut bit_vector = BitVector::with_fill(len, false);
+        for &position in ones {
+            bit_vector.set_bit(position, true);
+        }
+        BinSearchSelect::new(JacobsonRank::new(bit_vector))
+    }
+
+    #[test]
+    fn sparse() {
+        let bits = build(100, &[3, 31, 32, 64, 98]);
+
+        assert_eq!(None, bits.predecessor(0));
+        assert_eq!(None, bits.predecessor(2));
+        assert_eq!(Some(3), bits.predecessor(3));
+        assert_eq!(Some(3), bits.predecessor(30));
+        assert_eq!(Some(31), bits.predecessor(31));
+        assert_eq!(Some(32), bits.predecessor(32));
+        assert_eq!(Some(32), bits.predecessor(63));
+        assert_eq!(Some(98), bits.predecessor(99));
+        assert_eq!(Some(98), bits.predecessor(1000));
+
+        assert_eq!(Some(3), bits.successor(0));
+        assert_eq!(Some(3), bits.successor(3));
+        assert_eq!(Some(31), bits.successor(4));
+        assert_eq!(Some(32), bits.successor(32));
+        assert_eq!(Some(64), bits.successor(33));
+        assert_eq!(Some(98), bits.successor(98));
+        assert_eq!(None, bits.successor(99));
+        assert_eq!(None, bits.successor(100));
+    }
+
+    #[test]
+    fn boundaries() {
+        let bits = build(64, &[0, 63]);
+        assert_eq!(Some(0), bits.predecessor(0));
+        assert_eq!(Some(0), bits.predecessor(62));
+        assert_eq!(Some(63), bits.predecessor(63));
+        assert_eq!(Some(0), bits.successor(0));
+        assert_eq!(Some(63), bits.successor(1));
+        assert_eq!(Some(63), bits.successor(63));
+    }
+
+    #[test]
+    fn select_near() {
+        let bits = build(100, &[10, 20, 25, 90]);
+
+        assert_eq!(Some(10), bits.select_near(0));
+        assert_eq!(Some(10), bits.select_near(10));
+        assert_eq!(Some(10), bits.select_near(14));
+        assert_eq!(Some(10), bits.select_near(15));
+        assert_eq!(Some(20), bits.select_near(16));
+        assert_eq!(Some(20), bits.select_near(22));
+        assert_eq!(Some(25), bits.select_near(23));
+        assert_eq!(Some(25), bits.select_near(56));
+        assert_eq!(Some(25), bits.select_near(57));
+        assert_eq!(Some(90), bits.select_near(58));
+        assert_eq!(Some(90), bits.select_near(99));
+        assert_eq!(Some(90), bits.select_near(1000));
+
+        assert_eq!(None, build(50, &[]).select_near(25));
+        assert_eq!(Some(0), build(50, &[0]).select_near(49));
+    }
+
+    #[test]
+    fn empty() {
+        let bits = build(40, &[]);
+        assert_eq!(None, bits.predecessor(39));
+        assert_eq!(None, bits.successor(0));
+
+        let bits = build(0, &[]);
+        assert_eq!(None, bits.predecessor(0));
+        assert_eq!(None, bits.successor(0));
+    }
+}
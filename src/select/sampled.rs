@@ -0,0 +1,377 @@
+use std::cmp;
+
+use num_traits::PrimInt;
+
+use bit_vec::BitVec;
+use int_vec::{IntVec, IntVector};
+use space_usage::SpaceUsage;
+use storage::{Address, BlockType};
+use super::Select1Support;
+
+/// Performs select queries by jumping to a sampled position and then
+/// scanning forward a block at a time.
+///
+/// The position of every `sample_rate`th 1 bit is stored, so a query
+/// scans only the blocks spanned by `sample_rate` consecutive 1s.
+/// Smaller rates make queries faster but take more space.
+///
+/// Construct with `SampledSelect::new`.
+#[derive(Clone, Debug)]
+pub struct SampledSelect<Store> {
+    bit_store: Store,
+    samples: Samples,
+}
+
+impl<Store: BitVec> SampledSelect<Store> {
+    /// Creates a new select structure over `bits`, storing the position
+    /// of every `sample_rate`th 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is 0.
+    pub fn new(bits: Store, sample_rate: usize) -> Self {
+        assert!(sample_rate > 0, "SampledSelect::new: zero sample rate");
+        let samples = Samples::new(&bits, sample_rate);
+        SampledSelect {
+            bit_store: bits,
+            samples,
+        }
+    }
+
+    /// The number of 1s per sample.
+    pub fn sample_rate(&self) -> usize {
+        self.samples.sample_rate as usize
+    }
+
+    /// Borrows a reference to the underlying bit store.
+    pub fn inner(&self) -> &Store {
+        &self.bit_store
+    }
+
+    /// Returns the underlying bit store.
+    pub fn into_inner(self) -> Store {
+        self.bit_store
+    }
+}
+
+// The sampled positions of the 1s of some bits, which are passed back in
+// to each query. `SampledSelect` samples its store directly;
+// `SampledSelect0` samples the complement of its store.
+#[derive(Clone, Debug)]
+pub(super) struct Samples {
+    sample_rate: u64,
+    ones: u64,
+    positions: IntVector<u64>,
+}
+
+impl Samples {
+    // Samples the position of every `sample_rate`th 1 of `bits`, which
+    // must be nonzero.
+    pub(super) fn new<Bits: BitVec + ?Sized>(bits: &Bits, sample_rate: usize) -> Self {
+        let sample_rate = sample_rate as u64;
+        let position_bits = cmp::max(1, (bits.bit_len() + 1).ceil_lg());
+        let mut positions = IntVector::new(position_bits);
+
+        let mut ones = 0;
+        for i in 0 .. bits.block_len() {
+            let block = bits.get_block_masked(i);
+            let count = block.count_ones() as u64;
+
+            // Sample every 1 in this block whose index is a multiple of
+            // the rate.
+            let mut next = positions.len() * sample_rate;
+            while next < ones + count {
+                let offset = block.select_ones((next - ones) as usize)
+                                  .expect("SampledSelect::new: missing 1");
+                positions.push(Bits::Block::mul_nbits(i) + offset as u64);
+                next += sample_rate;
+            }
+
+            ones += count;
+        }
+
+        Samples {
+            sample_rate,
+            ones,
+            positions,
+        }
+    }
+
+    pub(super) fn sample_rate(&self) -> u64 {
+        self.sample_rate
+    }
+
+    // The number of positions sampled.
+    #[cfg(test)]
+    pub(super) fn sample_count(&self) -> u64 {
+        self.positions.len()
+    }
+
+    // The position of the `index`th 1 of `bits`, which must be the bits
+    // sampled.
+    pub(super) fn select<Bits: BitVec + ?Sized>(&self, bits: &Bits, index: u64)
+                                                -> Option<u64> {
+        if index >= self.ones { return None; }
+
+        let sample = index / self.sample_rate;
+        let position = self.positions.get(sample);
+        Some(scan(bits, position, index - sample * self.sample_rate))
+    }
+}
+
+impl SpaceUsage for Samples {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.positions.heap_bytes()
+    }
+}
+
+// The position of the `remaining`th 1 of `bits` at or after `position`,
+// which must exist.
+fn scan<Bits: BitVec + ?Sized>(bits: &Bits, position: u64, remaining: u64) -> u64 {
+    let mut remaining = remaining as usize;
+
+    // Clear the bits before `position` so they aren’t counted.
+    let address = Address::new::<Bits::Block>(position);
+    let mut block_index = address.block_index;
+    let mut block = bits.get_block_masked(block_index)
+                  & !Bits::Block::low_mask(address.bit_offset);
+
+    loop {
+        let count = block.count_ones() as usize;
+        if remaining < count {
+            let offset = block.select_ones(remaining)
+                              .expect("SampledSelect::select1: missing 1");
+            return Bits::Block::mul_nbits(block_index) + offset as u64;
+        }
+
+        remaining -= count;
+        block_index += 1;
+        block = bits.get_block_masked(block_index);
+    }
+}
+
+impl<Store: BitVec> BitVec for SampledSelect<Store> {
+    impl_bit_vec_adapter!(Store::Block, bit_store);
+}
+
+impl<Store: BitVec> Select1Support for SampledSelect<Store> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        self.samples.select(&self.bit_store, index)
+    }
+
+    fn select1_with_hint(&self, index: u64, hint: (u64, u64)) -> Option<u64> {
+        let (prev_index, prev_position) = hint;
+        if index >= self.samples.ones || index < prev_index {
+            return self.select1(index);
+        }
+        if index == prev_index { return Some(prev_position); }
+
+        // Scan from the hint only if it is at least as close as the
+        // sample.
+        let sample_rate = self.samples.sample_rate;
+        if prev_index < index / sample_rate * sample_rate {
+            return self.select1(index);
+        }
+
+        Some(scan(&self.bit_store, prev_position + 1, index - prev_index - 1))
+    }
+
+    fn select1_batch(&self, indices: &[u64]) -> Vec<Option<u64>> {
+        let mut order: Vec<usize> = (0 .. indices.len()).collect();
+        order.sort_by_key(|&i| indices[i]);
+
+        // A cursor at the start of a block, with the number of 1s before
+        // it. In sorted order each answer is at or after the last, so the
+        // cursor only moves forward: by scanning when the next answer is
+        // near, or by jumping to a sample that is further ahead.
+        let mut result = vec![None; indices.len()];
+        let mut cursor: Option<(usize, u64)> = None;
+        let sample_rate = self.samples.sample_rate;
+
+        for i in order {
+            let index = indices[i];
+            if index >= self.samples.ones { break; }
+
+            let sample = index / sample_rate;
+            let address = Address::new::<Store::Block>(self.samples.positions.get(sample));
+            let (mut block_index, mut before) = match cursor {
+                Some((block_index, before))
+                    if block_index >= address.block_index => (block_index, before),
+                _ => {
+                    let block = self.bit_store.get_block_masked(address.block_index)
+                              & Store::Block::low_mask(address.bit_offset);
+                    (address.block_index,
+                     sample * sample_rate - block.count_ones() as u64)
+                }
+            };
+
+            loop {
+                let block = self.bit_store.get_block_masked(block_index);
+                let count = block.count_ones() as u64;
+                if index < before + count {
+                    let offset = block.select_ones((index - before) as usize)
+                                      .expect("SampledSelect::select1_batch: missing 1");
+                    result[i] = Some(Store::Block::mul_nbits(block_index) + offset as u64);
+                    break;
+                }
+
+                before += count;
+                block_index += 1;
+            }
+
+            cursor = Some((block_index, before));
+        }
+
+        result
+    }
+}
+
+impl<Store: SpaceUsage> SpaceUsage for SampledSelect<Store> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.samples.heap_bytes() + self.bit_store.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use internal::test_util::{random_bits, Lcg};
+    use quickcheck::quickcheck;
+
+    fn check<Block: BlockType>(bits: BitVector<Block>, sample_rate: usize) {
+        let ones: Vec<u64> = (0 .. bits.bit_len())
+            .filter(|&i| bits.get_bit(i))
+            .collect();
+        let select = SampledSelect::new(bits, sample_rate);
+
+        for (index, &position) in ones.iter().enumerate() {
+            assert_eq!(Some(position), select.select1(index as u64));
+        }
+        assert_eq!(None, select.select1(ones.len() as u64));
+    }
+
+    #[test]
+    fn random() {
+        for &density in &[1, 16, 128, 250, 256] {
+            for &rate in &[1, 3, 64, 1000] {
+                check(random_bits::<u64>(3000, density, density + 1), rate);
+                check(random_bits::<u8>(500, density, density + 2), rate);
+            }
+        }
+    }
+
+    #[test]
+    fn edges() {
+        check(BitVector::<u32>::new(), 4);
+        check(BitVector::<u32>::with_fill(100, false), 4);
+        check(BitVector::<u32>::with_fill(100, true), 4);
+        check(BitVector::<u32>::with_fill(96, true), 32);
+    }
+
+    fn select1_prop(bits: Vec<bool>, rate: u8) -> bool {
+        let rate = rate as usize % 16 + 1;
+        let mut vec = BitVector::<u8>::new();
+        for &bit in &bits { vec.push_bit(bit); }
+        let select = SampledSelect::new(vec, rate);
+
+        bits.iter().enumerate().filter(|&(_, &bit)| bit).enumerate()
+            .all(|(index, (position, _))| {
+                select.select1(index as u64) == Some(position as u64)
+            })
+    }
+
+    #[test]
+    fn select1_qc() {
+        quickcheck(select1_prop as fn(Vec<bool>, u8) -> bool);
+    }
+
+    #[test]
+    fn space_scales_with_rate() {
+        let overhead = |rate| {
+            let bits = random_bits::<u64>(100_000, 128, 9);
+            let base = bits.heap_bytes();
+            SampledSelect::new(bits, rate).heap_bytes() - base
+        };
+
+        let dense = overhead(8);
+        let sparse = overhead(64);
+        assert!(sparse * 6 < dense);
+        assert!(overhead(1) > dense);
+    }
+
+    #[test]
+    fn select1_batch() {
+        let bits = random_bits::<u32>(20_000, 40, 6);
+        let select = SampledSelect::new(bits, 16);
+        let ones = (0 .. select.bit_len()).filter(|&i| select.get_bit(i)).count() as u64;
+
+        let ascending: Vec<u64> = (0 .. ones + 3).collect();
+        let expected: Vec<Option<u64>> = ascending.iter()
+            .map(|&index| select.select1(index)).collect();
+        assert_eq!(expected, select.select1_batch(&ascending));
+
+        // Shuffled, with duplicates, far-apart and nearby indices, and
+        // indices past the last 1.
+        let mut lcg = Lcg::new(5);
+        let mut shuffled: Vec<u64> = (0 .. 3000)
+            .map(|_| (lcg.next_u64() >> 33) % (ones + 10)).collect();
+        shuffled.extend_from_slice(&[0, 0, ones - 1, ones, 17, 16, 15]);
+        let expected: Vec<Option<u64>> = shuffled.iter()
+            .map(|&index| select.select1(index)).collect();
+        assert_eq!(expected, select.select1_batch(&shuffled));
+
+        assert!(select.select1_batch(&[]).is_empty());
+        let empty = SampledSelect::new(BitVector::<u8>::with_fill(50, false), 4);
+        assert_eq!(vec![None, None], empty.select1_batch(&[1, 0]));
+    }
+
+    fn select1_batch_prop(bits: Vec<bool>, rate: u8, indices: Vec<u8>) -> bool {
+        let mut vec = BitVector::<u8>::new();
+        for &bit in &bits { vec.push_bit(bit); }
+        let select = SampledSelect::new(vec, rate as usize % 8 + 1);
+        let indices: Vec<u64> = indices.into_iter().map(|i| i as u64).collect();
+
+        select.select1_batch(&indices)
+            == indices.iter().map(|&i| select.select1(i)).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn select1_batch_qc() {
+        quickcheck(select1_batch_prop as fn(Vec<bool>, u8, Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn select1_with_hint() {
+        for &(density, rate) in &[(3, 4), (40, 16), (200, 64), (255, 1)] {
+            let bits = random_bits::<u16>(5000, density, density + 9);
+            let select = SampledSelect::new(bits, rate);
+
+            let mut hint = (0, select.select1(0).unwrap());
+            let mut index = 0;
+            while let Some(position) = select.select1(index) {
+                assert_eq!(Some(position), select.select1_with_hint(index, hint));
+                hint = (index, position);
+                index += 1;
+            }
+            assert_eq!(None, select.select1_with_hint(index, hint));
+
+            // Strides past a sample, and a hint from after the index.
+            let hint = (3, select.select1(3).unwrap());
+            assert_eq!(select.select1(200), select.select1_with_hint(200, hint));
+            assert_eq!(select.select1(1), select.select1_with_hint(1, hint));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_rate() {
+        SampledSelect::new(BitVector::<u64>::new(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+use bit_vec::{BitVec, NotBits};
+use space_usage::SpaceUsage;
+use super::Select0Support;
+use super::sampled::Samples;
+
+/// Performs select0 queries by jumping to a sampled position and then
+/// scanning forward a block at a time.
+///
+/// This is the counterpart of [`SampledSelect`](struct.SampledSelect.html)
+/// for 0 bits: it samples and scans a `NotBits` view of the store, whose
+/// 1s are the store’s 0s. Use it on its own when only
+/// select0 is needed, or alongside `SampledSelect` for both.
+///
+/// Construct with `SampledSelect0::new`.
+#[derive(Clone, Debug)]
+pub struct SampledSelect0<Store> {
+    bit_store: Store,
+    samples: Samples,
+}
+
+impl<Store: BitVec> SampledSelect0<Store> {
+    /// Creates a new select0 structure over `bits`, storing the position
+    /// of every `sample_rate`th 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is 0.
+    pub fn new(bits: Store, sample_rate: usize) -> Self {
+        assert!(sample_rate > 0, "SampledSelect0::new: zero sample rate");
+        let samples = Samples::new(&NotBits::new(&bits), sample_rate);
+        SampledSelect0 {
+            bit_store: bits,
+            samples,
+        }
+    }
+
+    /// The number of 0s per sample.
+    pub fn sample_rate(&self) -> usize {
+        self.samples.sample_rate() as usize
+    }
+
+    /// Borrows a reference to the underlying bit store.
+    pub fn inner(&self) -> &Store {
+        &self.bit_store
+    }
+
+    /// Returns the underlying bit store.
+    pub fn into_inner(self) -> Store {
+        self.bit_store
+    }
+}
+
+impl<Store: BitVec> BitVec for SampledSelect0<Store> {
+    impl_bit_vec_adapter!(Store::Block, bit_store);
+}
+
+impl<Store: BitVec> Select0Support for SampledSelect0<Store> {
+    fn select0(&self, index: u64) -> Option<u64> {
+        self.samples.select(&NotBits::new(&self.bit_store), index)
+    }
+}
+
+impl<Store: SpaceUsage> SpaceUsage for SampledSelect0<Store> {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.samples.heap_bytes() + self.bit_store.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use internal::test_util::random_bits;
+    use quickcheck::quickcheck;
+    use storage::BlockType;
+
+    fn check<Block: BlockType>(bits: BitVector<Block>, sample_rate: usize) {
+        let zeros: Vec<u64> = (0 .. bits.bit_len())
+            .filter(|&i| !bits.get_bit(i))
+            .collect();
+        let select = SampledSelect0::new(bits, sample_rate);
+
+        for (index, &position) in zeros.iter().enumerate() {
+            assert_eq!(Some(position), select.select0(index as u64));
+        }
+        assert_eq!(None, select.select0(zeros.len() as u64));
+    }
+
+    #[test]
+    fn random() {
+        for &density in &[0, 6, 128, 240, 255] {
+            for &rate in &[1, 3, 64, 1000] {
+                check(random_bits::<u64>(3000, density, density + 1), rate);
+                check(random_bits::<u8>(500, density, density + 2), rate);
+            }
+        }
+    }
+
+    #[test]
+    fn edges() {
+        check(BitVector::<u32>::new(), 4);
+        check(BitVector::<u32>::with_fill(100, false), 4);
+        check(BitVector::<u32>::with_fill(96, false), 32);
+        check(BitVector::<u32>::with_fill(100, true), 4);
+        check(BitVector::<u32>::with_fill(96, true), 32);
+    }
+
+    #[test]
+    fn all_ones() {
+        let select = SampledSelect0::new(BitVector::<u64>::with_fill(130, true), 2);
+        assert_eq!(None, select.select0(0));
+        assert_eq!(0, select.samples.sample_count());
+    }
+
+    fn select0_prop(bits: Vec<bool>, rate: u8) -> bool {
+        let rate = rate as usize % 16 + 1;
+        let mut vec = BitVector::<u8>::new();
+        for &bit in &bits { vec.push_bit(bit); }
+        let select = SampledSelect0::new(vec, rate);
+
+        let zeros = bits.iter().filter(|&&bit| !bit).count() as u64;
+        select.select0(zeros).is_none()
+            && bits.iter().enumerate().filter(|&(_, &bit)| !bit).enumerate()
+                .all(|(index, (position, _))| {
+                    select.select0(index as u64) == Some(position as u64)
+                })
+    }
+
+    #[test]
+    fn select0_qc() {
+        quickcheck(select0_prop as fn(Vec<bool>, u8) -> bool);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_rate() {
+        SampledSelect0::new(BitVector::<u64>::new(), 0);
+    }
+}
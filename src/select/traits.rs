@@ -1,12 +1,42 @@
 /// Supports selecting for 1 bits.
+///
+/// The trait is object safe, so select structures of different types
+/// can be stored together as `Box<dyn Select1Support>`.
 pub trait Select1Support {
-    /// Returns the position of the `index`th 1 bit.
+    /// Returns the position of the `index`th 1 bit, or `None` if there
+    /// are no more than `index` 1s, as in an empty or all-0 vector.
     fn select1(&self, index: u64) -> Option<u64>;
+
+    /// Returns the position of the 1 bit for each of the given indices,
+    /// in the same order.
+    ///
+    /// The default implementation calls `select1` for each index;
+    /// implementations may answer the queries in sorted order to share
+    /// work between nearby indices, so the input needn’t be sorted.
+    fn select1_batch(&self, indices: &[u64]) -> Vec<Option<u64>> {
+        indices.iter().map(|&index| self.select1(index)).collect()
+    }
+
+    /// Returns the position of the `index`th 1 bit, given the answer to
+    /// an earlier query as `hint = (prev_index, prev_position)`.
+    ///
+    /// When `prev_index <= index` implementations may search forward
+    /// from `prev_position` rather than from scratch, which makes
+    /// sequential selects, as in merging or iteration, cheaper. The hint
+    /// is trusted: if `prev_position` is not the position of the
+    /// `prev_index`th 1 the result is unspecified, though it is never
+    /// unsafe. The result is `None` whenever `select1` would be, whatever
+    /// the hint. The default implementation ignores the hint.
+    fn select1_with_hint(&self, index: u64, hint: (u64, u64)) -> Option<u64> {
+        let _ = hint;
+        self.select1(index)
+    }
 }
 
 /// Supports selecting for 0 bits.
 pub trait Select0Support {
-    /// Returns the position of the `index`th 0 bit.
+    /// Returns the position of the `index`th 0 bit, or `None` if there
+    /// are no more than `index` 0s, as in an empty or all-1 vector.
     fn select0(&self, index: u64) -> Option<u64>;
 }
 
@@ -15,6 +45,54 @@ pub trait SelectSupport {
     /// The type of value that we can search for.
     type Over: Copy;
 
-    /// Returns the position of the `index`th occurrence of `value`.
+    /// Returns the position of the `index`th occurrence of `value`, or
+    /// `None` if there are no more than `index` occurrences.
     fn select(&self, index: u64, value: Self::Over) -> Option<u64>;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVec, BitVector};
+    use broadword::Broadword;
+    use rank::{BitRankSupport, JacobsonRank};
+    use select::{BinSearchSelect, SampledSelect};
+    use sparse::SparseBitVector;
+
+    #[test]
+    fn select1_none_past_count() {
+        let vectors: Vec<BitVector<u64>> = vec![
+            BitVector::new(),
+            BitVector::with_fill(100, false),
+            BitVector::with_fill(100, true),
+            BitVector::every_nth(1000, 9),
+            BitVector::from_fn(777, |i| i % 64 > 60),
+        ];
+
+        for bits in &vectors {
+            let rank = JacobsonRank::new(bits);
+            let ones = rank.total_ones();
+            let structures: Vec<Box<dyn Select1Support>> = vec![
+                Box::new(BinSearchSelect::new(JacobsonRank::new(bits))),
+                Box::new(SampledSelect::new(bits, 5)),
+                Box::new(SparseBitVector::new(bits)),
+            ];
+
+            for select in &structures {
+                for index in 0 .. ones {
+                    let position = select.select1(index).unwrap();
+                    assert!(bits.get_bit(position));
+                    assert_eq!(index + 1, rank.rank1(position));
+                }
+                for &index in &[ones, ones + 1, u64::MAX] {
+                    assert_eq!(None, select.select1(index));
+                }
+            }
+        }
+
+        let word = Broadword(0b1011_0000);
+        assert_eq!(Some(7), word.select1(2));
+        assert_eq!(None, word.select1(3));
+        assert_eq!(None, word.select1(64));
+    }
+}
@@ -65,7 +65,6 @@ pub trait SpaceUsage: Sized {
     /// including any portion of its size that is
     /// included in `stack_bytes`. This is typically for containers
     /// that heap allocate varying amounts of memory.
-    #[inline]
     fn heap_bytes(&self) -> usize;
 }
 
@@ -74,11 +73,13 @@ impl_stack_only_space_usage!(u8);
 impl_stack_only_space_usage!(u16);
 impl_stack_only_space_usage!(u32);
 impl_stack_only_space_usage!(u64);
+impl_stack_only_space_usage!(u128);
 impl_stack_only_space_usage!(usize);
 impl_stack_only_space_usage!(i8);
 impl_stack_only_space_usage!(i16);
 impl_stack_only_space_usage!(i32);
 impl_stack_only_space_usage!(i64);
+impl_stack_only_space_usage!(i128);
 impl_stack_only_space_usage!(isize);
 impl_stack_only_space_usage!(f32);
 impl_stack_only_space_usage!(f64);
@@ -193,6 +194,25 @@ mod test {
                    v.total_bytes());
     }
 
+    #[test]
+    fn vec_u32_size() {
+        let mut v = Vec::<u32>::with_capacity(10);
+        v.push(1);
+        v.push(2);
+        assert_eq!(10, v.capacity());
+        assert_eq!(40, v.heap_bytes());
+        assert_eq!(40 + size_of::<Vec<u32>>(), v.total_bytes());
+    }
+
+    #[test]
+    fn slice_size() {
+        let v = vec![0u32; 10];
+        let s: &[u32] = &v;
+        assert!(<&[u32]>::is_stack_only());
+        assert_eq!(0, s.heap_bytes());
+        assert_eq!(size_of::<&[u32]>(), s.total_bytes());
+    }
+
     #[test]
     fn vec_vec_size() {
         let v1 = Vec::<u64>::with_capacity(8);
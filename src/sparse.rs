@@ -0,0 +1,264 @@
+//! Sparse bit vectors, storing only the positions of their 1s.
+//!
+//! A [`SparseBitVector`](struct.SparseBitVector.html) encodes the sorted
+//! positions of its set bits with [Elias–Fano](../elias_fano/index.html),
+//! taking about `2 + lg(n/m)` bits per 1 for `n` bits with `m` ones. At
+//! low densities this is far smaller than a dense `BitVector`.
+
+use std::cmp;
+
+use num_traits::{One, PrimInt, Zero};
+
+use bit_vec::BitVec;
+use elias_fano::EliasFano;
+use rank::{BitRankSupport, RankSupport};
+use select::{Select0Support, Select1Support};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A bit vector stored as the Elias–Fano encoded positions of its 1s,
+/// supporting rank and select.
+///
+/// Construct with `SparseBitVector::new` or
+/// `SparseBitVector::from_positions`.
+#[derive(Clone, Debug)]
+pub struct SparseBitVector {
+    len: u64,
+    ones: EliasFano,
+}
+
+impl SparseBitVector {
+    /// Encodes the 1s of `bits`.
+    pub fn new<Bits: BitVec + ?Sized>(bits: &Bits) -> Self {
+        let mut positions = Vec::new();
+
+        for i in 0 .. bits.block_len() {
+            let mut block = bits.get_block_masked(i);
+
+            let start = Bits::Block::mul_nbits(i);
+            while !block.is_zero() {
+                positions.push(start + PrimInt::trailing_zeros(block) as u64);
+                block = block & (block - Bits::Block::one());
+            }
+        }
+
+        SparseBitVector {
+            len: bits.bit_len(),
+            ones: EliasFano::new(&positions),
+        }
+    }
+
+    /// Creates a vector of `len` bits with 1s at exactly the given
+    /// positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `positions` is not strictly increasing, or if any
+    /// position is not less than `len`.
+    pub fn from_positions(len: u64, positions: &[u64]) -> Self {
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]),
+                "SparseBitVector::from_positions: positions not increasing");
+        assert!(positions.last().is_none_or(|&last| last < len),
+                "SparseBitVector::from_positions: out of bounds");
+
+        SparseBitVector {
+            len,
+            ones: EliasFano::new(positions),
+        }
+    }
+
+    /// The number of 1s.
+    pub fn count_ones(&self) -> u64 {
+        self.ones.len()
+    }
+}
+
+impl BitVec for SparseBitVector {
+    type Block = u64;
+
+    #[inline]
+    fn bit_len(&self) -> u64 {
+        self.len
+    }
+
+    fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.len, "SparseBitVector::get_bit: out of bounds");
+        self.ones.successor(position) == Some(position)
+    }
+
+    fn get_block(&self, position: usize) -> u64 {
+        let start = u64::mul_nbits(position);
+        assert!(start < self.len, "SparseBitVector::get_block: out of bounds");
+
+        let limit = cmp::min(start + 64, self.len);
+        let mut result = 0;
+        let mut index = self.ones.rank(start);
+
+        while index < self.ones.len() {
+            let one = self.ones.get(index);
+            if one >= limit { break; }
+            result |= 1 << (one - start);
+            index += 1;
+        }
+
+        result
+    }
+}
+
+impl RankSupport for SparseBitVector {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl BitRankSupport for SparseBitVector {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.len, "SparseBitVector::rank1: out of bounds");
+        self.ones.rank(position + 1)
+    }
+}
+
+impl Select1Support for SparseBitVector {
+    fn select1(&self, index: u64) -> Option<u64> {
+        if index < self.ones.len() { Some(self.ones.get(index)) } else { None }
+    }
+}
+
+impl Select0Support for SparseBitVector {
+    fn select0(&self, index: u64) -> Option<u64> {
+        if index >= self.len - self.ones.len() { return None; }
+
+        // There are `get(k) - k` zeros before the `k`th 1, so find the
+        // first 1 with more than `index` zeros before it.
+        let mut low = 0;
+        let mut high = self.ones.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.ones.get(mid) - mid > index {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        Some(index + low)
+    }
+}
+
+impl SpaceUsage for SparseBitVector {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.ones.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use internal::test_util::random_bits;
+    use rank::JacobsonRank;
+    use select::BinSearchSelect;
+    use quickcheck::quickcheck;
+
+    fn check(bits: BitVector<u64>) {
+        let sparse = SparseBitVector::new(&bits);
+        let dense = BinSearchSelect::new(JacobsonRank::new(bits));
+        assert_eq!(dense.bit_len(), sparse.bit_len());
+        assert_eq!(dense.block_len(), sparse.block_len());
+        assert_eq!(dense.total_ones(), sparse.count_ones());
+
+        for i in 0 .. dense.bit_len() {
+            assert_eq!(dense.get_bit(i), sparse.get_bit(i));
+            assert_eq!(dense.rank1(i), sparse.rank1(i));
+        }
+        for i in 0 .. dense.block_len() {
+            assert_eq!(dense.get_block(i), sparse.get_block(i));
+        }
+        for index in 0 .. dense.total_ones() + 1 {
+            assert_eq!(dense.select1(index), sparse.select1(index));
+        }
+        for index in 0 .. dense.total_zeros() + 1 {
+            assert_eq!(dense.select0(index), sparse.select0(index));
+        }
+    }
+
+    #[test]
+    fn one_percent() {
+        check(random_bits(20_000, 3, 5));
+        check(random_bits(1000, 3, 6));
+    }
+
+    #[test]
+    fn densities() {
+        for &density in &[0, 26, 128, 256] {
+            check(random_bits(1000, density, density + 1));
+        }
+    }
+
+    #[test]
+    fn edges() {
+        check(BitVector::new());
+        check(BitVector::with_fill(64, true));
+        check(BitVector::with_fill(65, false));
+    }
+
+    #[test]
+    fn from_positions() {
+        let sparse = SparseBitVector::from_positions(100, &[0, 7, 64, 99]);
+        assert_eq!(100, sparse.bit_len());
+        assert!(sparse.get_bit(7));
+        assert!(!sparse.get_bit(8));
+        assert_eq!(3, sparse.rank1(64));
+        assert_eq!(Some(99), sparse.select1(3));
+        assert_eq!(Some(8), sparse.select0(6));
+        assert_eq!(1 | 1 << 7, sparse.get_block(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_positions_out_of_bounds() {
+        SparseBitVector::from_positions(10, &[3, 10]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_positions_duplicate() {
+        SparseBitVector::from_positions(10, &[3, 3]);
+    }
+
+    #[test]
+    fn space() {
+        let bits = random_bits::<u64>(1_000_000, 3, 11);
+        let sparse = SparseBitVector::new(&bits);
+        assert!(sparse.total_bytes() * 8 < bits.total_bytes());
+    }
+
+    fn rank_select_prop(bits: Vec<bool>) -> bool {
+        let mut vec = BitVector::<u8>::new();
+        for &bit in &bits { vec.push_bit(bit); }
+        let sparse = SparseBitVector::new(&vec);
+
+        let mut ones = 0;
+        bits.iter().enumerate().all(|(i, &bit)| {
+            if bit { ones += 1; }
+            sparse.get_bit(i as u64) == bit
+                && sparse.rank1(i as u64) == ones
+                && (!bit || sparse.select1(ones - 1) == Some(i as u64))
+                && (bit || sparse.select0(i as u64 - ones) == Some(i as u64))
+        })
+    }
+
+    #[test]
+    fn rank_select_qc() {
+        quickcheck(rank_select_prop as fn(Vec<bool>) -> bool);
+    }
+}
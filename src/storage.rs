@@ -8,6 +8,7 @@ use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use num_traits::{One, PrimInt, ToPrimitive};
 
 use bit_vec::{BitVec, BitVecMut};
+use broadword;
 use rank::{BitRankSupport, RankSupport};
 use space_usage::SpaceUsage;
 
@@ -20,14 +21,23 @@ use space_usage::SpaceUsage;
 ///   - a method for computing rank,
 ///   - three arithmetic methods that probably belong elsewhere, and
 ///   - block-based, endian-specified I/O.
+///
+/// Bit-counting operations such as `count_ones`, `count_zeros`,
+/// `leading_zeros` and `trailing_zeros` come from the `PrimInt`
+/// supertrait, so they are available on any `Block: BlockType`.
 pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
                      RankSupport<Over = bool> + SpaceUsage + fmt::Debug {
     // Methods for computing sizes and offsets relative to the block size.
 
+    /// The number of bits in a block, usable in constant expressions.
+    const NBITS: usize = 8 * mem::size_of::<Self>();
+
     /// The number of bits in a block.
+    ///
+    /// The default implementation returns `Self::NBITS`.
     #[inline]
     fn nbits() -> usize {
-        8 * mem::size_of::<Self>()
+        Self::NBITS
     }
 
     /// Returns `index / Self::nbits()`, computed by shifting.
@@ -60,6 +70,16 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         Self::div_nbits(index + (Self::nbits() as u64 - 1))
     }
 
+    /// The number of blocks needed to hold `bits` bits.
+    ///
+    /// This is `ceil_div_nbits` under the name used when sizing buffers
+    /// and tables: 0 for 0 bits, 1 for up to `Self::nbits()` bits, and so
+    /// on.
+    #[inline]
+    fn blocks_for_bits(bits: u64) -> usize {
+        Self::ceil_div_nbits(bits)
+    }
+
     /// Returns `index / Self::nbits()` rounded up, computed by shifting.
     ///
     /// This is intended for converting a bit size into a block
@@ -102,6 +122,13 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         if masked == 0 { Self::nbits() } else { masked }
     }
 
+    /// The mask of the bits of the last block that are in use, given
+    /// `len` bits: `Self::low_mask(Self::last_block_bits(len))`.
+    #[inline]
+    fn last_block_mask(len: u64) -> Self {
+        Self::low_mask(Self::last_block_bits(len))
+    }
+
     /// Log-base-2 of the number of bits in a block.
     #[inline]
     fn lg_nbits() -> usize {
@@ -131,6 +158,30 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         }
     }
 
+    /// The bit mask consisting of `element_bits` ones followed by
+    /// `Self::nbits() - element_bits` zeroes.
+    ///
+    /// # Precondition
+    ///
+    /// `element_bits <= Self::nbits()`
+    #[inline]
+    fn high_mask(element_bits: usize) -> Self {
+        debug_assert!(element_bits <= Self::nbits());
+        !Self::low_mask(Self::nbits() - element_bits)
+    }
+
+    /// The bit mask with bits `start` up to (but not including) `limit`
+    /// set.
+    ///
+    /// # Precondition
+    ///
+    /// `start <= limit <= Self::nbits()`
+    #[inline]
+    fn range_mask(start: usize, limit: usize) -> Self {
+        debug_assert!(start <= limit && limit <= Self::nbits());
+        Self::low_mask(limit) & !Self::low_mask(start)
+    }
+
     /// The bit mask with the `bit_index`th bit set.
     ///
     /// BitVec are index in little-endian style based at 0.
@@ -204,6 +255,90 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         (self & !mask) | (shifted_value & mask)
     }
 
+    /// Finds the bit index of the `k`th (0-based) one bit in the block,
+    /// or `None` if the block has fewer than `k + 1` ones.
+    ///
+    /// The default implementation clears the lowest `k` ones and then
+    /// takes the trailing zeros; implementations may override it with
+    /// something faster.
+    #[inline]
+    fn select_ones(self, k: usize) -> Option<usize> {
+        let mut block = self;
+
+        for _ in 0 .. k {
+            if block == Self::zero() { return None; }
+            block = block & (block - Self::one());
+        }
+
+        if block == Self::zero() {
+            None
+        } else {
+            Some(block.trailing_zeros() as usize)
+        }
+    }
+
+    /// Counts the one bits in a slice of blocks.
+    ///
+    /// The default implementation sums `count_ones` block by block;
+    /// `u64` overrides it with eight independent accumulators, which
+    /// helps when the target has a hardware popcount instruction.
+    #[inline]
+    fn count_ones_slice(blocks: &[Self]) -> u64 {
+        blocks.iter().map(|block| block.count_ones() as u64).sum()
+    }
+
+    /// Reverses the order of the bits in the block, so that bit 0 becomes
+    /// bit `nbits() - 1` and vice versa.
+    ///
+    /// This isn’t called `reverse_bits` because `PrimInt` already has a
+    /// method of that name, and calls in generic code would be ambiguous.
+    #[inline]
+    fn reverse_block(self) -> Self {
+        PrimInt::reverse_bits(self)
+    }
+
+    /// Interleaves the bits of `self` and `other` in Morton order.
+    ///
+    /// Bit `i` of `self` becomes bit `2 * i` of the result and bit `i` of
+    /// `other` becomes bit `2 * i + 1`. The `2 * nbits()` interleaved bits
+    /// are returned as `(low, high)` blocks.
+    fn interleave(self, other: Self) -> (Self, Self) {
+        let half = Self::nbits() / 2;
+        let low = Self::low_mask(half);
+        (spread_half(self & low) | spread_half(other & low) << 1,
+         spread_half(self >> half) | spread_half(other >> half) << 1)
+    }
+
+    /// Splits Morton-interleaved bits back into the two blocks they came
+    /// from, with `self` as the low block and `high` as the high block.
+    ///
+    /// This is the inverse of `interleave`: if `a.interleave(b)` is
+    /// `(low, high)` then `low.deinterleave(high)` is `(a, b)`.
+    fn deinterleave(self, high: Self) -> (Self, Self) {
+        let half = Self::nbits() / 2;
+        (compact_half(self) | compact_half(high) << half,
+         compact_half(self >> 1) | compact_half(high >> 1) << half)
+    }
+
+    /// Converts to the reflected binary Gray code, in which consecutive
+    /// values differ in exactly one bit.
+    #[inline]
+    fn gray_code(self) -> Self {
+        self ^ (self >> 1)
+    }
+
+    /// Converts from the reflected binary Gray code; the inverse of
+    /// `gray_code`.
+    fn gray_decode(self) -> Self {
+        let mut result = self;
+        let mut shift = 1;
+        while shift < Self::nbits() {
+            result = result ^ (result >> shift);
+            shift *= 2;
+        }
+        result
+    }
+
     // Arithmetic methods that probably belong elsewhere.
 
     /// Returns the smallest number `n` such that `2.pow(n) >= self`.
@@ -237,6 +372,36 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         where W: io::Write, T: ByteOrder;
 }
 
+// The mask with the low `period` bits of every `2 * period` set, for
+// `period` a power of two no more than half the block.
+fn alternating_mask<Block: BlockType>(period: usize) -> Block {
+    Block::max_value() / ((Block::one() << period) + Block::one())
+}
+
+// Moves bit `i` of `block` to bit `2 * i`, for `block` with only its low
+// half set, by halving the distance between groups of bits at each step.
+fn spread_half<Block: BlockType>(block: Block) -> Block {
+    let mut result = block;
+    let mut shift = Block::nbits() / 4;
+    while shift > 0 {
+        result = (result | result << shift) & alternating_mask(shift);
+        shift /= 2;
+    }
+    result
+}
+
+// Moves bit `2 * i` of `block` to bit `i`, dropping the odd bits; the
+// inverse of `spread_half`.
+fn compact_half<Block: BlockType>(block: Block) -> Block {
+    let mut result = block & alternating_mask(1);
+    let mut shift = 1;
+    while shift < Block::nbits() / 2 {
+        result = (result | result >> shift) & alternating_mask(2 * shift);
+        shift *= 2;
+    }
+    result
+}
+
 macro_rules! fn_low_mask {
     ( $ty:ident )
         =>
@@ -275,6 +440,12 @@ impl BlockType for u8 {
 macro_rules! impl_block_type {
     ($ty:ident, $read:ident, $write:ident)
         =>
+    {
+        impl_block_type!($ty, $read, $write, {});
+    };
+
+    ($ty:ident, $read:ident, $write:ident, { $( $extra:tt )* })
+        =>
     {
         impl BlockType for $ty {
             fn read_block<R, T>(source: &mut R) -> io::Result<Self>
@@ -290,13 +461,49 @@ macro_rules! impl_block_type {
             }
 
             fn_low_mask!($ty);
+
+            $( $extra )*
         }
     }
 }
 
 impl_block_type!(u16, read_u16, write_u16);
 impl_block_type!(u32, read_u32, write_u32);
-impl_block_type!(u64, read_u64, write_u64);
+impl_block_type!(u64, read_u64, write_u64, {
+    #[inline]
+    fn select_ones(self, k: usize) -> Option<usize> {
+        if k >= 64 { return None; }
+        broadword::select1(k, self)
+    }
+
+    fn count_ones_slice(blocks: &[u64]) -> u64 {
+        let mut sums = [0u64; 8];
+        let chunks = blocks.chunks_exact(8);
+        let remainder = chunks.remainder();
+
+        for chunk in chunks {
+            for (sum, block) in sums.iter_mut().zip(chunk) {
+                *sum += block.count_ones() as u64;
+            }
+        }
+
+        sums.iter().sum::<u64>()
+            + remainder.iter().map(|block| block.count_ones() as u64).sum::<u64>()
+    }
+});
+
+impl_block_type!(u128, read_u128, write_u128, {
+    #[inline]
+    fn select_ones(self, k: usize) -> Option<usize> {
+        let low = self as u64;
+        let low_ones = low.count_ones() as usize;
+        if k < low_ones {
+            broadword::select1(k, low)
+        } else {
+            ((self >> 64) as u64).select_ones(k - low_ones).map(|i| i + 64)
+        }
+    }
+});
 
 impl BlockType for usize {
     #[cfg(target_pointer_width = "64")]
@@ -370,13 +577,68 @@ impl Address {
 #[cfg(test)]
 mod test {
     use super::*;
+    use internal::test_util::Lcg;
     use quickcheck::{quickcheck, TestResult};
 
+    #[test]
+    fn nbits_const() {
+        const TWO_WORDS: usize = 2 * <u64 as BlockType>::NBITS;
+        let table = [0u8; <u32 as BlockType>::NBITS];
+
+        assert_eq!(128, TWO_WORDS);
+        assert_eq!(32, table.len());
+        assert_eq!(8, u8::NBITS);
+        assert_eq!(16, u16::NBITS);
+        assert_eq!(64, u64::NBITS);
+        assert_eq!(128, u128::NBITS);
+        assert_eq!(7, u128::lg_nbits());
+        assert_eq!(u32::nbits(), u32::NBITS);
+        assert_eq!(usize::nbits(), usize::NBITS);
+    }
+
     #[test]
     fn low_mask() {
         assert_eq!(0b00011111, u8::low_mask(5));
         assert_eq!(0b0011111111111111, u16::low_mask(14));
         assert_eq!(0b1111111111111111, u16::low_mask(16));
+        assert_eq!(!0u128, u128::low_mask(128));
+        assert_eq!(!0u128 >> 1, u128::low_mask(127));
+        assert_eq!(0, u128::low_mask(0));
+    }
+
+    fn reference_mask<Block: BlockType>(start: usize, limit: usize) -> Block {
+        let mut result = Block::zero();
+        for i in start .. limit {
+            result = result | Block::one() << i;
+        }
+        result
+    }
+
+    fn masks_exhaustive<Block: BlockType>() {
+        let n = Block::nbits();
+        for k in 0 .. n + 1 {
+            assert_eq!(reference_mask::<Block>(0, k), Block::low_mask(k));
+            assert_eq!(reference_mask::<Block>(n - k, n), Block::high_mask(k));
+            for i in 0 .. k + 1 {
+                assert_eq!(reference_mask::<Block>(i, k),
+                           Block::range_mask(i, k));
+            }
+        }
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(0b11100000, u8::high_mask(3));
+        assert_eq!(0b00111100, u8::range_mask(2, 6));
+        assert_eq!(0, u8::range_mask(4, 4));
+
+        masks_exhaustive::<u8>();
+        masks_exhaustive::<u32>();
+        masks_exhaustive::<u128>();
+
+        assert_eq!(!0u128, u128::high_mask(128));
+        assert_eq!(1 << 127, u128::high_mask(1));
+        assert_eq!(!0u128, u128::range_mask(0, 128));
     }
 
     #[test]
@@ -440,6 +702,216 @@ mod test {
         assert_eq!(0b10101010, 0b10101010u8.with_bit(0, false));
     }
 
+    fn bit_counts<Block: BlockType>(block: Block) -> (u32, u32, u32, u32) {
+        (block.count_ones(), block.count_zeros(),
+         block.leading_zeros(), block.trailing_zeros())
+    }
+
+    #[test]
+    fn bit_counts_u8() {
+        assert_eq!((0, 8, 8, 8), bit_counts(0u8));
+        assert_eq!((8, 0, 0, 0), bit_counts(!0u8));
+        assert_eq!((3, 5, 1, 2), bit_counts(0b01001100u8));
+    }
+
+    #[test]
+    fn bit_counts_u32() {
+        assert_eq!((0, 32, 32, 32), bit_counts(0u32));
+        assert_eq!((32, 0, 0, 0), bit_counts(!0u32));
+        assert_eq!((2, 30, 0, 4), bit_counts(0x8000_0010u32));
+    }
+
+    #[test]
+    fn bit_counts_u64() {
+        assert_eq!((0, 64, 64, 64), bit_counts(0u64));
+        assert_eq!((64, 0, 0, 0), bit_counts(!0u64));
+        assert_eq!((1, 63, 23, 40), bit_counts(1u64 << 40));
+    }
+
+    #[test]
+    fn bit_counts_u128() {
+        assert_eq!((0, 128, 128, 128), bit_counts(0u128));
+        assert_eq!((128, 0, 0, 0), bit_counts(!0u128));
+        assert_eq!((2, 126, 27, 3), bit_counts(1u128 << 100 | 1 << 3));
+    }
+
+    #[test]
+    fn u128_bits() {
+        let block = 1u128 << 127 | 1 << 64 | 1;
+        assert!(block.get_bit(127));
+        assert!(block.get_bit(64));
+        assert!(!block.get_bit(63));
+        assert_eq!(0, block.with_bit(127, false).with_bit(64, false).with_bit(0, false));
+        assert_eq!(0b100, block.get_bits(62, 3));
+        assert_eq!(1, block.get_bits(127, 1));
+        assert_eq!(!0u128, 0u128.with_bits(0, 128, !0));
+        assert_eq!(0xFFF, 1u128.with_bits(100, 28, 0xFFF).get_bits(100, 28));
+        assert_eq!(1, 1u128.reverse_block() >> 127);
+
+        let mut buffer = Vec::new();
+        block.write_block::<_, byteorder::LittleEndian>(&mut buffer).unwrap();
+        assert_eq!(16, buffer.len());
+        assert_eq!(block,
+                   u128::read_block::<_, byteorder::LittleEndian>(&mut &*buffer).unwrap());
+    }
+
+    #[test]
+    fn select_ones_u128() {
+        let block = 1u128 << 3 | 1 << 64 | 1 << 127;
+        assert_eq!(Some(3), block.select_ones(0));
+        assert_eq!(Some(64), block.select_ones(1));
+        assert_eq!(Some(127), block.select_ones(2));
+        assert_eq!(None, block.select_ones(3));
+
+        for k in 0 .. 128 {
+            assert_eq!(Some(k), (!0u128).select_ones(k));
+        }
+        assert_eq!(None, (!0u128).select_ones(128));
+        assert_eq!(None, 0u128.select_ones(0));
+    }
+
+    fn count_ones_slice_prop(blocks: Vec<u64>) -> bool {
+        let expected: u64 = blocks.iter().map(|b| b.count_ones() as u64).sum();
+        let narrow: Vec<u32> = blocks.iter().map(|&b| b as u32).collect();
+        let narrow_expected: u64 =
+            narrow.iter().map(|b| b.count_ones() as u64).sum();
+
+        u64::count_ones_slice(&blocks) == expected
+            && u32::count_ones_slice(&narrow) == narrow_expected
+    }
+
+    #[test]
+    fn count_ones_slice() {
+        assert_eq!(0, u64::count_ones_slice(&[]));
+        assert_eq!(64 * 17, u64::count_ones_slice(&[!0; 17]));
+        assert_eq!(3 * 8, u8::count_ones_slice(&[0b10101000; 8]));
+
+        for len in 0 .. 20 {
+            let blocks: Vec<u64> = (0 .. len)
+                .map(|i| (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+                .collect();
+            assert!(count_ones_slice_prop(blocks));
+        }
+
+        quickcheck(count_ones_slice_prop as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn reverse_block() {
+        assert_eq!(0x80, 1u8.reverse_block());
+        assert_eq!(0b0110_0001, 0b1000_0110u8.reverse_block());
+        assert_eq!(1 << 63, 1u64.reverse_block());
+        assert_eq!(0x0000_FFFF, 0xFFFF_0000u32.reverse_block());
+        assert_eq!(!0usize, (!0usize).reverse_block());
+
+        for i in 0 .. 16 {
+            assert_eq!(1 << (15 - i), (1u16 << i).reverse_block());
+        }
+    }
+
+    // Interleaves bit by bit.
+    fn reference_interleave<Block: BlockType>(a: Block, b: Block) -> (Block, Block) {
+        let n = Block::nbits();
+        let mut result = (Block::zero(), Block::zero());
+        for i in 0 .. 2 * n {
+            let source = if i % 2 == 0 { a } else { b };
+            if source.get_bit(i / 2) {
+                if i < n { result.0 = result.0.with_bit(i, true); }
+                else { result.1 = result.1.with_bit(i - n, true); }
+            }
+        }
+        result
+    }
+
+    fn interleave_prop<Block: BlockType>(a: Block, b: Block) -> bool {
+        let (low, high) = a.interleave(b);
+        (low, high) == reference_interleave(a, b)
+            && low.deinterleave(high) == (a, b)
+    }
+
+    #[test]
+    fn interleave() {
+        assert_eq!((0b0101, 0), 0b11u8.interleave(0));
+        assert_eq!((0b1010, 0), 0u8.interleave(0b11));
+        assert_eq!((0b1110, 0), 0b10u8.interleave(0b11));
+        assert_eq!((0, 0x55), 0xF0u8.interleave(0));
+        assert_eq!((0xFF, 0xFF), 0xFFu8.interleave(0xFF));
+        assert_eq!((0x5555_5555, 0), 0x0000_FFFFu32.interleave(0));
+        assert_eq!((1, 1 << 63), 1u64.interleave(1 << 63));
+        assert_eq!((0, 1 << 127), 0u128.interleave(1 << 127));
+
+        for a in 0 ..= u8::MAX {
+            for b in 0 ..= u8::MAX {
+                assert!(interleave_prop(a, b));
+            }
+        }
+
+        let mut lcg = Lcg::new(11);
+        for _ in 0 .. 100 {
+            let (a, b) = (lcg.next_u64(), lcg.next_u64());
+            assert!(interleave_prop((a as u128) << 64 | b as u128, b as u128 * a as u128));
+        }
+
+        quickcheck(interleave_prop::<u16> as fn(u16, u16) -> bool);
+        quickcheck(interleave_prop::<u32> as fn(u32, u32) -> bool);
+        quickcheck(interleave_prop::<u64> as fn(u64, u64) -> bool);
+        quickcheck(interleave_prop::<usize> as fn(usize, usize) -> bool);
+    }
+
+    #[test]
+    fn gray_code() {
+        let codes: Vec<u8> = (0 .. 8u8).map(BlockType::gray_code).collect();
+        assert_eq!(vec![0, 1, 3, 2, 6, 7, 5, 4], codes);
+
+        for i in 0 ..= u16::MAX {
+            assert_eq!(i, i.gray_code().gray_decode());
+            if i > 0 {
+                assert_eq!(1, (i.gray_code() ^ (i - 1).gray_code()).count_ones());
+            }
+        }
+        assert_eq!(u64::MAX, u64::MAX.gray_code().gray_decode());
+        assert_eq!(1 << 127, (1u128 << 127).gray_decode().gray_code());
+    }
+
+    fn brute_force_select(block: u64, k: usize) -> Option<usize> {
+        (0 .. 64).filter(|&i| block.get_bit(i)).nth(k)
+    }
+
+    #[test]
+    fn select_ones_sparse() {
+        let block = (1u64 << 3) | (1 << 40) | (1 << 63);
+        assert_eq!(Some(3), block.select_ones(0));
+        assert_eq!(Some(40), block.select_ones(1));
+        assert_eq!(Some(63), block.select_ones(2));
+        assert_eq!(None, block.select_ones(3));
+        assert_eq!(None, 0u64.select_ones(0));
+        assert_eq!(None, block.select_ones(64));
+        assert_eq!(None, block.select_ones(!0));
+    }
+
+    #[test]
+    fn select_ones_dense() {
+        for k in 0 .. 64 {
+            assert_eq!(Some(k), (!0u64).select_ones(k));
+        }
+        assert_eq!(None, (!0u64).select_ones(64));
+        assert_eq!(Some(6), 0b11000000u8.select_ones(0));
+        assert_eq!(Some(7), 0b11000000u8.select_ones(1));
+        assert_eq!(None, 0b11000000u8.select_ones(2));
+    }
+
+    #[test]
+    fn select_ones_qc() {
+        fn prop(block: u64, k: u8) -> bool {
+            let k = k as usize % 66;
+            block.select_ones(k) == brute_force_select(block, k)
+                && (block as u32).select_ones(k)
+                       == brute_force_select(block as u32 as u64, k)
+        }
+
+        quickcheck(prop as fn(u64, u8) -> bool);
+    }
+
     #[test]
     fn floor_lg() {
         assert_eq!(0, 1u32.floor_lg());
@@ -483,6 +955,20 @@ mod test {
         quickcheck(prop as fn(u64) -> TestResult);
     }
 
+    #[test]
+    fn blocks_for_bits() {
+        assert_eq!(0, u8::blocks_for_bits(0));
+        assert_eq!(1, u8::blocks_for_bits(1));
+        assert_eq!(1, u8::blocks_for_bits(8));
+        assert_eq!(2, u8::blocks_for_bits(9));
+        assert_eq!(1, u64::blocks_for_bits(64));
+        assert_eq!(2, u64::blocks_for_bits(65));
+        assert_eq!(3, u32::blocks_for_bits(96));
+        assert_eq!(4, u32::blocks_for_bits(97));
+        assert_eq!(1, u128::blocks_for_bits(128));
+        assert_eq!(2, u128::blocks_for_bits(129));
+    }
+
     #[test]
     fn ceil_div() {
         assert_eq!(6, 12u32.ceil_div(2));
@@ -0,0 +1,197 @@
+//! Variable-byte encoded integer sequences.
+//!
+//! A [`VByteVector`](struct.VByteVector.html) stores each `u64` in as
+//! few bytes as it needs, seven bits per byte, low bits first, with the
+//! high bit of each byte set if more bytes follow. Small values thus take
+//! one byte each. A bit vector with a 1 at the first byte of each value
+//! supports select, so any value can be found without decoding those
+//! before it.
+
+use bit_vec::{BitVecPush, BitVector};
+use rank::{BitRankSupport, JacobsonRank};
+use select::{BinSearchSelect, Select1Support};
+use space_usage::SpaceUsage;
+
+/// A variable-byte encoded sequence of `u64`s, supporting random access
+/// and sequential decoding.
+///
+/// Construct with `VByteVector::new`.
+#[derive(Clone, Debug)]
+pub struct VByteVector {
+    bytes: Vec<u8>,
+    starts: BinSearchSelect<JacobsonRank<BitVector<u64>>>,
+}
+
+impl VByteVector {
+    /// Encodes a sequence of values.
+    pub fn new(values: &[u64]) -> Self {
+        let mut bytes = Vec::with_capacity(values.len());
+        let mut starts = BitVector::with_capacity(values.len() as u64);
+
+        for &value in values {
+            let mut value = value;
+            starts.push_bit(true);
+            while value >= 0x80 {
+                bytes.push(value as u8 | 0x80);
+                starts.push_bit(false);
+                value >>= 7;
+            }
+            bytes.push(value as u8);
+        }
+
+        bytes.shrink_to_fit();
+
+        VByteVector {
+            bytes,
+            starts: BinSearchSelect::new(JacobsonRank::new(starts)),
+        }
+    }
+
+    /// The number of values in the sequence.
+    pub fn len(&self) -> u64 {
+        self.starts.total_ones()
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The number of bytes used by the encoded values.
+    pub fn byte_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the `index`th value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= self.len()`.
+    pub fn get(&self, index: u64) -> u64 {
+        let start = self.starts.select1(index)
+                        .expect("VByteVector::get: out of bounds");
+        decode(&self.bytes, start as usize).0
+    }
+
+    /// Iterates over the values in order, decoding sequentially.
+    pub fn iter(&self) -> VByteIter<'_> {
+        VByteIter {
+            bytes: &self.bytes,
+            offset: 0,
+        }
+    }
+}
+
+// Decodes the value starting at `offset`, returning it and the offset
+// of the next value.
+fn decode(bytes: &[u8], offset: usize) -> (u64, usize) {
+    let mut result = 0;
+    let mut shift = 0;
+    let mut offset = offset;
+
+    loop {
+        let byte = bytes[offset];
+        offset += 1;
+        result |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 { return (result, offset); }
+        shift += 7;
+    }
+}
+
+/// An iterator over the values of a `VByteVector`.
+#[derive(Clone, Debug)]
+pub struct VByteIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for VByteIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.offset == self.bytes.len() { return None; }
+
+        let (value, next) = decode(self.bytes, self.offset);
+        self.offset = next;
+        Some(value)
+    }
+}
+
+impl<'a> IntoIterator for &'a VByteVector {
+    type Item = u64;
+    type IntoIter = VByteIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl SpaceUsage for VByteVector {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.bytes.heap_bytes() + self.starts.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn check(values: &[u64]) {
+        let vbyte = VByteVector::new(values);
+        assert_eq!(values.len() as u64, vbyte.len());
+        assert_eq!(values.is_empty(), vbyte.is_empty());
+        assert_eq!(values.to_vec(), vbyte.iter().collect::<Vec<_>>());
+
+        for (i, &value) in values.iter().enumerate() {
+            assert_eq!(value, vbyte.get(i as u64));
+        }
+    }
+
+    #[test]
+    fn mixed() {
+        let values = [0, 1, 127, 128, 300, 16_383, 16_384, 5,
+                      1 << 35, u64::MAX, 0, 42];
+        check(&values);
+
+        let vbyte = VByteVector::new(&values);
+        assert_eq!(u64::MAX, vbyte.get(9));
+        assert_eq!(42, vbyte.get(11));
+        // 1 + 1 + 1 + 2 + 2 + 2 + 3 + 1 + 6 + 10 + 1 + 1
+        assert_eq!(31, vbyte.byte_len());
+    }
+
+    #[test]
+    fn empty() {
+        check(&[]);
+    }
+
+    #[test]
+    fn small_values_compress() {
+        let values: Vec<u64> = (0 .. 10_000).map(|i| i % 100).collect();
+        let vbyte = VByteVector::new(&values);
+        assert_eq!(10_000, vbyte.byte_len());
+        assert!(vbyte.total_bytes() < values.len() * 2);
+        check(&values);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_oob() {
+        VByteVector::new(&[1, 2, 3]).get(3);
+    }
+
+    fn round_trip_prop(values: Vec<u64>) -> bool {
+        let vbyte = VByteVector::new(&values);
+        vbyte.iter().eq(values.iter().cloned())
+            && values.iter().enumerate().all(|(i, &value)| vbyte.get(i as u64) == value)
+    }
+
+    #[test]
+    fn round_trip_qc() {
+        quickcheck(round_trip_prop as fn(Vec<u64>) -> bool);
+    }
+}
@@ -0,0 +1,276 @@
+//! Wavelet trees over byte sequences.
+//!
+//! A [`WaveletTree`](struct.WaveletTree.html) stores a sequence of
+//! symbols as one bit vector per bit of the symbols, most significant
+//! first. Level 0 holds the top bit of each symbol; each later level
+//! holds the next bit, with the sequence stably partitioned so that every
+//! node of the tree occupies a contiguous range. Access, rank and select
+//! each take one rank or select query per level.
+
+use bit_vec::{BitVec, BitVecPush, BitVector};
+use rank::{BitRankSupport, JacobsonRank, RankSupport};
+use select::{BinSearchSelect, Select0Support, Select1Support, SelectSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+type Level = BinSearchSelect<JacobsonRank<BitVector<u64>>>;
+
+/// A wavelet tree over a sequence of bytes, supporting access, rank and
+/// select.
+///
+/// Construct with `WaveletTree::new`.
+#[derive(Clone, Debug)]
+pub struct WaveletTree {
+    len: u64,
+    levels: Vec<Level>,
+}
+
+impl WaveletTree {
+    /// Builds a wavelet tree for the given sequence.
+    ///
+    /// The tree has one level per bit needed for the largest symbol, so
+    /// small alphabets take proportionally less space.
+    pub fn new(symbols: &[u8]) -> Self {
+        let max = symbols.iter().cloned().max().unwrap_or(0);
+        let depth = (max as u64 + 1).ceil_lg();
+
+        let mut current = symbols.to_vec();
+        let mut levels = Vec::with_capacity(depth);
+
+        for level in 0 .. depth {
+            let shift = depth - 1 - level;
+
+            let mut bits = BitVector::with_capacity(symbols.len() as u64);
+            for &symbol in &current {
+                bits.push_bit(symbol >> shift & 1 == 1);
+            }
+            levels.push(BinSearchSelect::new(JacobsonRank::new(bits)));
+
+            // Sorting by the prefix so far is a stable partition of each
+            // node into its children.
+            current.sort_by_key(|&symbol| symbol >> shift);
+        }
+
+        WaveletTree {
+            len: symbols.len() as u64,
+            levels,
+        }
+    }
+
+    /// The number of symbols in the sequence.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the sequence empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the symbol at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is out of bounds.
+    pub fn access(&self, position: u64) -> u8 {
+        assert!(position < self.len, "WaveletTree::access: out of bounds");
+
+        let mut symbol = 0;
+        let mut start = 0;
+        let mut end = self.len;
+        let mut position = position;
+
+        for level in &self.levels {
+            let zeros = level.rank0_range(start .. end);
+            let bit = level.get_bit(position);
+            if bit {
+                position = start + zeros + level.rank_range(start .. position);
+                start += zeros;
+            } else {
+                position = start + level.rank0_range(start .. position);
+                end = start + zeros;
+            }
+            symbol = symbol << 1 | bit as u8;
+        }
+
+        symbol
+    }
+
+    // The number of occurrences of `symbol` before `limit`.
+    fn count(&self, symbol: u8, limit: u64) -> u64 {
+        if self.too_wide(symbol) { return 0; }
+
+        let mut start = 0;
+        let mut end = self.len;
+        let mut limit = limit;
+
+        for (i, level) in self.levels.iter().enumerate() {
+            let zeros = level.rank0_range(start .. end);
+            if self.bit_of(symbol, i) {
+                limit = start + zeros + level.rank_range(start .. limit);
+                start += zeros;
+            } else {
+                limit = start + level.rank0_range(start .. limit);
+                end = start + zeros;
+            }
+        }
+
+        limit - start
+    }
+
+    fn too_wide(&self, symbol: u8) -> bool {
+        self.levels.len() < 8 && symbol >> self.levels.len() != 0
+    }
+
+    fn bit_of(&self, symbol: u8, level: usize) -> bool {
+        symbol >> (self.levels.len() - 1 - level) & 1 == 1
+    }
+}
+
+impl RankSupport for WaveletTree {
+    type Over = u8;
+
+    fn rank(&self, position: u64, value: u8) -> u64 {
+        assert!(position < self.len, "WaveletTree::rank: out of bounds");
+        self.count(value, position + 1)
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl SelectSupport for WaveletTree {
+    type Over = u8;
+
+    fn select(&self, index: u64, value: u8) -> Option<u64> {
+        if self.too_wide(value) { return None; }
+
+        // Find the range of the leaf for `value`, remembering where each
+        // node on the way starts.
+        let mut starts = Vec::with_capacity(self.levels.len());
+        let mut start = 0;
+        let mut end = self.len;
+
+        for (i, level) in self.levels.iter().enumerate() {
+            starts.push(start);
+            let zeros = level.rank0_range(start .. end);
+            if self.bit_of(value, i) {
+                start += zeros;
+            } else {
+                end = start + zeros;
+            }
+        }
+
+        if index >= end - start { return None; }
+
+        // Map the position back up, one level at a time.
+        let mut position = start + index;
+        for (i, level) in self.levels.iter().enumerate().rev() {
+            let node_start = starts[i];
+            let child_start = if i + 1 < starts.len() { starts[i + 1] } else { start };
+            let within = position - child_start;
+
+            position = if self.bit_of(value, i) {
+                let before = level.rank_range(0 .. node_start);
+                level.select1(before + within)?
+            } else {
+                let before = node_start - level.rank_range(0 .. node_start);
+                level.select0(before + within)?
+            };
+        }
+
+        Some(position)
+    }
+}
+
+impl SpaceUsage for WaveletTree {
+    #[inline]
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.levels.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn naive_rank(symbols: &[u8], position: usize, value: u8) -> u64 {
+        symbols[.. position + 1].iter().filter(|&&s| s == value).count() as u64
+    }
+
+    fn naive_select(symbols: &[u8], index: u64, value: u8) -> Option<u64> {
+        symbols.iter().enumerate()
+            .filter(|&(_, &s)| s == value)
+            .nth(index as usize)
+            .map(|(i, _)| i as u64)
+    }
+
+    fn check(symbols: &[u8]) {
+        let tree = WaveletTree::new(symbols);
+        assert_eq!(symbols.len() as u64, tree.len());
+
+        for (i, &symbol) in symbols.iter().enumerate() {
+            assert_eq!(symbol, tree.access(i as u64));
+        }
+
+        let mut alphabet: Vec<u8> = symbols.to_vec();
+        alphabet.extend_from_slice(&[0, 1, 255]);
+        alphabet.sort();
+        alphabet.dedup();
+
+        for &value in &alphabet {
+            for i in 0 .. symbols.len() {
+                assert_eq!(naive_rank(symbols, i, value), tree.rank(i as u64, value));
+            }
+            for index in 0 .. symbols.len() as u64 + 1 {
+                assert_eq!(naive_select(symbols, index, value),
+                           tree.select(index, value));
+            }
+        }
+    }
+
+    #[test]
+    fn abracadabra() {
+        let text = b"abracadabra";
+        let tree = WaveletTree::new(text);
+
+        assert_eq!(b'c', tree.access(4));
+        assert_eq!(5, tree.rank(10, b'a'));
+        assert_eq!(2, tree.rank(8, b'b'));
+        assert_eq!(0, tree.rank(3, b'c'));
+        assert_eq!(Some(7), tree.select(3, b'a'));
+        assert_eq!(Some(9), tree.select(1, b'r'));
+        assert_eq!(None, tree.select(1, b'd'));
+        assert_eq!(None, tree.select(0, b'z'));
+
+        check(text);
+    }
+
+    #[test]
+    fn alphabets() {
+        check(b"");
+        check(&[0, 0, 0]);
+        check(&[1, 0, 1, 1, 0]);
+        check(&[255, 0, 128, 127, 255, 1]);
+        check(b"mississippi river banks");
+    }
+
+    fn access_rank_prop(symbols: Vec<u8>) -> bool {
+        let tree = WaveletTree::new(&symbols);
+        symbols.iter().enumerate().all(|(i, &symbol)| {
+            tree.access(i as u64) == symbol
+                && tree.rank(i as u64, symbol) == naive_rank(&symbols, i, symbol)
+                && tree.select(tree.rank(i as u64, symbol) - 1, symbol)
+                       == Some(i as u64)
+        })
+    }
+
+    #[test]
+    fn access_rank_qc() {
+        quickcheck(access_rank_prop as fn(Vec<u8>) -> bool);
+    }
+}